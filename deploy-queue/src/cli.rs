@@ -1,3 +1,5 @@
+use std::{path::PathBuf, time::Duration};
+
 use clap::{Parser, Subcommand, ValueEnum};
 
 /// Environment enum for deployment targets
@@ -22,6 +24,36 @@ impl AsRef<str> for Environment {
     }
 }
 
+/// Output format for commands that print a result to stdout (`Info`,
+/// `Outliers`). `info!` logging always goes to stderr regardless of this, so
+/// `--format json` leaves stdout carrying only the JSON document - safe for
+/// a GitHub Actions step to pipe into `fromJSON`.
+#[derive(Clone, Copy, Default, Debug, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// How `Retention` disposes of old deployment rows once their runs are
+/// done - see `handler::retention::RetentionMode`, which this mirrors.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum RetentionMode {
+    KeepForever,
+    RemoveFinished,
+    RemoveAll,
+}
+
+/// Which deployments `Prune` is allowed to delete, each against its own
+/// `--after` threshold - see `handler::retention::RetentionPolicy`, which
+/// this mirrors.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum PrunePolicy {
+    KeepAll,
+    RemoveFinishedAfter,
+    RemoveCancelledAfter,
+}
+
 /// CLI for starting and finishing and canceling deployments.
 /// This CLI is used by the Deploy Queue GitHub Action.
 #[derive(Parser)]
@@ -31,6 +63,15 @@ pub struct Cli {
     #[arg(long, global = true)]
     pub skip_migrations: bool,
 
+    /// Path to a JSON file configuring notifiers (Slack, GitHub commit
+    /// status, generic webhooks) to notify on deployment state transitions
+    #[arg(long, global = true)]
+    pub notifier_config: Option<PathBuf>,
+
+    /// Output format for commands that print a result to stdout
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+
     #[command(subcommand)]
     pub mode: Mode,
 }
@@ -66,6 +107,17 @@ pub enum Mode {
         #[arg(long)]
         /// Concurrency key for this deployment
         concurrency_key: Option<String>,
+        #[arg(long)]
+        /// MutexBot resource to hold exclusively for the duration of this
+        /// deployment's `running` window
+        reserve_resource: Option<String>,
+        #[arg(long)]
+        /// MutexBot isolation channel for `reserve_resource`
+        isolation_channel: Option<String>,
+        #[arg(long, default_value_t = 0)]
+        /// How many times to automatically re-enqueue this deployment, with
+        /// exponential backoff, if it's cancelled for a stale heartbeat
+        max_retries: i32,
     },
     /// Finish deployment for a component
     Finish {
@@ -85,8 +137,96 @@ pub enum Mode {
         /// Deployment ID to get info for
         deployment_id: i64,
     },
+    /// Open a new run against a deployment whose latest run has finished,
+    /// been cancelled, or expired
+    Retry {
+        /// Deployment ID to retry
+        deployment_id: i64,
+    },
     /// List deployments that are taking substantially longer than expected
     Outliers,
+    /// Stream deployment state transitions (queued/started/finished/
+    /// cancelled) live as they happen, instead of polling `Info`/`Outliers`
+    /// on an interval
+    Watch,
+    /// Report queue-health metrics (p50/p95 queue wait and deploy duration
+    /// per component and location) over a trailing window
+    Metrics {
+        #[arg(long, value_parser = humantime::parse_duration, default_value = "7d")]
+        /// How far back to aggregate metric samples
+        since: Duration,
+    },
+    /// Report a per-component rollup (state counts, duration mean/median/
+    /// stddev, outlier count) over a trailing window - unlike `Metrics`
+    /// (which aggregates `deployment_metrics` samples recorded along the
+    /// way), this is computed directly from the `deployments` fetched for
+    /// the window, so it reflects whatever's in the table right now
+    Stats {
+        #[arg(long, value_parser = humantime::parse_duration, default_value = "24h")]
+        /// How far back to roll deployments up
+        lookback: Duration,
+    },
+    /// Print a Prometheus text-exposition snapshot of every deployment and
+    /// stale heartbeat to stdout, for a scrape job to capture - this crate
+    /// has no long-running HTTP server of its own to add a `/metrics` route
+    /// to
+    Prometheus,
+    /// Expire queued/blocked/running deployments whose heartbeat has gone stale
+    Reap {
+        #[arg(long, value_parser = humantime::parse_duration, default_value = "2m")]
+        /// How long a deployment can go without a heartbeat before it is reaped
+        stale_after: Duration,
+    },
+    /// Run a background janitor that evaluates outliers on an interval
+    /// (alerting on newly-flagged ones) and prunes old deployments so
+    /// `deployments`/`deployment_runs` stay bounded
+    Retention {
+        #[arg(long, value_parser = humantime::parse_duration, default_value = "5m")]
+        /// How often to evaluate outliers and run the retention sweep
+        tick_interval: Duration,
+        #[arg(long, value_enum, default_value = "keep-forever")]
+        /// Which deployments the retention sweep is allowed to delete
+        retention_mode: RetentionMode,
+        #[arg(long, value_parser = humantime::parse_duration, default_value = "30d")]
+        /// How long after finishing/cancelling a deployment is kept before
+        /// the retention sweep (if enabled) deletes it
+        retention_window: Duration,
+    },
+    /// One-shot prune of old finished/cancelled deployments - the one-shot
+    /// counterpart to `Retention`'s background sweep, meant to be invoked as
+    /// a scheduled GitHub Action step rather than run continuously
+    Prune {
+        #[arg(long, value_enum, default_value = "keep-all")]
+        /// Which deployments this prune is allowed to delete
+        policy: PrunePolicy,
+        #[arg(long, value_parser = humantime::parse_duration, default_value = "30d")]
+        /// How long after finishing/cancelling a deployment is kept before
+        /// this prune (if its policy selects that status) deletes it
+        after: Duration,
+    },
+    /// Run a background janitor that requeues (with backoff) `running`
+    /// deployments whose worker lease (heartbeat) has expired, and times them
+    /// out once they've lost their lease too many times, freeing their
+    /// concurrency key
+    Reaper {
+        #[arg(long, value_parser = humantime::parse_duration, default_value = "5m")]
+        /// How long a running deployment can go without a heartbeat before
+        /// its lease is considered expired
+        lease_timeout: Duration,
+        #[arg(long, default_value = "3")]
+        /// Number of times a deployment can lose its worker lease before it
+        /// is given up on and moved to `timed_out` instead of requeued
+        max_attempts: i32,
+        #[arg(long, value_parser = humantime::parse_duration, default_value = "30s")]
+        /// Base delay before retrying a deployment that lost its worker lease
+        base_backoff: Duration,
+        #[arg(long, default_value = "2.0")]
+        /// Multiplier applied to `base_backoff` for each prior lease-expiry retry
+        backoff_factor: f64,
+        #[arg(long, value_parser = humantime::parse_duration, default_value = "10m")]
+        /// Upper bound on the requeue delay, regardless of retry count
+        max_backoff: Duration,
+    },
 }
 
 #[derive(Subcommand, Clone)]
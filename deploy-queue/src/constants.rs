@@ -3,4 +3,86 @@ use std::time::Duration;
 pub const CONNECTION_TIMEOUT: Duration = Duration::from_secs(10);
 pub const ACQUIRE_TIMEOUT: Duration = Duration::from_secs(10);
 pub const IDLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Env var overriding the production pool's `max_connections`; unset falls
+/// back to `DEFAULT_MAX_CONNECTIONS`. The test pool helpers honor the same
+/// var so a developer debugging a connection-exhaustion issue can shrink
+/// both with one setting.
+pub const MAX_CONNECTIONS_ENV: &str = "DEPLOY_QUEUE_MAX_CONNECTIONS";
+pub const DEFAULT_MAX_CONNECTIONS: u32 = 10;
+/// `create_test_db_connection`'s default `max_connections` absent
+/// `MAX_CONNECTIONS_ENV` - CI creates one Postgres database per test and
+/// runs many tests in parallel, so a production-sized pool per test
+/// database exhausts the server's connection limit fast.
+pub const TEST_MAX_CONNECTIONS: u32 = 2;
+/// Env var overriding the `acquire_timeout` (in whole seconds) that
+/// `PgPoolOptions` is built with, for both the production and test pools.
+/// Distinct from the `ACQUIRE_TIMEOUT` duration reused below as a per-query
+/// timeout in `util::instrument::Instrumented` - this only governs how long
+/// a pool will wait for a free connection before giving up.
+pub const ACQUIRE_TIMEOUT_ENV: &str = "DEPLOY_QUEUE_ACQUIRE_TIMEOUT_SECS";
+
+/// `Instrumented::instrumented` logs a warning naming the operation and call
+/// site when a query's wall time (which, since nothing in this crate holds a
+/// connection across more than one query, is also how long that query held
+/// its pool connection) exceeds this - a cheap first signal on the
+/// `blocking_deployments`/`outlier_deployments` fetches that scan the
+/// largest tables, without waiting on a metrics dashboard.
+pub const SLOW_QUERY_WARN_THRESHOLD: Duration = Duration::from_secs(2);
+
 pub const BUSY_RETRY: Duration = Duration::from_secs(5);
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+pub const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(120);
+pub const HEARTBEAT_UPDATE_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long `HeartbeatHandle::shutdown` waits for the loop to flush its
+/// final heartbeat and return before giving up and logging a warning.
+pub const HEARTBEAT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+pub const REAPER_SWEEP_INTERVAL: Duration = Duration::from_secs(30);
+
+/// `DeploymentFilter::push_order_and_page`'s limit when the caller doesn't
+/// set one, and the cap any caller-supplied limit is clamped to - keeps a
+/// forgotten/unbounded filter from pulling the whole `deployments` table.
+pub const DEFAULT_LIST_LIMIT: i64 = 100;
+pub const MAX_LIST_LIMIT: i64 = 1000;
+pub const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Starting delay and cap for `handler::subscribe`'s reconnect backoff after
+/// its `PgListener` connection is lost.
+pub const SUBSCRIBE_RECONNECT_BASE: Duration = Duration::from_secs(1);
+pub const SUBSCRIBE_RECONNECT_CAP: Duration = Duration::from_secs(60);
+
+/// Width, in standard deviations, of the confidence band `predict::eta`
+/// draws around a group's mean duration - also the threshold an in-flight
+/// deployment has to cross before it's flagged `is_anomalous`.
+pub const ETA_CONFIDENCE_K: f64 = 2.0;
+
+/// Width, in MADs, of the `Outliers` threshold above a group's median
+/// duration - see `history::Baseline::threshold`.
+pub const OUTLIER_MAD_K: f64 = 3.0;
+
+/// A `(component, environment, region)` group needs at least this many
+/// finished runs in `deployment_durations` before its median/MAD baseline is
+/// trusted; below it, `OUTLIER_FALLBACK_THRESHOLD` applies instead.
+pub const OUTLIER_MIN_SAMPLES: usize = 8;
+
+/// Flat elapsed-time threshold for `Outliers` when a group doesn't yet have
+/// `OUTLIER_MIN_SAMPLES` finished runs to build a baseline from.
+pub const OUTLIER_FALLBACK_THRESHOLD: Duration = Duration::from_secs(3600);
+
+/// Floor on `history::Baseline::threshold`'s MAD-derived spread, so a group
+/// whose finished runs so far have all taken (near enough) the same time -
+/// MAD of zero - doesn't flag the very next run that's a hair slower than
+/// the median.
+pub const OUTLIER_MAD_EPSILON: Duration = Duration::from_secs(60);
+
+/// Modified z-score `stats::rollup` judges each finished run's duration
+/// against the rest of its component's window, via `OutlierDeployment::
+/// detect` - Iglesias & Hoaglin's rule of thumb for this statistic.
+pub const STATS_OUTLIER_THRESHOLD: f64 = 3.5;
+
+/// Bucket boundaries, in seconds, for `prometheus::render`'s
+/// `deploy_queue_deployment_duration_seconds` histogram - wide enough to
+/// span a quick config-only deploy up through a multi-hour migration
+/// without needing more than a handful of buckets per component.
+pub const DURATION_HISTOGRAM_BUCKETS_SECONDS: &[f64] =
+    &[30.0, 60.0, 120.0, 300.0, 600.0, 1200.0, 1800.0, 3600.0, 7200.0, 14400.0];
@@ -1,19 +1,126 @@
 use anyhow::Result;
-use log::info;
-use sqlx::{Pool, Postgres};
+use log::{info, warn};
+use sqlx::{FromRow, Pool, Postgres, QueryBuilder};
+
+use crate::{
+    handler::{filter::DeploymentFilter, subscribe},
+    model::{Cell, DeploymentStatus},
+    notifier::{self, DeploymentEvent, Notifier},
+};
 
 pub async fn deployment(
     client: &Pool<Postgres>,
     deployment_id: i64,
     cancellation_note: Option<impl AsRef<str>>,
+    notifiers: &[Box<dyn Notifier>],
 ) -> Result<()> {
     let cancellation_note: Option<&str> = cancellation_note.as_ref().map(|note| note.as_ref());
 
     log::info!("Cancelling deployment {}", deployment_id);
-    sqlx::query!("UPDATE deployments SET cancellation_timestamp = NOW(), cancellation_note = $2 WHERE id = $1", deployment_id, cancellation_note)
-        .execute(client)
-        .await?;
+
+    // Cancellation targets the latest run of this job, not the job row
+    // itself - a job can have several runs once it's been retried. The
+    // `latest` CTE keeps the read-then-write atomic and reports the
+    // pre-cancel status. `notified` runs `pg_notify` on the same row inside
+    // the same statement, so a worker executing this deployment learns
+    // about the cancellation (via `watch_cancellations`) the moment it
+    // commits, instead of waiting for its next poll. It also fires
+    // `deploy_queue_changed`, so a `handler::listen::ChangeListener` waiter
+    // (e.g. `wait_for_blocking_deployments`) learns a blocker went away at
+    // the same time.
+    let row = sqlx::query!(
+        r#"WITH latest AS (
+             SELECT id, status FROM deployment_runs
+             WHERE deployment_id = $1
+             ORDER BY attempt_number DESC
+             LIMIT 1
+           ),
+           updated AS (
+             UPDATE deployment_runs AS r
+             SET status = 'cancelled', cancellation_timestamp = NOW(), cancellation_note = $2
+             FROM latest, deployments AS d
+             WHERE r.id = latest.id
+               AND d.id = $1
+               AND latest.status NOT IN ('finished', 'cancelled', 'expired', 'timed_out', 'failed')
+             RETURNING
+               latest.status AS old_status,
+               d.component,
+               d.version,
+               d.environment,
+               d.cloud_provider,
+               d.region,
+               d.cell_index
+           ),
+           notified AS (
+             SELECT *, pg_notify('deployment_cancelled', $1::text), pg_notify('deploy_queue_changed', $1::text) FROM updated
+           )
+           SELECT
+             old_status AS "old_status: DeploymentStatus",
+             component,
+             version,
+             environment,
+             cloud_provider,
+             region,
+             cell_index
+           FROM notified"#,
+        deployment_id,
+        cancellation_note
+    )
+    .fetch_optional(client)
+    .await?;
+
+    let Some(row) = row else {
+        anyhow::bail!(
+            "Deployment {} cannot be cancelled (not found, or already in a terminal state)",
+            deployment_id
+        );
+    };
+
     log::info!("Deployment {} has been cancelled", deployment_id);
+
+    let cell = Cell {
+        environment: row.environment,
+        cloud_provider: row.cloud_provider,
+        region: row.region,
+        index: row.cell_index,
+    };
+
+    if let Err(err) = subscribe::notify(
+        client,
+        &subscribe::DeploymentEvent {
+            deployment_id,
+            component: row.component.clone(),
+            cell: cell.clone(),
+            new_state: DeploymentStatus::Cancelled,
+        },
+    )
+    .await
+    {
+        warn!("Failed to publish deployment_events notification for deployment {deployment_id}: {err:#}");
+    }
+
+    notifier::notify_all(
+        notifiers,
+        &DeploymentEvent {
+            deployment_id,
+            component: row.component,
+            version: row.version,
+            location: cell.location(),
+            old_state: Some(row.old_status),
+            new_state: DeploymentStatus::Cancelled,
+            note: cancellation_note.map(str::to_owned),
+        },
+    )
+    .await;
+
+    if let Err(err) = super::mutexbot::release_if_reserved(client, deployment_id).await {
+        log::warn!(
+            "Failed to release reserved resource for deployment {}: {}",
+            deployment_id,
+            err
+        );
+    }
+
     Ok(())
 }
 
@@ -22,6 +129,7 @@ pub async fn by_component_version(
     component: impl AsRef<str>,
     version: impl AsRef<str>,
     cancellation_note: Option<impl AsRef<str>>,
+    notifiers: &[Box<dyn Notifier>],
 ) -> Result<u64> {
     let component: &str = component.as_ref();
     let version: &str = version.as_ref();
@@ -34,28 +142,256 @@ pub async fn by_component_version(
         component, version
     );
 
-    let result = sqlx::query!(
-        "UPDATE deployments
-         SET cancellation_timestamp = NOW(), cancellation_note = $1
-         WHERE component = $2
-           AND version = $3",
+    let rows = sqlx::query!(
+        r#"WITH latest_runs AS (
+             SELECT DISTINCT ON (deployment_id) id, deployment_id, status
+             FROM deployment_runs
+             ORDER BY deployment_id, attempt_number DESC
+           ),
+           updated AS (
+             UPDATE deployment_runs AS r
+             SET status = 'cancelled', cancellation_timestamp = NOW(), cancellation_note = $1
+             FROM latest_runs, deployments AS d
+             WHERE r.id = latest_runs.id
+               AND d.id = latest_runs.deployment_id
+               AND d.component = $2
+               AND d.version = $3
+               AND latest_runs.status NOT IN ('finished', 'cancelled', 'expired', 'timed_out', 'failed')
+             RETURNING
+               d.id,
+               latest_runs.status AS old_status,
+               d.component,
+               d.version,
+               d.environment,
+               d.cloud_provider,
+               d.region,
+               d.cell_index
+           ),
+           notified AS (
+             SELECT *, pg_notify('deployment_cancelled', id::text), pg_notify('deploy_queue_changed', id::text) FROM updated
+           )
+           SELECT
+             id,
+             old_status AS "old_status: DeploymentStatus",
+             component,
+             version,
+             environment,
+             cloud_provider,
+             region,
+             cell_index
+           FROM notified"#,
         cancellation_note,
         component,
         version
     )
-    .execute(client)
+    .fetch_all(client)
     .await?;
 
-    let rows_affected = result.rows_affected();
+    let rows_affected = rows.len() as u64;
     log::info!(
         "Cancelled {} deployment(s) for component {} version {}",
         rows_affected,
         component,
         version,
     );
+
+    for row in rows {
+        let cell = Cell {
+            environment: row.environment,
+            cloud_provider: row.cloud_provider,
+            region: row.region,
+            index: row.cell_index,
+        };
+
+        if let Err(err) = subscribe::notify(
+            client,
+            &subscribe::DeploymentEvent {
+                deployment_id: row.id,
+                component: row.component.clone(),
+                cell: cell.clone(),
+                new_state: DeploymentStatus::Cancelled,
+            },
+        )
+        .await
+        {
+            warn!("Failed to publish deployment_events notification for deployment {}: {err:#}", row.id);
+        }
+
+        notifier::notify_all(
+            notifiers,
+            &DeploymentEvent {
+                deployment_id: row.id,
+                component: row.component,
+                version: row.version,
+                location: cell.location(),
+                old_state: Some(row.old_status),
+                new_state: DeploymentStatus::Cancelled,
+                note: cancellation_note.map(str::to_owned),
+            },
+        )
+        .await;
+
+        if let Err(err) = super::mutexbot::release_if_reserved(client, row.id).await {
+            log::warn!(
+                "Failed to release reserved resource for deployment {}: {}",
+                row.id,
+                err
+            );
+        }
+    }
+
     Ok(rows_affected)
 }
 
+#[derive(FromRow)]
+struct CancelledRow {
+    id: i64,
+    old_status: DeploymentStatus,
+    component: String,
+    version: Option<String>,
+    environment: String,
+    cloud_provider: String,
+    region: String,
+    cell_index: i32,
+}
+
+/// Cancel every non-terminal deployment matching `filter`, atomically, in a
+/// single dynamically-built statement: only the predicates the caller set
+/// on `filter` narrow down which rows are affected, same as `handler::list`
+/// - so an operator can cancel "everything for this component, in any
+/// region" or "everything still queued older than an hour" without a
+/// bespoke function per predicate combination. `filter.limit`/`offset` are
+/// honored the same way `list` honors them, in case a caller wants to
+/// cancel a capped batch at a time.
+pub async fn by_filter(
+    client: &Pool<Postgres>,
+    filter: DeploymentFilter,
+    cancellation_note: Option<impl AsRef<str>>,
+    notifiers: &[Box<dyn Notifier>],
+) -> Result<Vec<i64>> {
+    let cancellation_note: Option<&str> = cancellation_note.as_ref().map(|note| note.as_ref());
+
+    let mut query = QueryBuilder::<Postgres>::new(
+        r#"WITH latest_runs AS (
+             SELECT DISTINCT ON (d.id) r.id AS run_id, d.id, r.status
+             FROM deployment_runs r
+             JOIN deployments d ON d.id = r.deployment_id
+             WHERE 1 = 1"#,
+    );
+    filter.push_where(&mut query, "d", "r");
+    query.push(" ORDER BY d.id, r.attempt_number DESC) ");
+    query.push(
+        r#"SELECT latest_runs.id FROM latest_runs
+           WHERE latest_runs.status NOT IN ('finished', 'cancelled', 'expired', 'timed_out', 'failed')"#,
+    );
+    filter.push_order_and_page(&mut query, "latest_runs");
+
+    // `latest_runs` already applied the filter and the status guard; the
+    // actual cancellation just targets whichever run ids it selected, so
+    // `LIMIT`/`OFFSET` (if set) bound the batch being cancelled rather than
+    // the candidate scan.
+    let candidate_ids: Vec<i64> = query.build_query_scalar().fetch_all(client).await?;
+
+    if candidate_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let rows: Vec<CancelledRow> = sqlx::query_as(
+        r#"WITH updated AS (
+             UPDATE deployment_runs AS r
+             SET status = 'cancelled', cancellation_timestamp = NOW(), cancellation_note = $2
+             FROM deployments AS d
+             WHERE r.id = ANY($1)
+               AND d.id = r.deployment_id
+             RETURNING
+               d.id,
+               r.status AS old_status,
+               d.component,
+               d.version,
+               d.environment,
+               d.cloud_provider,
+               d.region,
+               d.cell_index
+           ),
+           notified AS (
+             SELECT *, pg_notify('deployment_cancelled', id::text), pg_notify('deploy_queue_changed', id::text) FROM updated
+           )
+           SELECT
+             id,
+             old_status,
+             component,
+             version,
+             environment,
+             cloud_provider,
+             region,
+             cell_index
+           FROM notified"#,
+    )
+    .bind(&candidate_ids)
+    .bind(cancellation_note)
+    .fetch_all(client)
+    .await?;
+
+    let cancelled_ids: Vec<i64> = rows.iter().map(|row| row.id).collect();
+    info!(
+        "Cancelled {} deployment(s) matching filter: {:?}",
+        cancelled_ids.len(),
+        cancelled_ids
+    );
+
+    for row in rows {
+        let cell = Cell {
+            environment: row.environment,
+            cloud_provider: row.cloud_provider,
+            region: row.region,
+            index: row.cell_index,
+        };
+
+        if let Err(err) = subscribe::notify(
+            client,
+            &subscribe::DeploymentEvent {
+                deployment_id: row.id,
+                component: row.component.clone(),
+                cell: cell.clone(),
+                new_state: DeploymentStatus::Cancelled,
+            },
+        )
+        .await
+        {
+            warn!("Failed to publish deployment_events notification for deployment {}: {err:#}", row.id);
+        }
+
+        notifier::notify_all(
+            notifiers,
+            &DeploymentEvent {
+                deployment_id: row.id,
+                component: row.component,
+                version: row.version,
+                location: cell.location(),
+                old_state: Some(row.old_status),
+                new_state: DeploymentStatus::Cancelled,
+                note: cancellation_note.map(str::to_owned),
+            },
+        )
+        .await;
+
+        if let Err(err) = super::mutexbot::release_if_reserved(client, row.id).await {
+            log::warn!(
+                "Failed to release reserved resource for deployment {}: {}",
+                row.id,
+                err
+            );
+        }
+    }
+
+    Ok(cancelled_ids)
+}
+
+/// Cancel every non-terminal deployment at `environment`/`cloud_provider`/
+/// `region` (optionally narrowed to one `cell_index`). A thin wrapper
+/// around `by_filter` - kept as its own function since "cancel this
+/// location" is common enough to deserve a name that doesn't require
+/// building a `DeploymentFilter` by hand.
 pub async fn by_location(
     client: &Pool<Postgres>,
     environment: impl AsRef<str>,
@@ -63,14 +399,12 @@ pub async fn by_location(
     region: impl AsRef<str>,
     cell_index: Option<i32>,
     cancellation_note: Option<impl AsRef<str>>,
+    notifiers: &[Box<dyn Notifier>],
 ) -> Result<u64> {
-    let environment: &str = environment.as_ref();
-    let cloud_provider: &str = cloud_provider.as_ref();
-    let region: &str = region.as_ref();
+    let environment = environment.as_ref().to_string();
+    let cloud_provider = cloud_provider.as_ref().to_string();
+    let region = region.as_ref().to_string();
 
-    let cancellation_note: Option<&str> = cancellation_note.as_ref().map(|note| note.as_ref());
-
-    // Cancel by location (environment + cloud_provider + region + optional cell_index)
     info!(
         "Cancelling all deployments for environment {} on cloud provider {} in region {}{}",
         environment,
@@ -81,56 +415,19 @@ pub async fn by_location(
             .unwrap_or_default()
     );
 
-    let result = if let Some(cell_index) = cell_index {
-        sqlx::query!(
-            "UPDATE deployments
-             SET cancellation_timestamp = NOW(), cancellation_note = $1
-             WHERE environment = $2
-               AND cloud_provider = $3
-               AND region = $4
-               AND cell_index = $5",
-            cancellation_note,
-            environment,
-            cloud_provider,
-            region,
-            cell_index
-        )
-        .execute(client)
-        .await?
-    } else {
-        sqlx::query!(
-            "UPDATE deployments
-             SET cancellation_timestamp = NOW(), cancellation_note = $1
-             WHERE environment = $2
-               AND cloud_provider = $3
-               AND region = $4",
-            cancellation_note,
-            environment,
-            cloud_provider,
-            region
-        )
-        .execute(client)
-        .await?
-    };
+    let cancelled = by_filter(
+        client,
+        DeploymentFilter {
+            environment: Some(environment),
+            cloud_provider: Some(cloud_provider),
+            region: Some(region),
+            cell_index,
+            ..Default::default()
+        },
+        cancellation_note,
+        notifiers,
+    )
+    .await?;
 
-    let rows_affected = result.rows_affected();
-    if let Some(cell_index) = cell_index {
-        log::info!(
-            "Cancelled {} deployment(s) in environment {} / {} / {} / cell {}",
-            rows_affected,
-            environment,
-            cloud_provider,
-            region,
-            cell_index
-        );
-    } else {
-        log::info!(
-            "Cancelled {} deployment(s) in environment {} / {} / {}",
-            rows_affected,
-            environment,
-            cloud_provider,
-            region
-        );
-    }
-    Ok(rows_affected)
+    Ok(cancelled.len() as u64)
 }
@@ -0,0 +1,161 @@
+use anyhow::{Context, Result};
+use log::warn;
+use sqlx::{Pool, Postgres};
+
+use crate::{
+    handler::subscribe,
+    model::{Cell, Deployment, DeploymentStatus},
+    notifier::{self, DeploymentEvent, Notifier},
+    util::duration::DurationExt,
+};
+
+/// Atomically select and start the oldest queued deployment that is
+/// currently runnable: its `concurrency_key` (if any) has no other run
+/// currently `running`, and the most recent `finished` run sharing that key
+/// finished at least `buffer_time` ago. `NULL` concurrency keys never
+/// exclude anything. A deployment whose `not_before` hasn't passed yet -
+/// a retry still waiting out its backoff - isn't eligible either.
+///
+/// Uses `FOR UPDATE SKIP LOCKED` inside the candidate CTE, so concurrent
+/// workers calling this at the same time never pick the same run - a worker
+/// that finds every eligible run already locked just gets `None` back
+/// instead of blocking.
+pub async fn claim_next(
+    client: &Pool<Postgres>,
+    worker_id: &str,
+    notifiers: &[Box<dyn Notifier>],
+) -> Result<Option<Deployment>> {
+    let row = sqlx::query!(
+        r#"WITH candidate AS (
+             SELECT r.id
+             FROM deployment_runs r
+             JOIN deployments d ON d.id = r.deployment_id
+             JOIN environments e ON e.environment = d.environment
+             WHERE r.status = 'queued'
+               AND r.next_run_at <= NOW()
+               AND (d.not_before IS NULL OR d.not_before <= NOW())
+               AND (
+                 d.concurrency_key IS NULL
+                 OR NOT EXISTS (
+                     SELECT 1 FROM deployment_runs other
+                     JOIN deployments od ON od.id = other.deployment_id
+                     WHERE od.concurrency_key = d.concurrency_key
+                       AND other.status = 'running'
+                 )
+               )
+               AND (
+                 d.concurrency_key IS NULL
+                 OR NOT EXISTS (
+                     SELECT 1 FROM deployment_runs other
+                     JOIN deployments od ON od.id = other.deployment_id
+                     WHERE od.concurrency_key = d.concurrency_key
+                       AND other.status = 'finished'
+                       AND NOW() - other.finish_timestamp < e.buffer_time
+                 )
+               )
+             ORDER BY r.created_at
+             FOR UPDATE OF r SKIP LOCKED
+             LIMIT 1
+           )
+           UPDATE deployment_runs AS r
+           SET status = 'running', start_timestamp = NOW(), run_host = $1
+           FROM candidate, deployments AS d
+           WHERE r.id = candidate.id AND d.id = r.deployment_id
+           RETURNING
+             r.deployment_id,
+             r.attempt_number,
+             r.start_timestamp,
+             d.component,
+             d.version,
+             d.url,
+             d.note,
+             d.concurrency_key,
+             d.environment,
+             d.cloud_provider,
+             d.region,
+             d.cell_index,
+             d.max_retries,
+             d.retry_of,
+             d.retry_attempt,
+             d.not_before,
+             (SELECT buffer_time FROM environments WHERE environment = d.environment) AS "buffer_time!",
+             (SELECT COUNT(*) FROM deployment_runs WHERE deployment_id = d.id) AS "run_count!""#,
+        worker_id
+    )
+    .fetch_optional(client)
+    .await?;
+
+    let Some(row) = row else {
+        return Ok(None);
+    };
+
+    log::info!(
+        "Worker {} claimed deployment {} (attempt {})",
+        worker_id,
+        row.deployment_id,
+        row.attempt_number
+    );
+
+    let cell = Cell {
+        environment: row.environment,
+        cloud_provider: row.cloud_provider,
+        region: row.region,
+        index: row.cell_index,
+    };
+
+    if let Err(err) = subscribe::notify(
+        client,
+        &subscribe::DeploymentEvent {
+            deployment_id: row.deployment_id,
+            component: row.component.clone(),
+            cell: cell.clone(),
+            new_state: DeploymentStatus::Running,
+        },
+    )
+    .await
+    {
+        warn!(
+            "Failed to publish deployment_events notification for deployment {}: {err:#}",
+            row.deployment_id
+        );
+    }
+
+    notifier::notify_all(
+        notifiers,
+        &DeploymentEvent {
+            deployment_id: row.deployment_id,
+            component: row.component.clone(),
+            version: row.version.clone(),
+            location: cell.location(),
+            old_state: Some(DeploymentStatus::Queued),
+            new_state: DeploymentStatus::Running,
+            note: None,
+        },
+    )
+    .await;
+
+    Ok(Some(Deployment {
+        id: row.deployment_id,
+        cell,
+        component: row.component,
+        version: row.version,
+        url: row.url,
+        note: row.note,
+        start_timestamp: row.start_timestamp,
+        finish_timestamp: None,
+        cancellation_timestamp: None,
+        cancellation_note: None,
+        concurrency_key: row.concurrency_key,
+        buffer_time: row
+            .buffer_time
+            .to_duration()
+            .context("Failed to convert buffer_time from database")?,
+        status: DeploymentStatus::Running,
+        attempt_number: row.attempt_number,
+        run_count: row.run_count,
+        max_retries: row.max_retries,
+        retry_of: row.retry_of,
+        retry_attempt: row.retry_attempt,
+        not_before: row.not_before,
+    }))
+}
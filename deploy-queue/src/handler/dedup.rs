@@ -0,0 +1,97 @@
+use std::sync::LazyLock;
+
+use anyhow::{Context, Result};
+use dashmap::{DashMap, mapref::entry::Entry};
+use tokio::sync::broadcast;
+
+use crate::cli::StartDeployment;
+
+/// Identifies a deployment target: two `Start` invocations with the same key
+/// are deploying the same component+version to the same location, so only
+/// one of them should actually hit the database.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct DeploymentKey {
+    component: String,
+    version: Option<String>,
+    environment: String,
+    cloud_provider: String,
+    region: String,
+    cell_index: i32,
+}
+
+impl DeploymentKey {
+    fn from_start(start: &StartDeployment) -> Self {
+        Self {
+            component: start.component.clone(),
+            version: start.version.clone(),
+            environment: start.environment.to_string(),
+            cloud_provider: start.cloud_provider.clone(),
+            region: start.region.clone(),
+            cell_index: start.cell_index,
+        }
+    }
+}
+
+/// In-flight `Start` invocations for this process, keyed by target. Only
+/// lives for the duration of one `deploy-queue` process, so it coalesces
+/// concurrent calls within a single GitHub Actions job, not across jobs.
+static IN_FLIGHT: LazyLock<DashMap<DeploymentKey, broadcast::Sender<i64>>> =
+    LazyLock::new(DashMap::new);
+
+/// Ensures the in-flight entry for `key` is removed no matter how the leader
+/// future finishes (success, error, or panic), so a crashed leader never
+/// strands its followers waiting forever.
+struct RemoveGuard(DeploymentKey);
+
+impl Drop for RemoveGuard {
+    fn drop(&mut self) {
+        IN_FLIGHT.remove(&self.0);
+    }
+}
+
+/// Run `enqueue` to completion as the sole "leader" for this deployment
+/// target, or, if an identical `Start` is already in flight in this process,
+/// skip straight to awaiting its result.
+pub async fn coalesce<F, Fut>(start: &StartDeployment, enqueue: F) -> Result<i64>
+where
+    F: FnOnce() -> Fut,
+    Fut: std::future::Future<Output = Result<i64>>,
+{
+    let key = DeploymentKey::from_start(start);
+
+    // Check-and-insert has to be one atomic map operation: holding the
+    // `Entry` across the match (rather than matching it, dropping the shard
+    // lock, and inserting separately) is what stops two concurrent callers
+    // from both observing `Vacant` and both becoming "leader".
+    let (mut receiver, sender) = match IN_FLIGHT.entry(key.clone()) {
+        Entry::Occupied(occupied) => (Some(occupied.get().subscribe()), None),
+        Entry::Vacant(vacant) => {
+            let (sender, _) = broadcast::channel(1);
+            vacant.insert(sender.clone());
+            (None, Some(sender))
+        }
+    };
+
+    if let Some(receiver) = receiver.as_mut() {
+        log::info!(
+            "An identical deployment for {} (@{}) is already in flight, coalescing onto it",
+            key.component,
+            key.version.as_deref().unwrap_or("unknown")
+        );
+        return receiver
+            .recv()
+            .await
+            .context("Leader for in-flight deployment disappeared without reporting a result");
+    }
+
+    // We won the race to become the leader.
+    let sender = sender.expect("Vacant branch always produces a sender");
+    let _guard = RemoveGuard(key);
+
+    let result = enqueue().await;
+    if let Ok(deployment_id) = result {
+        // No receivers is fine - nobody was waiting on us.
+        let _ = sender.send(deployment_id);
+    }
+    result
+}
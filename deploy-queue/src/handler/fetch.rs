@@ -1,11 +1,19 @@
 use anyhow::{Context, Result, bail};
-use sqlx::{Pool, Postgres};
-use time::Duration;
+use sqlx::{postgres::types::PgInterval, FromRow, Pool, Postgres, QueryBuilder};
+use time::{Duration, OffsetDateTime};
 
 use crate::{
     cli::Environment,
-    model::{BlockingDeployment, Cell, Deployment, OutlierDeployment},
-    util::duration::DurationExt,
+    constants::{
+        ACQUIRE_TIMEOUT, MAX_LIST_LIMIT, OUTLIER_FALLBACK_THRESHOLD, OUTLIER_MAD_K, OUTLIER_MIN_SAMPLES,
+    },
+    handler::filter::DeploymentFilter,
+    history::DeploymentHistory,
+    model::{
+        AnalyticsConfig, BlockingDeployment, Cell, Deployment, DeploymentStatus, DurationAnalytics,
+        OccupancyAnalytics, OutlierDeployment,
+    },
+    util::{duration::DurationExt, instrument::Instrumented},
 };
 
 pub async fn deployment(client: &Pool<Postgres>, deployment_id: i64) -> Result<Option<Deployment>> {
@@ -13,10 +21,20 @@ pub async fn deployment(client: &Pool<Postgres>, deployment_id: i64) -> Result<O
         r#"
         SELECT
             d.id, d.environment, d.cloud_provider, d.region, d.cell_index, d.component, d.version, d.url, d.note, d.concurrency_key,
-            d.start_timestamp, d.finish_timestamp, d.cancellation_timestamp, d.cancellation_note,
-            e.buffer_time
+            d.max_retries, d.retry_of, d.retry_attempt, d.not_before,
+            r.attempt_number,
+            r.start_timestamp, r.finish_timestamp, r.cancellation_timestamp, r.cancellation_note,
+            r.status as "status: DeploymentStatus",
+            e.buffer_time,
+            (SELECT COUNT(*) FROM deployment_runs WHERE deployment_id = d.id) AS "run_count!"
         FROM deployments d
         JOIN environments e ON d.environment = e.environment
+        JOIN LATERAL (
+            SELECT * FROM deployment_runs
+            WHERE deployment_id = d.id
+            ORDER BY attempt_number DESC
+            LIMIT 1
+        ) r ON true
         WHERE d.id = $1
         "#,
         deployment_id
@@ -42,6 +60,13 @@ pub async fn deployment(client: &Pool<Postgres>, deployment_id: i64) -> Result<O
             finish_timestamp: row.finish_timestamp,
             cancellation_timestamp: row.cancellation_timestamp,
             cancellation_note: row.cancellation_note,
+            status: row.status,
+            attempt_number: row.attempt_number,
+            run_count: row.run_count,
+            max_retries: row.max_retries,
+            retry_of: row.retry_of,
+            retry_attempt: row.retry_attempt,
+            not_before: row.not_before,
             buffer_time: row
                 .buffer_time
                 .to_duration()
@@ -73,12 +98,83 @@ pub async fn deployment_id_by_url(client: &Pool<Postgres>, url: &str) -> Result<
     }
 }
 
+/// List deployments that have failed at least once and are waiting out
+/// their backoff: `queued` with `attempts > 0` and `next_run_at <= now`.
+/// `claim::claim_next` already applies this same filter when it dequeues,
+/// so this is purely a read for a scheduler or dashboard to see what's about
+/// to be retried - it never claims or mutates anything itself.
+pub async fn deployments_due_for_retry(
+    client: &Pool<Postgres>,
+    now: OffsetDateTime,
+) -> Result<Vec<Deployment>> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            d.id, d.environment, d.cloud_provider, d.region, d.cell_index, d.component, d.version, d.url, d.note, d.concurrency_key,
+            d.max_retries, d.retry_of, d.retry_attempt, d.not_before,
+            r.attempt_number,
+            r.start_timestamp, r.finish_timestamp, r.cancellation_timestamp, r.cancellation_note,
+            r.status as "status: DeploymentStatus",
+            e.buffer_time,
+            (SELECT COUNT(*) FROM deployment_runs WHERE deployment_id = d.id) AS "run_count!"
+        FROM deployments d
+        JOIN environments e ON d.environment = e.environment
+        JOIN LATERAL (
+            SELECT * FROM deployment_runs
+            WHERE deployment_id = d.id
+            ORDER BY attempt_number DESC
+            LIMIT 1
+        ) r ON true
+        WHERE r.status = 'queued' AND r.attempts > 0 AND r.next_run_at <= $1
+        ORDER BY r.next_run_at
+        "#,
+        now
+    )
+    .fetch_all(client)
+    .await?;
+
+    rows.into_iter()
+        .map(|row| {
+            Ok(Deployment {
+                id: row.id,
+                cell: Cell {
+                    environment: row.environment,
+                    cloud_provider: row.cloud_provider,
+                    region: row.region,
+                    index: row.cell_index,
+                },
+                component: row.component,
+                version: row.version,
+                url: row.url,
+                note: row.note,
+                concurrency_key: row.concurrency_key,
+                start_timestamp: row.start_timestamp,
+                finish_timestamp: row.finish_timestamp,
+                cancellation_timestamp: row.cancellation_timestamp,
+                cancellation_note: row.cancellation_note,
+                status: row.status,
+                attempt_number: row.attempt_number,
+                run_count: row.run_count,
+                max_retries: row.max_retries,
+                retry_of: row.retry_of,
+                retry_attempt: row.retry_attempt,
+                not_before: row.not_before,
+                buffer_time: row
+                    .buffer_time
+                    .to_duration()
+                    .context("Failed to convert buffer_time from database")?,
+            })
+        })
+        .collect::<Result<Vec<_>>>()
+}
+
 pub async fn blocking_deployments(
     client: &Pool<Postgres>,
     deployment_id: i64,
 ) -> Result<Vec<BlockingDeployment>> {
     let rows = sqlx::query_file!("queries/blocking_deployments.sql", deployment_id)
         .fetch_all(client)
+        .instrumented("blocking_deployments", ACQUIRE_TIMEOUT)
         .await?;
 
     let blocking_deployments: Vec<BlockingDeployment> = rows
@@ -121,6 +217,13 @@ pub async fn blocking_deployments(
                     cancellation_timestamp: row.cancellation_timestamp,
                     cancellation_note: row.cancellation_note,
                     concurrency_key: row.concurrency_key,
+                    status: row.status,
+                    attempt_number: row.attempt_number,
+                    run_count: row.run_count,
+                    max_retries: row.max_retries,
+                    retry_of: row.retry_of,
+                    retry_attempt: row.retry_attempt,
+                    not_before: row.not_before,
                     buffer_time,
                 },
                 avg_duration,
@@ -132,55 +235,200 @@ pub async fn blocking_deployments(
     Ok(blocking_deployments)
 }
 
-pub async fn outlier_deployments(client: &Pool<Postgres>) -> Result<Vec<OutlierDeployment>> {
-    let rows = sqlx::query_file!("queries/active_outliers.sql")
-        .fetch_all(client)
-        .await?;
+/// Look up `deployment_duration_analytics` for one (component, region,
+/// environment) group, over the window and row cap described by `config`.
+/// Returns `None` if the group has no finished runs within that window.
+pub async fn duration_analytics(
+    client: &Pool<Postgres>,
+    component: &str,
+    region: &str,
+    environment: &str,
+    config: &AnalyticsConfig,
+) -> Result<Option<DurationAnalytics>> {
+    let lookback = config
+        .lookback
+        .to_pg_interval()
+        .context("Failed to convert lookback to a PgInterval")?;
 
-    let outliers: Vec<OutlierDeployment> = rows
-        .into_iter()
-        .map(|row| {
-            let current_duration = match row.current_duration {
-                Some(i) => i.to_duration().with_context(|| {
-                    format!(
-                        "Failed to convert current_duration for deployment {}",
-                        row.id
-                    )
-                })?,
-                None => Duration::ZERO,
-            };
-            let avg_duration = match row.avg_duration {
-                Some(i) => i.to_duration().with_context(|| {
-                    format!("Failed to convert avg_duration for deployment {}", row.id)
-                })?,
-                None => Duration::ZERO,
-            };
-            let stddev_duration = match row.stddev_duration {
-                Some(i) => i.to_duration().with_context(|| {
-                    format!(
-                        "Failed to convert stddev_duration for deployment {}",
-                        row.id
-                    )
-                })?,
-                None => Duration::ZERO,
-            };
+    let row = sqlx::query!(
+        r#"SELECT
+             deployment_count AS "deployment_count!",
+             avg_duration AS "avg_duration!",
+             stddev_duration AS "stddev_duration!",
+             p50_duration AS "p50_duration!",
+             p90_duration AS "p90_duration!",
+             p95_duration AS "p95_duration!",
+             p99_duration AS "p99_duration!"
+           FROM deployment_duration_analytics($4, $5)
+           WHERE component = $1 AND region = $2 AND environment = $3"#,
+        component,
+        region,
+        environment,
+        lookback,
+        config.row_cap,
+    )
+    .fetch_optional(client)
+    .instrumented("duration_analytics", ACQUIRE_TIMEOUT)
+    .await?;
 
-            Ok(OutlierDeployment {
-                id: row.id,
-                env: row.env,
-                cloud_provider: row.cloud_provider,
-                region: row.region,
-                cell_index: row.cell_index,
-                component: row.component,
-                url: row.url,
-                note: row.note,
-                version: row.version,
-                current_duration,
-                avg_duration,
-                stddev_duration,
-            })
-        })
-        .collect::<Result<Vec<_>>>()?;
+    let Some(row) = row else {
+        return Ok(None);
+    };
+
+    Ok(Some(DurationAnalytics {
+        deployment_count: row.deployment_count,
+        avg_duration: row
+            .avg_duration
+            .to_duration()
+            .context("Failed to convert avg_duration from database")?,
+        stddev_duration: row
+            .stddev_duration
+            .to_duration()
+            .context("Failed to convert stddev_duration from database")?,
+        p50_duration: row
+            .p50_duration
+            .to_duration()
+            .context("Failed to convert p50_duration from database")?,
+        p90_duration: row
+            .p90_duration
+            .to_duration()
+            .context("Failed to convert p90_duration from database")?,
+        p95_duration: row
+            .p95_duration
+            .to_duration()
+            .context("Failed to convert p95_duration from database")?,
+        p99_duration: row
+            .p99_duration
+            .to_duration()
+            .context("Failed to convert p99_duration from database")?,
+    }))
+}
+
+/// Look up `deployment_occupancy_analytics` for one (component, region,
+/// environment) group over the trailing `window`. Returns `None` if the
+/// group had no `running`/`blocked`/`finished` activity in that window.
+pub async fn occupancy_analytics(
+    client: &Pool<Postgres>,
+    component: &str,
+    region: &str,
+    environment: &str,
+    window: Duration,
+) -> Result<Option<OccupancyAnalytics>> {
+    let window = window
+        .to_pg_interval()
+        .context("Failed to convert window to a PgInterval")?;
+
+    let row = sqlx::query!(
+        r#"SELECT
+             occupancy_fraction AS "occupancy_fraction!",
+             throughput_count AS "throughput_count!",
+             p50_duration,
+             p95_duration
+           FROM deployment_occupancy_analytics($4)
+           WHERE component = $1 AND region = $2 AND environment = $3"#,
+        component,
+        region,
+        environment,
+        window,
+    )
+    .fetch_optional(client)
+    .instrumented("occupancy_analytics", ACQUIRE_TIMEOUT)
+    .await?;
+
+    let Some(row) = row else {
+        return Ok(None);
+    };
+
+    Ok(Some(OccupancyAnalytics {
+        occupancy_fraction: row.occupancy_fraction,
+        throughput_count: row.throughput_count,
+        p50_duration: row
+            .p50_duration
+            .map(|i| i.to_duration())
+            .transpose()
+            .context("Failed to convert p50_duration from database")?,
+        p95_duration: row
+            .p95_duration
+            .map(|i| i.to_duration())
+            .transpose()
+            .context("Failed to convert p95_duration from database")?,
+    }))
+}
+
+/// Flag every `running` deployment whose elapsed time exceeds its
+/// `(component, environment, region)` group's robust baseline - median plus
+/// `OUTLIER_MAD_K` MADs, falling back to `OUTLIER_FALLBACK_THRESHOLD` for a
+/// group that hasn't finished `OUTLIER_MIN_SAMPLES` runs yet. Postgres is
+/// still the source of truth for which deployments are running; `history`
+/// supplies the baseline each one is judged against.
+pub async fn outlier_deployments(
+    client: &Pool<Postgres>,
+    history: &DeploymentHistory,
+) -> Result<Vec<OutlierDeployment>> {
+    // Oldest-first and raised to MAX_LIST_LIMIT: the default (newest-first,
+    // DEFAULT_LIST_LIMIT) would silently drop the longest-running
+    // deployments past the cutoff - exactly the ones this is trying to
+    // flag - if more are ever running at once than the default allows.
+    let running = list(
+        client,
+        DeploymentFilter {
+            statuses: vec![DeploymentStatus::Running],
+            reverse: true,
+            limit: Some(MAX_LIST_LIMIT),
+            ..Default::default()
+        },
+    )
+    .await
+    .context("Failed to list running deployments")?;
+
+    let fallback_threshold = OUTLIER_FALLBACK_THRESHOLD
+        .to_duration()
+        .context("Failed to convert OUTLIER_FALLBACK_THRESHOLD")?;
+
+    let mut outliers = Vec::new();
+    for deployment in running {
+        let Some(start_timestamp) = deployment.start_timestamp else {
+            continue;
+        };
+        let current_duration = OffsetDateTime::now_utc() - start_timestamp;
+
+        let baseline = history
+            .baseline(
+                &deployment.component,
+                &deployment.cell.environment,
+                &deployment.cell.region,
+            )
+            .await
+            .with_context(|| format!("Failed to load baseline for deployment {}", deployment.id))?;
+
+        let (median_duration, mad_duration, threshold) = match baseline {
+            Some(baseline) if baseline.sample_count >= OUTLIER_MIN_SAMPLES => {
+                (baseline.median, baseline.mad, baseline.threshold(OUTLIER_MAD_K))
+            }
+            _ => (Duration::ZERO, Duration::ZERO, fallback_threshold),
+        };
+
+        if current_duration <= threshold {
+            continue;
+        }
+
+        outliers.push(OutlierDeployment {
+            id: deployment.id,
+            env: deployment.cell.environment,
+            cloud_provider: deployment.cell.cloud_provider,
+            region: deployment.cell.region,
+            cell_index: deployment.cell.index,
+            component: deployment.component,
+            url: deployment.url,
+            note: deployment.note,
+            version: deployment.version,
+            current_duration,
+            median_duration,
+            mad_duration,
+            threshold_duration: threshold,
+            overage: current_duration - threshold,
+        });
+    }
 
     Ok(outliers)
 }
@@ -225,3 +473,101 @@ pub(crate) async fn cells(client: &Pool<Postgres>, environment: Environment) ->
 
     Ok(cells)
 }
+
+#[derive(FromRow)]
+struct ListRow {
+    id: i64,
+    environment: String,
+    cloud_provider: String,
+    region: String,
+    cell_index: i32,
+    component: String,
+    version: Option<String>,
+    url: Option<String>,
+    note: Option<String>,
+    concurrency_key: Option<String>,
+    attempt_number: i32,
+    start_timestamp: Option<OffsetDateTime>,
+    finish_timestamp: Option<OffsetDateTime>,
+    cancellation_timestamp: Option<OffsetDateTime>,
+    cancellation_note: Option<String>,
+    status: DeploymentStatus,
+    buffer_time: PgInterval,
+    run_count: i64,
+    max_retries: i32,
+    retry_of: Option<i64>,
+    retry_attempt: i32,
+    not_before: Option<OffsetDateTime>,
+}
+
+/// List deployments matching `filter`, newest first unless `filter.reverse`
+/// is set. Builds the `WHERE` clause dynamically via
+/// `DeploymentFilter::push_where` (only the predicates the caller actually
+/// set are added) since `query!`'s compile-time checking needs a single
+/// static SQL string and can't express "this clause only appears if this
+/// field is `Some`".
+pub async fn list(client: &Pool<Postgres>, filter: DeploymentFilter) -> Result<Vec<Deployment>> {
+    let mut query = QueryBuilder::<Postgres>::new(
+        r#"SELECT
+             d.id, d.environment, d.cloud_provider, d.region, d.cell_index, d.component, d.version,
+             d.url, d.note, d.concurrency_key,
+             d.max_retries, d.retry_of, d.retry_attempt, d.not_before,
+             r.attempt_number,
+             r.start_timestamp, r.finish_timestamp, r.cancellation_timestamp, r.cancellation_note,
+             r.status,
+             e.buffer_time,
+             (SELECT COUNT(*) FROM deployment_runs WHERE deployment_id = d.id) AS run_count
+           FROM deployments d
+           JOIN environments e ON d.environment = e.environment
+           JOIN LATERAL (
+               SELECT * FROM deployment_runs
+               WHERE deployment_id = d.id
+               ORDER BY attempt_number DESC
+               LIMIT 1
+           ) r ON true
+           WHERE 1 = 1"#,
+    );
+
+    filter.push_where(&mut query, "d", "r");
+    filter.push_order_and_page(&mut query, "d");
+
+    let rows = query
+        .build_query_as::<ListRow>()
+        .fetch_all(client)
+        .instrumented("list_deployments", ACQUIRE_TIMEOUT)
+        .await?;
+
+    rows.into_iter()
+        .map(|row| {
+            Ok(Deployment {
+                id: row.id,
+                cell: Cell {
+                    environment: row.environment,
+                    cloud_provider: row.cloud_provider,
+                    region: row.region,
+                    index: row.cell_index,
+                },
+                component: row.component,
+                version: row.version,
+                url: row.url,
+                note: row.note,
+                concurrency_key: row.concurrency_key,
+                start_timestamp: row.start_timestamp,
+                finish_timestamp: row.finish_timestamp,
+                cancellation_timestamp: row.cancellation_timestamp,
+                cancellation_note: row.cancellation_note,
+                status: row.status,
+                attempt_number: row.attempt_number,
+                run_count: row.run_count,
+                max_retries: row.max_retries,
+                retry_of: row.retry_of,
+                retry_attempt: row.retry_attempt,
+                not_before: row.not_before,
+                buffer_time: row
+                    .buffer_time
+                    .to_duration()
+                    .context("Failed to convert buffer_time from database")?,
+            })
+        })
+        .collect::<Result<Vec<_>>>()
+}
@@ -0,0 +1,125 @@
+use sqlx::{Postgres, QueryBuilder};
+use time::OffsetDateTime;
+
+use crate::{
+    constants::{DEFAULT_LIST_LIMIT, MAX_LIST_LIMIT},
+    model::DeploymentStatus,
+};
+
+/// Predicates shared by `handler::list` and `handler::cancel::by_filter`.
+/// Every field defaults to "unconstrained" so callers only need to set the
+/// ones they care about - e.g. `DeploymentFilter { component:
+/// Some("api-server".into()), statuses: vec![DeploymentStatus::Running],
+/// ..Default::default() }` to answer "what's running for api-server right
+/// now," across every location instead of one at a time.
+#[derive(Default, Debug, Clone)]
+pub struct DeploymentFilter {
+    pub environment: Option<String>,
+    pub cloud_provider: Option<String>,
+    pub region: Option<String>,
+    pub cell_index: Option<i32>,
+    pub component: Option<String>,
+    pub version: Option<String>,
+    /// Matches any of these statuses; unconstrained (matches every status)
+    /// when empty.
+    pub statuses: Vec<DeploymentStatus>,
+    pub enqueued_before: Option<OffsetDateTime>,
+    pub enqueued_after: Option<OffsetDateTime>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    /// Oldest first instead of the default newest first.
+    pub reverse: bool,
+    /// Keyset pagination cursor: only match rows strictly past this
+    /// `(created_at, id)` pair in the current sort order (past meaning
+    /// "older" when `reverse` is unset, "newer" when it is set). Set this to
+    /// the last row's `(created_at, id)` from the previous page instead of
+    /// bumping `offset` - unlike `OFFSET`, a cursor comparison can use the
+    /// `(created_at, id)` index directly instead of re-scanning and
+    /// discarding every row ahead of it, so deep pages stay just as fast as
+    /// the first one.
+    pub after: Option<(OffsetDateTime, i64)>,
+}
+
+impl DeploymentFilter {
+    /// Push this filter's predicates onto `query` as `AND <col> = ...`
+    /// clauses, only adding the ones the caller actually set - `query!`'s
+    /// compile-time checking needs a single static SQL string and can't
+    /// express "this clause only appears if this field is `Some`".
+    ///
+    /// `deployments_alias` and `run_alias` name the tables in the caller's
+    /// query that carry the job fields (`environment`/`component`/...) and
+    /// the targeted run's `status`/`created_at` - `list` joins them with a
+    /// `LATERAL` subquery, `cancel::by_filter` with a `DISTINCT ON` CTE, but
+    /// the predicates themselves don't care which.
+    pub fn push_where(&self, query: &mut QueryBuilder<Postgres>, deployments_alias: &str, run_alias: &str) {
+        if let Some(ref environment) = self.environment {
+            query.push(format!(" AND {deployments_alias}.environment = "));
+            query.push_bind(environment.clone());
+        }
+        if let Some(ref cloud_provider) = self.cloud_provider {
+            query.push(format!(" AND {deployments_alias}.cloud_provider = "));
+            query.push_bind(cloud_provider.clone());
+        }
+        if let Some(ref region) = self.region {
+            query.push(format!(" AND {deployments_alias}.region = "));
+            query.push_bind(region.clone());
+        }
+        if let Some(cell_index) = self.cell_index {
+            query.push(format!(" AND {deployments_alias}.cell_index = "));
+            query.push_bind(cell_index);
+        }
+        if let Some(ref component) = self.component {
+            query.push(format!(" AND {deployments_alias}.component = "));
+            query.push_bind(component.clone());
+        }
+        if let Some(ref version) = self.version {
+            query.push(format!(" AND {deployments_alias}.version = "));
+            query.push_bind(version.clone());
+        }
+        if !self.statuses.is_empty() {
+            query.push(format!(" AND {run_alias}.status = ANY("));
+            query.push_bind(self.statuses.clone());
+            query.push(")");
+        }
+        if let Some(enqueued_before) = self.enqueued_before {
+            query.push(format!(" AND {run_alias}.created_at < "));
+            query.push_bind(enqueued_before);
+        }
+        if let Some(enqueued_after) = self.enqueued_after {
+            query.push(format!(" AND {run_alias}.created_at > "));
+            query.push_bind(enqueued_after);
+        }
+        if let Some((created_at, id)) = self.after {
+            let op = if self.reverse { ">" } else { "<" };
+            query.push(format!(
+                " AND ({run_alias}.created_at, {deployments_alias}.id) {op} ("
+            ));
+            query.push_bind(created_at);
+            query.push(", ");
+            query.push_bind(id);
+            query.push(")");
+        }
+    }
+
+    /// Push `ORDER BY`, `LIMIT`, and `OFFSET` onto `query`, ordering by
+    /// `deployments_alias.id` since that's a stable proxy for enqueue order
+    /// across every caller of this filter. `limit` defaults to
+    /// `DEFAULT_LIST_LIMIT` and is always clamped to `MAX_LIST_LIMIT`, so an
+    /// unconstrained filter can't pull the whole table.
+    pub fn push_order_and_page(&self, query: &mut QueryBuilder<Postgres>, deployments_alias: &str) {
+        query.push(format!(" ORDER BY {deployments_alias}.id "));
+        query.push(if self.reverse { "ASC" } else { "DESC" });
+
+        let limit = self.limit.unwrap_or(DEFAULT_LIST_LIMIT).min(MAX_LIST_LIMIT);
+        query.push(" LIMIT ").push_bind(limit);
+
+        // A cursor already narrows the `WHERE` clause down to "past" the
+        // last page, so combining it with `OFFSET` would skip rows beyond
+        // what the caller intended - ignore `offset` once `after` is set.
+        if self.after.is_none() {
+            if let Some(offset) = self.offset {
+                query.push(" OFFSET ").push_bind(offset);
+            }
+        }
+    }
+}
@@ -1,17 +1,201 @@
 use anyhow::{Context, Result};
 use sqlx::{Pool, Postgres};
+use time::{Duration, OffsetDateTime};
 
-use crate::{cli::Environment, handler::fetch, util::github};
+use crate::{
+    cli::{Environment, OutputFormat},
+    constants::HEARTBEAT_TIMEOUT,
+    handler::{fetch, filter::DeploymentFilter, metrics},
+    history::DeploymentHistory,
+    prometheus, stats,
+    util::{duration::DurationExt, github},
+};
 
-pub(crate) async fn outliers(client: &Pool<Postgres>) -> Result<()> {
-    let outliers = fetch::outlier_deployments(client).await?;
+pub(crate) async fn outliers(
+    client: &Pool<Postgres>,
+    history: &DeploymentHistory,
+    format: OutputFormat,
+) -> Result<()> {
+    let outliers = fetch::outlier_deployments(client, history).await?;
 
     github::write_output("active-outliers", || {
         serde_json::to_string(&outliers).context("Failed to serialize outliers to JSON")
     })?;
 
-    let json_output = serde_json::to_string_pretty(&outliers)?;
-    println!("{}", json_output);
+    match format {
+        OutputFormat::Json => {
+            let json_output = serde_json::to_string_pretty(&outliers)?;
+            println!("{}", json_output);
+        }
+        OutputFormat::Text => {
+            if outliers.is_empty() {
+                println!("No outliers found.");
+            }
+            for outlier in &outliers {
+                println!(
+                    "Deployment {} ({} @ {}/{}/{}/{}) has been running for {}, {} over its median of {}.",
+                    outlier.id,
+                    outlier.component,
+                    outlier.env,
+                    outlier.cloud_provider,
+                    outlier.region,
+                    outlier.cell_index,
+                    outlier.current_duration.format_human(),
+                    outlier.overage.format_human(),
+                    outlier.median_duration.format_human(),
+                );
+            }
+        }
+    }
+
+    notify_outliers(&outliers).await.context("Failed to notify about outliers")?;
+
+    Ok(())
+}
+
+/// Alert on every outlier found, one message per deployment, naming its ID,
+/// location, and how far over its median duration it's running. Best-effort
+/// like every other `Notifier` delivery - a broken webhook shouldn't fail the
+/// `Outliers` listing itself. Shared with `handler::retention::tick_once`,
+/// which calls this with only the newly-flagged subset of a tick's outliers
+/// instead of every outlier still active.
+pub(crate) async fn notify_outliers(outliers: &[crate::model::OutlierDeployment]) -> Result<()> {
+    if outliers.is_empty() {
+        return Ok(());
+    }
+
+    let notifiers = mutexbot_client::notifier::from_env().context("Failed to configure notifiers")?;
+    if notifiers.is_empty() {
+        return Ok(());
+    }
+
+    for outlier in outliers {
+        let location = crate::model::Cell {
+            environment: outlier.env.clone(),
+            cloud_provider: outlier.cloud_provider.clone(),
+            region: outlier.region.clone(),
+            index: outlier.cell_index,
+        }
+        .location();
+        let subject = format!("Deployment {} is an outlier", outlier.id);
+        let body = format!(
+            "Deployment {} ({} @ {}) has been running for {}, {} over its median of {}.",
+            outlier.id,
+            outlier.component,
+            location,
+            outlier.current_duration.format_human(),
+            outlier.overage.format_human(),
+            outlier.median_duration.format_human(),
+        );
+        mutexbot_client::notifier::notify_all(&notifiers, &subject, &body).await;
+    }
+
+    Ok(())
+}
+
+/// Queue-health view: p50/p95 queue wait and deploy duration per component
+/// and location, over the trailing `since` window. Unlike `outliers`, this
+/// aggregates history instead of showing a point-in-time snapshot.
+pub(crate) async fn metrics(client: &Pool<Postgres>, since: Duration) -> Result<()> {
+    let summaries = metrics::summarize(client, since).await?;
+
+    github::write_output("metrics", || {
+        serde_json::to_string(
+            &summaries
+                .iter()
+                .map(|s| {
+                    serde_json::json!({
+                        "component": s.component,
+                        "location": s.location,
+                        "name": s.name,
+                        "sample_count": s.sample_count,
+                        "p50": s.p50,
+                        "p95": s.p95,
+                    })
+                })
+                .collect::<Vec<_>>(),
+        )
+        .context("Failed to serialize metrics to JSON")
+    })?;
+
+    for summary in &summaries {
+        println!(
+            "{} @ {}: {} (n={}) p50={:.1} p95={:.1}",
+            summary.component,
+            summary.location,
+            summary.name,
+            summary.sample_count,
+            summary.p50,
+            summary.p95
+        );
+    }
+
+    Ok(())
+}
+
+/// Per-component rollup of `lookback`'s worth of deployments - state counts,
+/// duration mean/median/stddev, and outlier count. Fetches with
+/// `enqueued_after` set to `lookback` before now and hands the result
+/// straight to `stats::rollup`, the same "fetch then compute in memory"
+/// split `outliers` and `stats::component_stats` already use, rather than a
+/// bespoke aggregate query.
+pub(crate) async fn stats(client: &Pool<Postgres>, lookback: Duration, format: OutputFormat) -> Result<()> {
+    let now = OffsetDateTime::now_utc();
+    let deployments = fetch::list(
+        client,
+        DeploymentFilter {
+            enqueued_after: Some(now - lookback),
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    let rollup = stats::rollup(&deployments, lookback, now);
+
+    github::write_output("stats", || {
+        serde_json::to_string(&rollup).context("Failed to serialize stats to JSON")
+    })?;
+
+    match format {
+        OutputFormat::Json => {
+            let json_output = serde_json::to_string_pretty(&rollup)?;
+            println!("{}", json_output);
+        }
+        OutputFormat::Text => {
+            if rollup.is_empty() {
+                println!("No deployments in the last {}.", lookback.format_human());
+            }
+            for component in &rollup {
+                println!(
+                    "{}: {} total (queued={}, running={}, finished={}, cancelled={}), duration mean={} median={} stddev={}, {} outlier(s)",
+                    component.component,
+                    component.total,
+                    component.queued,
+                    component.running,
+                    component.finished,
+                    component.cancelled,
+                    component.mean_duration.format_human(),
+                    component.median_duration.format_human(),
+                    component.stddev_duration.format_human(),
+                    component.outlier_count,
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Print a Prometheus text-exposition snapshot of every deployment and
+/// stale heartbeat to stdout, for a scrape job to capture - this crate has
+/// no long-running HTTP server of its own to add a `/metrics` route to, so
+/// unlike the other `list` printers there's no `--format`/`github::write_output`
+/// here: the whole point is a stable, scrapable text body on stdout.
+pub(crate) async fn prometheus_snapshot(client: &Pool<Postgres>) -> Result<()> {
+    let deployments = fetch::list(client, DeploymentFilter::default()).await?;
+    let stale = fetch::stale_heartbeat_deployments(client, HEARTBEAT_TIMEOUT).await?;
+
+    print!("{}", prometheus::render(&deployments, &stale));
 
     Ok(())
 }
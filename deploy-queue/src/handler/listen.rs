@@ -0,0 +1,71 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use log::warn;
+use sqlx::{postgres::PgListener, Pool, Postgres};
+use tokio::sync::Notify;
+
+/// The channel `enqueue_deployment`, `start_deployment`, `finish_deployment`,
+/// and every `handler::cancel::*` function notify on whenever they change a
+/// deployment's state - distinct from `CANCELLATION_CHANNEL`, which only
+/// fires for cancellations and carries a specific deployment id. This one
+/// carries no payload: waiters don't care which deployment changed, only
+/// that *something* did, so it's cheap to fire on every write path without
+/// worrying about losing or misordering payloads.
+const CHANGED_CHANNEL: &str = "deploy_queue_changed";
+
+/// A live subscription to `CHANGED_CHANNEL`, exposed as a `Notify` instead of
+/// a `Stream`: callers like `wait_for_blocking_deployments` only ever want
+/// to wake up and re-check their own condition, never to consume individual
+/// notifications, so `notify_one`/`notified` (which coalesces any number of
+/// pending notifications into a single permit) is a better fit than
+/// `watch_cancellations`'s per-item stream.
+///
+/// Holds its own dedicated connection (via `PgListener`) for the lifetime of
+/// the background task that forwards notifications onto `Notify`, separate
+/// from the pool used for ordinary queries.
+pub struct ChangeListener {
+    notify: Arc<Notify>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl ChangeListener {
+    /// Subscribe to `CHANGED_CHANNEL`. Returns once `LISTEN` has been
+    /// issued, so a change committed after this call is guaranteed to wake
+    /// a subsequent `notified().await`.
+    pub async fn connect(client: &Pool<Postgres>) -> Result<Self> {
+        let mut listener = PgListener::connect_with(client).await?;
+        listener.listen(CHANGED_CHANNEL).await?;
+
+        let notify = Arc::new(Notify::new());
+        let task_notify = notify.clone();
+        let task = tokio::spawn(async move {
+            loop {
+                match listener.recv().await {
+                    Ok(_) => task_notify.notify_one(),
+                    Err(err) => {
+                        warn!("Change listener stopped: {}", err);
+                        return;
+                    }
+                }
+            }
+        });
+
+        Ok(Self { notify, task })
+    }
+
+    /// Wait for at least one change notification since the last call (or
+    /// since `connect`, for the first call). Coalesces any number of
+    /// notifications that arrive before a waiter calls this into a single
+    /// wakeup, so a burst of writes never queues up more wakeups than
+    /// callers actually asked for.
+    pub async fn notified(&self) {
+        self.notify.notified().await;
+    }
+}
+
+impl Drop for ChangeListener {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
@@ -0,0 +1,75 @@
+use anyhow::Result;
+use sqlx::{Pool, Postgres};
+use time::Duration;
+
+/// Record a single named metric sample for a deployment (e.g.
+/// `queue_wait_seconds`, `deploy_duration_seconds`). Samples are append-only
+/// so later analytics can aggregate over any time window without needing a
+/// dedicated column for every metric that comes along.
+pub async fn record(
+    client: &Pool<Postgres>,
+    deployment_id: i64,
+    name: impl AsRef<str>,
+    value: f64,
+) -> Result<()> {
+    let name: &str = name.as_ref();
+
+    sqlx::query!(
+        "INSERT INTO deployment_metrics (deployment_id, name, value) VALUES ($1, $2, $3)",
+        deployment_id,
+        name,
+        value
+    )
+    .execute(client)
+    .await?;
+
+    Ok(())
+}
+
+/// p50/p95 of a named metric, grouped by component and location, over the
+/// last `since`.
+pub struct MetricSummary {
+    pub component: String,
+    pub location: String,
+    pub name: String,
+    pub sample_count: i64,
+    pub p50: f64,
+    pub p95: f64,
+}
+
+pub async fn summarize(
+    client: &Pool<Postgres>,
+    since: Duration,
+) -> Result<Vec<MetricSummary>> {
+    let pg_interval = crate::util::duration::DurationExt::to_pg_interval(&since)?;
+
+    let rows = sqlx::query!(
+        r#"SELECT
+             d.component,
+             d.environment || '/' || d.cloud_provider || '/' || d.region || '/' || d.cell_index AS "location!",
+             m.name,
+             COUNT(*) AS "sample_count!",
+             PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY m.value) AS "p50!",
+             PERCENTILE_CONT(0.95) WITHIN GROUP (ORDER BY m.value) AS "p95!"
+           FROM deployment_metrics m
+           JOIN deployments d ON d.id = m.deployment_id
+           WHERE m.recorded_at > NOW() - $1
+           GROUP BY d.component, location, m.name
+           ORDER BY d.component, location, m.name"#,
+        pg_interval
+    )
+    .fetch_all(client)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| MetricSummary {
+            component: row.component,
+            location: row.location,
+            name: row.name,
+            sample_count: row.sample_count,
+            p50: row.p50,
+            p95: row.p95,
+        })
+        .collect())
+}
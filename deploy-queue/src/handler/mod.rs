@@ -1,25 +1,84 @@
 pub mod cancel;
+pub mod claim;
+pub mod dedup;
 pub mod fetch;
+pub mod filter;
 pub mod list;
-
-use anyhow::Result;
+pub mod listen;
+pub mod metrics;
+pub mod mutexbot;
+pub mod reap;
+pub mod reaper;
+pub mod retention;
+pub mod subscribe;
+pub mod transition;
+pub mod worker;
+
+use anyhow::{Context, Result};
+use futures::Stream;
 use log::{info, warn};
-use sqlx::{Pool, Postgres};
+#[cfg(feature = "metrics")]
+use metrics::{counter, gauge, histogram};
+use sqlx::{postgres::PgListener, Pool, Postgres};
 use time::Duration;
 use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
 
 use crate::{
-    constants::{BUSY_RETRY, HEARTBEAT_INTERVAL, HEARTBEAT_TIMEOUT, HEARTBEAT_UPDATE_TIMEOUT},
-    model::Deployment,
-    util::{duration::DurationExt, github},
+    constants::{
+        ACQUIRE_TIMEOUT, BUSY_RETRY, HEARTBEAT_INTERVAL, HEARTBEAT_TIMEOUT, HEARTBEAT_UPDATE_TIMEOUT,
+    },
+    model::{Deployment, DeploymentStatus, HeartbeatPolicy, Severity},
+    notifier::{self, DeploymentEvent, Notifier},
+    util::{duration::DurationExt, instrument::Instrumented},
 };
 
-pub async fn enqueue_deployment(client: &Pool<Postgres>, deployment: Deployment) -> Result<i64> {
-    let record = sqlx::query!("INSERT INTO deployments (environment, cloud_provider, region, cell_index, component, version, url, note, concurrency_key) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) RETURNING id",
-        deployment.cell.environment, deployment.cell.cloud_provider, deployment.cell.region, deployment.cell.index, deployment.component, deployment.version, deployment.url, deployment.note, deployment.concurrency_key)
+pub use filter::DeploymentFilter;
+
+/// List deployments matching `filter` - an operator-facing query across
+/// every predicate `DeploymentFilter` knows about, not just location. Not
+/// to be confused with the `list` module (CLI-facing JSON printers for
+/// outliers/metrics/cells); this lives here instead so `cancel::by_filter`
+/// can share the exact same `DeploymentFilter` without the two modules
+/// depending on each other.
+pub async fn list(client: &Pool<Postgres>, filter: DeploymentFilter) -> Result<Vec<Deployment>> {
+    fetch::list(client, filter).await
+}
+
+pub async fn enqueue_deployment(
+    client: &Pool<Postgres>,
+    deployment: Deployment,
+    notifiers: &[Box<dyn Notifier>],
+) -> Result<i64> {
+    let record = sqlx::query!("INSERT INTO deployments (environment, cloud_provider, region, cell_index, component, version, url, note, concurrency_key, max_retries, retry_of, retry_attempt, not_before) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13) RETURNING id",
+        deployment.cell.environment, deployment.cell.cloud_provider, deployment.cell.region, deployment.cell.index, deployment.component, deployment.version, deployment.url, deployment.note, deployment.concurrency_key,
+        deployment.max_retries, deployment.retry_of, deployment.retry_attempt, deployment.not_before)
         .fetch_one(client)
+        .instrumented("insert_deployment_record", ACQUIRE_TIMEOUT)
         .await?;
     let deployment_id = record.id;
+
+    // Every job starts with a single run; retries append more via
+    // `retry_deployment` instead of mutating this one in place. `notified`
+    // fires `CHANGED_CHANNEL` in the same statement as the insert, so a
+    // worker or `wait_for_blocking_deployments` caller blocked on
+    // `ChangeListener::notified` wakes up the moment this commits.
+    sqlx::query!(
+        r#"WITH inserted AS (
+             INSERT INTO deployment_runs (deployment_id, attempt_number, status)
+             VALUES ($1, 1, 'queued')
+             RETURNING id
+           ),
+           notified AS (
+             SELECT *, pg_notify('deploy_queue_changed', $1::text) FROM inserted
+           )
+           SELECT id FROM notified"#,
+        deployment_id
+    )
+    .fetch_one(client)
+    .instrumented("enqueue_deployment", ACQUIRE_TIMEOUT)
+    .await?;
+
     log::info!(
         "Successfully inserted deployment record: id={}, environment={}, cloud_provider={}, region={}, cell_index={}, component={}",
         deployment_id,
@@ -30,16 +89,199 @@ pub async fn enqueue_deployment(client: &Pool<Postgres>, deployment: Deployment)
         deployment.component
     );
 
-    // Write deployment ID to GitHub outputs
-    github::write_output("deployment-id", || Ok(deployment_id.to_string()))?;
+    #[cfg(feature = "metrics")]
+    counter!("deploy_queue.enqueued", "component" => deployment.component.clone()).increment(1);
+
+    if let Err(err) = subscribe::notify(
+        client,
+        &subscribe::DeploymentEvent {
+            deployment_id,
+            component: deployment.component.clone(),
+            cell: deployment.cell.clone(),
+            new_state: DeploymentStatus::Queued,
+        },
+    )
+    .await
+    {
+        warn!("Failed to publish deployment_events notification for deployment {deployment_id}: {err:#}");
+    }
+
+    notifier::notify_all(
+        notifiers,
+        &DeploymentEvent {
+            deployment_id,
+            component: deployment.component,
+            version: deployment.version,
+            location: deployment.cell.location(),
+            old_state: None,
+            new_state: DeploymentStatus::Queued,
+            note: deployment.note,
+        },
+    )
+    .await;
 
     Ok(deployment_id)
 }
 
+/// Enqueue `deployment`, atomically cancelling any existing non-terminal
+/// deployment for the same `(environment, cloud_provider, region,
+/// cell_index, component)` target as obsolete.
+///
+/// The insert and the supersede-scan happen in a single statement, so there
+/// is no window between "find the older deployments" and "cancel them" for
+/// one of them to start running or finish on its own - whatever the scan
+/// sees mid-statement is exactly what gets cancelled, consistently, against
+/// the same new deployment id. Returns the new deployment's id alongside
+/// the ids of whatever it superseded.
+pub async fn enqueue_deployment_superseding_older(
+    client: &Pool<Postgres>,
+    deployment: Deployment,
+    notifiers: &[Box<dyn Notifier>],
+) -> Result<(i64, Vec<i64>)> {
+    let row = sqlx::query!(
+        r#"WITH new_deployment AS (
+             INSERT INTO deployments (environment, cloud_provider, region, cell_index, component, version, url, note, concurrency_key)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+             RETURNING id
+           ),
+           new_run AS (
+             INSERT INTO deployment_runs (deployment_id, attempt_number, status)
+             SELECT id, 1, 'queued' FROM new_deployment
+           ),
+           latest_other_runs AS (
+             SELECT DISTINCT ON (d.id) r.id AS run_id, d.id AS deployment_id
+             FROM deployment_runs r
+             JOIN deployments d ON d.id = r.deployment_id
+             WHERE d.environment = $1
+               AND d.cloud_provider = $2
+               AND d.region = $3
+               AND d.cell_index = $4
+               AND d.component = $5
+               AND d.id <> (SELECT id FROM new_deployment)
+             ORDER BY d.id, r.attempt_number DESC
+           ),
+           superseded AS (
+             UPDATE deployment_runs AS r
+             SET status = 'cancelled',
+                 cancellation_timestamp = NOW(),
+                 cancellation_note = 'Superseded by deployment ' || (SELECT id FROM new_deployment)::text
+             FROM latest_other_runs
+             WHERE r.id = latest_other_runs.run_id
+               AND r.status NOT IN ('finished', 'cancelled', 'expired', 'timed_out', 'failed')
+             RETURNING latest_other_runs.deployment_id
+           )
+           SELECT
+             (SELECT id FROM new_deployment) AS "new_id!",
+             ARRAY(SELECT deployment_id FROM superseded) AS "superseded_ids!""#,
+        deployment.cell.environment,
+        deployment.cell.cloud_provider,
+        deployment.cell.region,
+        deployment.cell.index,
+        deployment.component,
+        deployment.version,
+        deployment.url,
+        deployment.note,
+        deployment.concurrency_key
+    )
+    .fetch_one(client)
+    .await?;
+
+    let deployment_id = row.new_id;
+    let superseded_ids = row.superseded_ids;
+
+    log::info!(
+        "Successfully inserted deployment record: id={}, environment={}, cloud_provider={}, region={}, cell_index={}, component={}",
+        deployment_id,
+        deployment.cell.environment,
+        deployment.cell.cloud_provider,
+        deployment.cell.region,
+        deployment.cell.index,
+        deployment.component
+    );
+
+    if !superseded_ids.is_empty() {
+        log::info!(
+            "Deployment {} superseded {} older deployment(s) for the same target: {:?}",
+            deployment_id,
+            superseded_ids.len(),
+            superseded_ids
+        );
+    }
+
+    for superseded_id in &superseded_ids {
+        if let Err(err) = subscribe::notify(
+            client,
+            &subscribe::DeploymentEvent {
+                deployment_id: *superseded_id,
+                component: deployment.component.clone(),
+                cell: deployment.cell.clone(),
+                new_state: DeploymentStatus::Cancelled,
+            },
+        )
+        .await
+        {
+            warn!("Failed to publish deployment_events notification for deployment {superseded_id}: {err:#}");
+        }
+
+        notifier::notify_all(
+            notifiers,
+            &DeploymentEvent {
+                deployment_id: *superseded_id,
+                component: deployment.component.clone(),
+                version: deployment.version.clone(),
+                location: deployment.cell.location(),
+                old_state: None,
+                new_state: DeploymentStatus::Cancelled,
+                note: Some(format!("Superseded by deployment {}", deployment_id)),
+            },
+        )
+        .await;
+
+        if let Err(err) = mutexbot::release_if_reserved(client, *superseded_id).await {
+            log::warn!(
+                "Failed to release reserved resource for superseded deployment {}: {}",
+                superseded_id,
+                err
+            );
+        }
+    }
+
+    if let Err(err) = subscribe::notify(
+        client,
+        &subscribe::DeploymentEvent {
+            deployment_id,
+            component: deployment.component.clone(),
+            cell: deployment.cell.clone(),
+            new_state: DeploymentStatus::Queued,
+        },
+    )
+    .await
+    {
+        warn!("Failed to publish deployment_events notification for deployment {deployment_id}: {err:#}");
+    }
+
+    notifier::notify_all(
+        notifiers,
+        &DeploymentEvent {
+            deployment_id,
+            component: deployment.component,
+            version: deployment.version,
+            location: deployment.cell.location(),
+            old_state: None,
+            new_state: DeploymentStatus::Queued,
+            note: deployment.note,
+        },
+    )
+    .await;
+
+    Ok((deployment_id, superseded_ids))
+}
+
 /// Cancel deployments with stale heartbeats
 async fn cancel_stale_heartbeat_deployments(
     client: &Pool<Postgres>,
     canceller_deployment_id: i64,
+    notifiers: &[Box<dyn Notifier>],
 ) -> Result<()> {
     let stale_deployments = fetch::stale_heartbeat_deployments(client, HEARTBEAT_TIMEOUT).await?;
 
@@ -48,34 +290,178 @@ async fn cancel_stale_heartbeat_deployments(
         canceller_deployment_id
     );
 
+    let heartbeat_policy = HeartbeatPolicy::default();
+
     for deployment in stale_deployments {
-        log::warn!(
-            "Cancelling deployment {} ({}, version={}) due to stale heartbeat: last seen {} ago at {}",
+        // `summary` already carries the component/version/elapsed text this
+        // logged unconditionally before; escalating the log level with
+        // `severity` means a `Page`-tier deployment actually surfaces above
+        // the noise instead of every stale cancellation looking the same.
+        let summary = deployment.summary(&heartbeat_policy);
+        match deployment.severity(&heartbeat_policy) {
+            Some(Severity::Page) => log::error!("Cancelling {summary}"),
+            Some(Severity::Alert) => log::warn!("Cancelling {summary}"),
+            Some(Severity::Warn) | None => log::info!("Cancelling {summary}"),
+        }
+
+        cancel::deployment(
+            client,
             deployment.id,
-            deployment.component,
-            deployment.version.as_deref().unwrap_or("unknown"),
-            deployment.time_since_heartbeat.format_human(),
-            deployment.heartbeat_timestamp.to_string(),
-        );
+            Some(cancellation_note.as_str()),
+            notifiers,
+        )
+        .await?;
+
+        #[cfg(feature = "metrics")]
+        counter!("deploy_queue.stale_heartbeat_cancellations", "component" => deployment.component.clone())
+            .increment(1);
 
-        cancel::deployment(client, deployment.id, Some(cancellation_note.as_str())).await?;
+        if let Err(err) = retry_stale_heartbeat_deployment(client, deployment.id, notifiers).await {
+            warn!(
+                "Failed to re-enqueue deployment {} after stale-heartbeat cancellation: {err:#}",
+                deployment.id
+            );
+        }
     }
 
     Ok(())
 }
 
+/// Backoff between automatic retries of a deployment cancelled for a stale
+/// heartbeat - doubles each `retry_attempt`, starting at 30s and capped at
+/// an hour.
+const STALE_HEARTBEAT_RETRY_BACKOFF: worker::BackoffPolicy = worker::BackoffPolicy::Exponential {
+    base: Duration::seconds(30),
+    factor: 2.0,
+    max: Duration::seconds(3600),
+};
+
+/// Re-enqueue a fresh deployment in place of `cancelled_id` - same cell,
+/// component, version, and `concurrency_key` - if it has retries left under
+/// its `max_retries`. A no-op if `cancelled_id` has already used up its
+/// retries, or is gone by the time this looks it up again. The retry carries
+/// `retry_of = Some(cancelled_id)`, an incremented `retry_attempt`, and a
+/// `not_before` delayed by `STALE_HEARTBEAT_RETRY_BACKOFF` so the new
+/// deployment doesn't run again the instant its predecessor's stale worker
+/// is reaped.
+///
+/// Doesn't carry forward a `--reserve-resource` MutexBot reservation:
+/// `Deployment` has no field for it (only `cli::Mode::Start` knows the
+/// resource/isolation-channel, and `cancel::deployment` already released it
+/// on the original's cancellation), so a retry of a resource-guarded
+/// deployment re-enqueues without re-reserving. Fine for the common
+/// heartbeat-loss case this targets, but worth knowing if this is ever
+/// pointed at a `reserve_resource` deployment.
+async fn retry_stale_heartbeat_deployment(
+    client: &Pool<Postgres>,
+    cancelled_id: i64,
+    notifiers: &[Box<dyn Notifier>],
+) -> Result<()> {
+    let Some(cancelled) = fetch::deployment(client, cancelled_id).await? else {
+        return Ok(());
+    };
+
+    if cancelled.retry_attempt >= cancelled.max_retries {
+        return Ok(());
+    }
+
+    let retry_attempt = cancelled.retry_attempt + 1;
+    let not_before =
+        time::OffsetDateTime::now_utc() + STALE_HEARTBEAT_RETRY_BACKOFF.delay(cancelled.retry_attempt);
+
+    let retry = Deployment {
+        cell: cancelled.cell.clone(),
+        component: cancelled.component.clone(),
+        version: cancelled.version.clone(),
+        url: cancelled.url.clone(),
+        note: cancelled.note.clone(),
+        concurrency_key: cancelled.concurrency_key.clone(),
+        max_retries: cancelled.max_retries,
+        retry_of: Some(cancelled_id),
+        retry_attempt,
+        not_before: Some(not_before),
+        ..Default::default()
+    };
+
+    let retry_id = enqueue_deployment(client, retry, notifiers).await?;
+    info!(
+        "Re-enqueued deployment {} as retry {} of {} ({}, version={}), not runnable before {}",
+        retry_id,
+        retry_attempt,
+        cancelled_id,
+        cancelled.component,
+        cancelled.version.as_deref().unwrap_or("unknown"),
+        not_before,
+    );
+
+    Ok(())
+}
+
+/// Wait for every deployment blocking `deployment_id` to clear, re-checking
+/// the moment one does instead of sleeping out the full `BUSY_RETRY` window
+/// every time. A `listen::ChangeListener` on `CHANGED_CHANNEL` wakes this
+/// loop early whenever any deployment starts, finishes, or is cancelled;
+/// `BUSY_RETRY` remains the ceiling on each iteration so a missed or
+/// coalesced notification (or a listener that failed to connect at all)
+/// never wedges the wait.
+///
+/// Deliberately doesn't scope the listener to this deployment's
+/// `concurrency_key` or fan it out through a shared per-key registry: what
+/// actually blocks a deployment is sharing a *cell*
+/// (environment/cloud_provider/region/cell_index - see
+/// `queries/blocking_deployments.sql`), not a `concurrency_key`, and this
+/// CLI runs one `wait_for_blocking_deployments` call per process, so a
+/// dedicated `PgListener` per call already gets the same effect a shared
+/// `DashMap<String, Arc<Notify>>` of per-key waiters would, without the
+/// bookkeeping a long-lived multi-waiter server would need it for. A
+/// coarser "wake on any change, then re-run the real query" is also simpler
+/// to keep correct than decoding a cell or concurrency key out of the
+/// notify payload and risking a stale filter silently suppressing a wakeup.
 pub async fn wait_for_blocking_deployments(
     pg_pool: &Pool<Postgres>,
     deployment_id: i64,
+    notifiers: &[Box<dyn Notifier>],
 ) -> Result<()> {
+    // Proxy for "time since enqueue": this is called immediately after
+    // `enqueue_deployment` in the CLI flow, so its own elapsed wall time
+    // approximates queue wait closely enough without a second DB round trip
+    // to re-fetch `created_at`.
+    let wait_start = tokio::time::Instant::now();
+    // Last cell the blocking-depth gauge below was set for, so it can be
+    // zeroed out for that same label once blockers clear - otherwise it
+    // would keep reporting the last nonzero depth forever after this
+    // function returns.
+    #[cfg(feature = "metrics")]
+    let mut last_blocking_cell: Option<String> = None;
+
+    let change_listener = match listen::ChangeListener::connect(pg_pool).await {
+        Ok(listener) => Some(listener),
+        Err(err) => {
+            warn!(
+                "Failed to start change listener; falling back to polling every {} seconds: {}",
+                BUSY_RETRY.as_secs(),
+                err
+            );
+            None
+        }
+    };
+
     loop {
         // Check for and cancel any deployments with stale heartbeats
-        cancel_stale_heartbeat_deployments(pg_pool, deployment_id).await?;
+        cancel_stale_heartbeat_deployments(pg_pool, deployment_id, notifiers).await?;
 
         let blocking_deployments = fetch::blocking_deployments(pg_pool, deployment_id).await?;
 
         if blocking_deployments.is_empty() {
             info!("No conflicting deployments found. Starting deployment...");
+            #[cfg(feature = "metrics")]
+            {
+                histogram!("deploy_queue.queue_wait_seconds")
+                    .record(wait_start.elapsed().as_secs_f64());
+                if let Some(cell) = last_blocking_cell.take() {
+                    gauge!("deploy_queue.blocking_deployments", "cell" => cell).set(0.0);
+                }
+            }
             break;
         } else {
             let blocking_ids: Vec<i64> = blocking_deployments
@@ -83,6 +469,16 @@ pub async fn wait_for_blocking_deployments(
                 .map(|b| b.deployment.id)
                 .collect();
 
+            // All of these block `deployment_id` by sharing its cell (see
+            // `queries/blocking_deployments.sql`), not a `concurrency_key`, so
+            // any one of them names the cell to label this gauge with.
+            #[cfg(feature = "metrics")]
+            {
+                let cell = blocking_deployments[0].deployment.cell.location();
+                gauge!("deploy_queue.blocking_deployments", "cell" => cell.clone()).set(blocking_deployments.len() as f64);
+                last_blocking_cell = Some(cell);
+            }
+
             // Calculate total ETA and per-component breakdown
             let mut total_remaining = Duration::ZERO;
             let mut component_times: std::collections::HashMap<String, Duration> =
@@ -138,48 +534,297 @@ pub async fn wait_for_blocking_deployments(
                 info!("  {}", blocking.summary()?);
             }
 
-            tokio::time::sleep(BUSY_RETRY).await;
+            match &change_listener {
+                Some(change_listener) => {
+                    tokio::select! {
+                        _ = change_listener.notified() => {}
+                        _ = tokio::time::sleep(BUSY_RETRY) => {}
+                    }
+                }
+                None => tokio::time::sleep(BUSY_RETRY).await,
+            }
         }
     }
     Ok(())
 }
 
-pub async fn show_deployment_info(client: &Pool<Postgres>, deployment_id: i64) -> Result<()> {
-    if let Some(deployment) = fetch::deployment(client, deployment_id).await? {
-        println!("{}", deployment.summary());
-    } else {
-        println!("Deployment with ID {} not found", deployment_id);
+pub async fn show_deployment_info(
+    client: &Pool<Postgres>,
+    deployment_id: i64,
+    format: crate::cli::OutputFormat,
+) -> Result<()> {
+    let deployment = fetch::deployment(client, deployment_id).await?;
+
+    match format {
+        crate::cli::OutputFormat::Json => {
+            let json_output = serde_json::to_string_pretty(&deployment)
+                .context("Failed to serialize deployment to JSON")?;
+            println!("{}", json_output);
+        }
+        crate::cli::OutputFormat::Text => match deployment {
+            Some(deployment) => println!("{}", deployment.summary()),
+            None => println!("Deployment with ID {} not found", deployment_id),
+        },
     }
+
     Ok(())
 }
 
-pub async fn start_deployment(client: &Pool<Postgres>, deployment_id: i64) -> Result<()> {
-    sqlx::query!(
-        "UPDATE deployments SET start_timestamp = NOW() WHERE id = $1",
-        deployment_id
+/// Transition a deployment to `running`, rejecting the move if it is already
+/// past `queued`/`blocked` (e.g. already running, finished, or cancelled).
+/// Fires `deploy_queue_changed` in the same statement, waking any
+/// `handler::listen::ChangeListener` waiter.
+pub async fn start_deployment(
+    client: &Pool<Postgres>,
+    deployment_id: i64,
+    notifiers: &[Box<dyn Notifier>],
+) -> Result<()> {
+    // Operates on the latest run of this job, not the job row itself - a job
+    // can have several runs once it's been retried.
+    let row = transition::transition(
+        client,
+        deployment_id,
+        &[DeploymentStatus::Queued, DeploymentStatus::Blocked],
+        DeploymentStatus::Running,
+        "start_deployment",
     )
-    .execute(client)
     .await?;
+
+    let Some(row) = row else {
+        anyhow::bail!(
+            "Deployment {} cannot be started (not found, or not queued/blocked)",
+            deployment_id
+        );
+    };
+
     log::info!("Deployment {} has been started", deployment_id);
+
+    let queue_wait_seconds = (row
+        .start_timestamp
+        .context("transition() to Running didn't set start_timestamp")?
+        - row.created_at)
+        .as_seconds_f64();
+
+    if let Err(err) = metrics::record(client, deployment_id, "queue_wait_seconds", queue_wait_seconds).await
+    {
+        warn!(
+            "Failed to record queue_wait_seconds metric for deployment {}: {}",
+            deployment_id, err
+        );
+    }
+
+    let cell = crate::model::Cell {
+        environment: row.environment,
+        cloud_provider: row.cloud_provider,
+        region: row.region,
+        index: row.cell_index,
+    };
+
+    if let Err(err) = subscribe::notify(
+        client,
+        &subscribe::DeploymentEvent {
+            deployment_id,
+            component: row.component.clone(),
+            cell: cell.clone(),
+            new_state: DeploymentStatus::Running,
+        },
+    )
+    .await
+    {
+        warn!("Failed to publish deployment_events notification for deployment {deployment_id}: {err:#}");
+    }
+
+    notifier::notify_all(
+        notifiers,
+        &DeploymentEvent {
+            deployment_id,
+            component: row.component,
+            version: row.version,
+            location: cell.location(),
+            old_state: Some(row.old_status),
+            new_state: DeploymentStatus::Running,
+            note: None,
+        },
+    )
+    .await;
+
     Ok(())
 }
 
-pub async fn finish_deployment(client: &Pool<Postgres>, deployment_id: i64) -> Result<()> {
-    sqlx::query!(
-        "UPDATE deployments SET finish_timestamp = NOW() WHERE id = $1",
-        deployment_id
+/// Transition a deployment to `finished`, rejecting the move unless it is
+/// currently `running`. Fires `deploy_queue_changed` in the same statement,
+/// waking any `handler::listen::ChangeListener` waiter - most notably
+/// `wait_for_blocking_deployments`, which no longer has to wait out a full
+/// `BUSY_RETRY` poll to notice a blocker finished.
+pub async fn finish_deployment(
+    client: &Pool<Postgres>,
+    deployment_id: i64,
+    notifiers: &[Box<dyn Notifier>],
+) -> Result<()> {
+    let row = transition::transition(
+        client,
+        deployment_id,
+        &[DeploymentStatus::Running],
+        DeploymentStatus::Finished,
+        "finish_deployment",
     )
-    .execute(client)
     .await?;
+
+    let Some(row) = row else {
+        anyhow::bail!(
+            "Deployment {} cannot be finished (not found, or not running)",
+            deployment_id
+        );
+    };
+
     log::info!("Deployment {} has been finished", deployment_id);
+
+    let deploy_duration_seconds = (row
+        .finish_timestamp
+        .context("transition() to Finished didn't set finish_timestamp")?
+        - row
+            .start_timestamp
+            .context("finished deployment had no start_timestamp")?)
+    .as_seconds_f64();
+
+    #[cfg(feature = "metrics")]
+    histogram!("deploy_queue.deploy_duration_seconds", "component" => row.component.clone())
+        .record(deploy_duration_seconds);
+
+    if let Err(err) =
+        metrics::record(client, deployment_id, "deploy_duration_seconds", deploy_duration_seconds).await
+    {
+        warn!(
+            "Failed to record deploy_duration_seconds metric for deployment {}: {}",
+            deployment_id, err
+        );
+    }
+
+    let cell = crate::model::Cell {
+        environment: row.environment,
+        cloud_provider: row.cloud_provider,
+        region: row.region,
+        index: row.cell_index,
+    };
+
+    if let Err(err) = subscribe::notify(
+        client,
+        &subscribe::DeploymentEvent {
+            deployment_id,
+            component: row.component.clone(),
+            cell: cell.clone(),
+            new_state: DeploymentStatus::Finished,
+        },
+    )
+    .await
+    {
+        warn!("Failed to publish deployment_events notification for deployment {deployment_id}: {err:#}");
+    }
+
+    notifier::notify_all(
+        notifiers,
+        &DeploymentEvent {
+            deployment_id,
+            component: row.component,
+            version: row.version,
+            location: cell.location(),
+            old_state: Some(row.old_status),
+            new_state: DeploymentStatus::Finished,
+            note: None,
+        },
+    )
+    .await;
+
     Ok(())
 }
 
-/// Update the heartbeat timestamp for a deployment
+/// Open a new run against an existing job, preserving the history of earlier
+/// attempts instead of losing it to a fresh `enqueue_deployment`. The
+/// previous run must have reached a terminal state first.
+pub async fn retry_deployment(
+    client: &Pool<Postgres>,
+    deployment_id: i64,
+    notifiers: &[Box<dyn Notifier>],
+) -> Result<i64> {
+    let latest = sqlx::query!(
+        r#"SELECT attempt_number, status AS "status: DeploymentStatus"
+           FROM deployment_runs
+           WHERE deployment_id = $1
+           ORDER BY attempt_number DESC
+           LIMIT 1"#,
+        deployment_id
+    )
+    .fetch_optional(client)
+    .await?;
+
+    let Some(latest) = latest else {
+        anyhow::bail!("Deployment {} not found", deployment_id);
+    };
+
+    if !latest.status.is_terminal() {
+        anyhow::bail!(
+            "Deployment {} cannot be retried (latest run is not yet terminal)",
+            deployment_id
+        );
+    }
+
+    let next_attempt = latest.attempt_number + 1;
+    let run = sqlx::query!(
+        "INSERT INTO deployment_runs (deployment_id, attempt_number, status) VALUES ($1, $2, 'queued') RETURNING id",
+        deployment_id,
+        next_attempt
+    )
+    .fetch_one(client)
+    .await?;
+
+    let job = sqlx::query!(
+        "SELECT component, version, environment, cloud_provider, region, cell_index FROM deployments WHERE id = $1",
+        deployment_id
+    )
+    .fetch_one(client)
+    .await?;
+
+    log::info!(
+        "Deployment {} has a new run: attempt {}",
+        deployment_id,
+        next_attempt
+    );
+
+    notifier::notify_all(
+        notifiers,
+        &DeploymentEvent {
+            deployment_id,
+            component: job.component,
+            version: job.version,
+            location: crate::model::Cell {
+                environment: job.environment,
+                cloud_provider: job.cloud_provider,
+                region: job.region,
+                index: job.cell_index,
+            }
+            .location(),
+            old_state: None,
+            new_state: DeploymentStatus::Queued,
+            note: Some(format!("Retry: attempt {next_attempt}")),
+        },
+    )
+    .await;
+
+    Ok(run.id)
+}
+
+/// Update the heartbeat timestamp on the latest run of a deployment.
 /// This is the core function that can be called from anywhere (e.g., as a background task)
 pub async fn update_heartbeat(client: &Pool<Postgres>, deployment_id: i64) -> Result<()> {
     sqlx::query!(
-        "UPDATE deployments SET heartbeat_timestamp = NOW() WHERE id = $1",
+        r#"UPDATE deployment_runs
+           SET heartbeat_timestamp = NOW()
+           WHERE id = (
+               SELECT id FROM deployment_runs
+               WHERE deployment_id = $1
+               ORDER BY attempt_number DESC
+               LIMIT 1
+           )"#,
         deployment_id
     )
     .execute(client)
@@ -188,8 +833,17 @@ pub async fn update_heartbeat(client: &Pool<Postgres>, deployment_id: i64) -> Re
     Ok(())
 }
 
-/// Run heartbeat in a loop with periodic intervals until terminated
-pub async fn run_heartbeat_loop(client: &Pool<Postgres>, deployment_id: i64) -> Result<()> {
+/// Run heartbeat in a loop with periodic intervals until `shutdown` fires.
+///
+/// On shutdown, flushes one last heartbeat before returning `Ok(())` instead
+/// of letting the caller abort the task mid-`UPDATE` - `HeartbeatHandle` is
+/// the intended way to drive this cooperatively; see its docs for why a bare
+/// `JoinHandle::abort()` isn't enough to stop this cleanly.
+pub async fn run_heartbeat_loop(
+    client: &Pool<Postgres>,
+    deployment_id: i64,
+    shutdown: CancellationToken,
+) -> Result<()> {
     info!(
         "Starting heartbeat loop for deployment {} (interval: {}s)",
         deployment_id,
@@ -203,7 +857,22 @@ pub async fn run_heartbeat_loop(client: &Pool<Postgres>, deployment_id: i64) ->
     interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
 
     loop {
-        interval.tick().await;
+        tokio::select! {
+            _ = interval.tick() => {}
+            () = shutdown.cancelled() => {
+                info!(
+                    "Heartbeat loop for deployment {} shutting down; flushing final heartbeat",
+                    deployment_id
+                );
+                if let Err(err) = update_heartbeat(client, deployment_id).await {
+                    warn!(
+                        "Failed to flush final heartbeat for deployment {}: {}",
+                        deployment_id, err
+                    );
+                }
+                return Ok(());
+            }
+        }
 
         let result = tokio::time::timeout(
             HEARTBEAT_UPDATE_TIMEOUT,
@@ -236,15 +905,84 @@ pub async fn run_heartbeat_loop(client: &Pool<Postgres>, deployment_id: i64) ->
     }
 }
 
-/// Start a background heartbeat loop; returns a JoinHandle so caller can abort it
-pub fn start_heartbeat_background(client: &Pool<Postgres>, deployment_id: i64) -> JoinHandle<()> {
-    let heartbeat_client = client.clone();
-    tokio::spawn(async move {
-        if let Err(err) = run_heartbeat_loop(&heartbeat_client, deployment_id).await {
-            warn!(
-                "Heartbeat loop exited for deployment {}: {}",
-                deployment_id, err
-            );
+/// Handle to a `run_heartbeat_loop` spawned via `spawn_heartbeat`. Dropping
+/// this without calling `shutdown` leaves the loop running in the background
+/// - call `shutdown` to stop it cleanly before reusing the pool it was given.
+pub struct HeartbeatHandle {
+    deployment_id: i64,
+    shutdown: CancellationToken,
+    join_handle: JoinHandle<Result<()>>,
+}
+
+/// Start a background heartbeat loop for `deployment_id`, returning a
+/// `HeartbeatHandle` for shutting it down later.
+pub fn spawn_heartbeat(client: Pool<Postgres>, deployment_id: i64) -> HeartbeatHandle {
+    let shutdown = CancellationToken::new();
+    let task_shutdown = shutdown.clone();
+    let join_handle = tokio::spawn(async move {
+        run_heartbeat_loop(&client, deployment_id, task_shutdown).await
+    });
+
+    HeartbeatHandle {
+        deployment_id,
+        shutdown,
+        join_handle,
+    }
+}
+
+impl HeartbeatHandle {
+    /// Signal the loop to stop, and wait up to `timeout` for it to flush its
+    /// final heartbeat and return. If it hasn't stopped by then, log a
+    /// warning and move on rather than block the caller indefinitely - the
+    /// loop already has `shutdown` cancelled, so it will still exit on its
+    /// own the next time it wakes.
+    pub async fn shutdown(self, timeout: std::time::Duration) -> Result<()> {
+        self.shutdown.cancel();
+
+        match tokio::time::timeout(timeout, self.join_handle).await {
+            Ok(join_result) => join_result.context("heartbeat loop panicked")?,
+            Err(_) => {
+                warn!(
+                    "Heartbeat loop for deployment {} did not stop within {:?} of shutdown",
+                    self.deployment_id, timeout
+                );
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The channel `handler::cancel` notifies on when it cancels a deployment.
+const CANCELLATION_CHANNEL: &str = "deployment_cancelled";
+
+/// Subscribe to cancellations as they happen, instead of polling for them.
+/// Yields the ID of each deployment as `handler::cancel::deployment`,
+/// `cancel::by_component_version`, or `cancel::by_location` cancels it -
+/// mirroring the `CancellationToken` a worker would otherwise have to poll
+/// for, so a running deploy process can abort promptly.
+///
+/// Holds its own dedicated connection (via `PgListener`) for the lifetime of
+/// the stream, separate from the pool used for ordinary queries.
+pub async fn watch_cancellations(client: &Pool<Postgres>) -> Result<impl Stream<Item = i64>> {
+    let mut listener = PgListener::connect_with(client).await?;
+    listener.listen(CANCELLATION_CHANNEL).await?;
+
+    Ok(futures::stream::unfold(listener, |mut listener| async move {
+        loop {
+            match listener.recv().await {
+                Ok(notification) => match notification.payload().parse::<i64>() {
+                    Ok(deployment_id) => return Some((deployment_id, listener)),
+                    Err(err) => warn!(
+                        "Ignoring malformed deployment_cancelled payload {:?}: {}",
+                        notification.payload(),
+                        err
+                    ),
+                },
+                Err(err) => {
+                    warn!("Cancellation listener stopped: {}", err);
+                    return None;
+                }
+            }
         }
-    })
+    }))
 }
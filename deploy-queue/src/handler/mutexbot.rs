@@ -0,0 +1,122 @@
+use anyhow::{Context, Result};
+use mutexbot_client::MutexbotBackend;
+use sqlx::{Pool, Postgres};
+
+/// Reserve `resource_name` exclusively for the latest run of `deployment_id`,
+/// then record what was reserved so `release_if_reserved` can find it again
+/// from Finish, Cancel, or Reap without being told a second time.
+pub async fn reserve(
+    client: &Pool<Postgres>,
+    deployment_id: i64,
+    resource_name: &str,
+    isolation_channel: Option<&str>,
+) -> Result<()> {
+    let api_key =
+        std::env::var("MUTEXBOT_API_KEY").context("MUTEXBOT_API_KEY is not set")?;
+    let backend = MutexbotBackend::new(&api_key).context("Failed to initialize MutexBot client")?;
+    let isolation_channel_owned = isolation_channel.map(str::to_owned);
+
+    let notifiers = mutexbot_client::notifier::from_env().context("Failed to configure notifiers")?;
+    let blocking_notify = mutexbot_client::blocking_notify_after_from_env()
+        .context("Failed to configure blocking-reservation alerts")?
+        .map(|after| mutexbot_client::BlockingNotify {
+            notifiers: &notifiers,
+            after,
+        });
+
+    mutexbot_client::reserve_exclusive(
+        &backend,
+        resource_name,
+        &isolation_channel_owned,
+        format!("deploy-queue deployment {deployment_id}"),
+        None,
+        blocking_notify.as_ref(),
+    )
+    .await
+    .with_context(|| {
+        format!("Failed to reserve resource {resource_name} for deployment {deployment_id}")
+    })?;
+
+    sqlx::query!(
+        r#"UPDATE deployment_runs
+           SET reserved_resource = $2, reservation_isolation_channel = $3
+           WHERE id = (
+               SELECT id FROM deployment_runs
+               WHERE deployment_id = $1
+               ORDER BY attempt_number DESC
+               LIMIT 1
+           )"#,
+        deployment_id,
+        resource_name,
+        isolation_channel
+    )
+    .execute(client)
+    .await?;
+
+    log::info!(
+        "Reserved resource {} for deployment {}",
+        resource_name,
+        deployment_id
+    );
+
+    Ok(())
+}
+
+/// Release whatever resource the latest run of `deployment_id` reserved, if
+/// any. A no-op for deployments that never reserved a resource.
+pub async fn release_if_reserved(client: &Pool<Postgres>, deployment_id: i64) -> Result<()> {
+    let row = sqlx::query!(
+        r#"SELECT reserved_resource, reservation_isolation_channel
+           FROM deployment_runs
+           WHERE deployment_id = $1
+           ORDER BY attempt_number DESC
+           LIMIT 1"#,
+        deployment_id
+    )
+    .fetch_optional(client)
+    .await?;
+
+    let Some(row) = row else {
+        return Ok(());
+    };
+    let Some(resource_name) = row.reserved_resource else {
+        return Ok(());
+    };
+
+    let api_key =
+        std::env::var("MUTEXBOT_API_KEY").context("MUTEXBOT_API_KEY is not set")?;
+    let backend = MutexbotBackend::new(&api_key).context("Failed to initialize MutexBot client")?;
+
+    mutexbot_client::release(
+        &backend,
+        &resource_name,
+        &row.reservation_isolation_channel,
+        false,
+    )
+    .await
+    .with_context(|| {
+        format!("Failed to release resource {resource_name} for deployment {deployment_id}")
+    })?;
+
+    sqlx::query!(
+        r#"UPDATE deployment_runs
+           SET reserved_resource = NULL, reservation_isolation_channel = NULL
+           WHERE id = (
+               SELECT id FROM deployment_runs
+               WHERE deployment_id = $1
+               ORDER BY attempt_number DESC
+               LIMIT 1
+           )"#,
+        deployment_id
+    )
+    .execute(client)
+    .await?;
+
+    log::info!(
+        "Released resource {} for deployment {}",
+        resource_name,
+        deployment_id
+    );
+
+    Ok(())
+}
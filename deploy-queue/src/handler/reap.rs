@@ -0,0 +1,113 @@
+use anyhow::Result;
+use sqlx::{Pool, Postgres};
+use time::Duration;
+
+use crate::{
+    model::{Cell, DeploymentStatus},
+    notifier::{self, DeploymentEvent, Notifier},
+    util::duration::DurationExt,
+};
+
+/// Expire deployments that have not reached a terminal state but whose
+/// heartbeat has gone stale for longer than `stale_after`.
+///
+/// This is a single atomic `UPDATE` so that concurrent invocations (e.g. two
+/// overlapping cron runs) never double-process the same row. Fires
+/// `deploy_queue_changed` for each expired deployment, so a
+/// `handler::wait_for_blocking_deployments` waiter blocked on one of them
+/// notices right away instead of only after its next `BUSY_RETRY` timeout.
+pub async fn stale_deployments(
+    client: &Pool<Postgres>,
+    stale_after: Duration,
+    notifiers: &[Box<dyn Notifier>],
+) -> Result<u64> {
+    let pg_interval = stale_after.to_pg_interval()?;
+
+    let rows = sqlx::query!(
+        r#"WITH latest_runs AS (
+             SELECT DISTINCT ON (deployment_id) id, deployment_id, status, heartbeat_timestamp
+             FROM deployment_runs
+             ORDER BY deployment_id, attempt_number DESC
+           ),
+           updated AS (
+             UPDATE deployment_runs AS r
+             SET status = 'expired',
+                 cancellation_timestamp = NOW(),
+                 cancellation_note = 'Reaped: heartbeat stale for longer than ' || $1::interval
+             FROM latest_runs, deployments AS d
+             WHERE r.id = latest_runs.id
+               AND d.id = latest_runs.deployment_id
+               AND latest_runs.heartbeat_timestamp < NOW() - $1
+               AND latest_runs.status IN ('queued', 'blocked', 'running')
+             RETURNING
+               d.id,
+               latest_runs.status AS old_status,
+               d.component,
+               d.version,
+               d.environment,
+               d.cloud_provider,
+               d.region,
+               d.cell_index
+           ),
+           notified AS (
+             SELECT *, pg_notify('deploy_queue_changed', id::text) FROM updated
+           )
+           SELECT
+             id,
+             old_status AS "old_status: DeploymentStatus",
+             component,
+             version,
+             environment,
+             cloud_provider,
+             region,
+             cell_index
+           FROM notified"#,
+        pg_interval
+    )
+    .fetch_all(client)
+    .await?;
+
+    let reaped = rows.len() as u64;
+    if reaped > 0 {
+        log::warn!(
+            "Reaped {} deployment(s) with a stale heartbeat (older than {})",
+            reaped,
+            stale_after.format_human()
+        );
+    }
+
+    for row in rows {
+        notifier::notify_all(
+            notifiers,
+            &DeploymentEvent {
+                deployment_id: row.id,
+                component: row.component,
+                version: row.version,
+                location: Cell {
+                    environment: row.environment,
+                    cloud_provider: row.cloud_provider,
+                    region: row.region,
+                    index: row.cell_index,
+                }
+                .location(),
+                old_state: Some(row.old_status),
+                new_state: DeploymentStatus::Expired,
+                note: Some(format!(
+                    "Reaped: heartbeat stale for longer than {}",
+                    stale_after.format_human()
+                )),
+            },
+        )
+        .await;
+
+        if let Err(err) = super::mutexbot::release_if_reserved(client, row.id).await {
+            log::warn!(
+                "Failed to release reserved resource for deployment {}: {}",
+                row.id,
+                err
+            );
+        }
+    }
+
+    Ok(reaped)
+}
@@ -0,0 +1,124 @@
+use anyhow::Result;
+use sqlx::{Pool, Postgres};
+use time::Duration;
+use tokio::sync::watch;
+
+use crate::{
+    constants::REAPER_SWEEP_INTERVAL,
+    model::DeploymentStatus,
+    notifier::Notifier,
+    util::duration::DurationExt,
+};
+
+use super::worker::{reschedule_or_give_up, BackoffPolicy};
+
+/// Janitor for orphaned `running` deployments: a worker that crashes without
+/// cancelling or finishing its run otherwise leaves that run `running`
+/// forever, permanently blocking its `concurrency_key`. One sweep finds
+/// every `running` run whose heartbeat has gone stale for longer than
+/// `lease_timeout` and, via `reschedule_or_give_up`, requeues it with
+/// `backoff` - or, once it has lost its lease `max_attempts` times, moves it
+/// to the terminal `timed_out` status, freeing the key for the next claim.
+///
+/// Unlike `reap::stale_deployments` (which also expires queued/blocked runs
+/// that went stale before ever starting), this only targets runs that made
+/// it to `running` and then lost their lease - exposed separately from
+/// `run` so the state-transition tests can assert a single sweep's effect.
+/// `reschedule_or_give_up` itself fires `deploy_queue_changed` once a run
+/// times out (a terminal transition that frees its `concurrency_key`), so a
+/// `handler::wait_for_blocking_deployments` waiter notices right away
+/// instead of waiting out its `BUSY_RETRY` poll.
+pub async fn sweep_once(
+    client: &Pool<Postgres>,
+    lease_timeout: Duration,
+    backoff: BackoffPolicy,
+    max_attempts: i32,
+    notifiers: &[Box<dyn Notifier>],
+) -> Result<u64> {
+    let pg_interval = lease_timeout.to_pg_interval()?;
+
+    let stale = sqlx::query!(
+        r#"SELECT DISTINCT ON (deployment_id) deployment_id
+           FROM deployment_runs
+           WHERE status = 'running' AND heartbeat_timestamp < NOW() - $1::interval
+           ORDER BY deployment_id, attempt_number DESC"#,
+        pg_interval
+    )
+    .fetch_all(client)
+    .await?;
+
+    let reason = format!(
+        "Reaped: worker lease expired (no heartbeat for longer than {})",
+        lease_timeout.format_human()
+    );
+
+    let mut reaped = 0u64;
+    for row in stale {
+        let gave_up = reschedule_or_give_up(
+            client,
+            row.deployment_id,
+            &reason,
+            backoff,
+            max_attempts,
+            DeploymentStatus::TimedOut,
+            notifiers,
+        )
+        .await?;
+
+        reaped += 1;
+        if gave_up {
+            log::warn!(
+                "Deployment {} timed out after losing its worker lease {} time(s)",
+                row.deployment_id,
+                max_attempts
+            );
+        } else {
+            log::warn!(
+                "Deployment {} lost its worker lease (no heartbeat for longer than {}); requeued with backoff",
+                row.deployment_id,
+                lease_timeout.format_human()
+            );
+        }
+    }
+
+    Ok(reaped)
+}
+
+/// Run the janitor in a loop, sweeping every `REAPER_SWEEP_INTERVAL` until
+/// `shutdown` is signalled, like cyclotron's janitor process. Checks
+/// `shutdown` between sweeps rather than being aborted mid-sweep, so a sweep
+/// already in flight is always allowed to finish its UPDATE before the
+/// caller's pool is closed.
+pub async fn run(
+    client: &Pool<Postgres>,
+    lease_timeout: Duration,
+    backoff: BackoffPolicy,
+    max_attempts: i32,
+    notifiers: &[Box<dyn Notifier>],
+    mut shutdown: watch::Receiver<bool>,
+) -> Result<()> {
+    log::info!(
+        "Starting reaper loop (lease timeout: {}, sweep interval: {}s)",
+        lease_timeout.format_human(),
+        REAPER_SWEEP_INTERVAL.as_secs()
+    );
+
+    let mut interval = tokio::time::interval(REAPER_SWEEP_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                if let Err(err) = sweep_once(client, lease_timeout, backoff, max_attempts, notifiers).await {
+                    log::warn!("Reaper sweep failed: {}", err);
+                }
+            }
+            result = shutdown.changed() => {
+                if result.is_err() || *shutdown.borrow() {
+                    log::info!("Reaper loop shutting down");
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use std::collections::HashSet;
+
+use anyhow::{Context, Result};
+use log::{info, warn};
+use sqlx::{Pool, Postgres, QueryBuilder};
+use time::{Duration, OffsetDateTime};
+use tokio_util::sync::CancellationToken;
+
+use crate::{handler::fetch, history::DeploymentHistory, model::DeploymentStatus};
+
+use super::list::notify_outliers;
+
+/// Which deployments `prune` deletes, alongside their `deployment_runs` rows,
+/// once their terminal timestamp is older than `retention_window` - see
+/// `run`. Inspired by the retention-mode knobs retention-focused job queues
+/// (e.g. fang/backie) expose, rather than inventing a bespoke policy shape.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetentionMode {
+    /// Never delete deployment rows - a tick only evaluates outliers.
+    KeepForever,
+    /// Delete deployments whose latest run is `finished`.
+    RemoveFinished,
+    /// Delete deployments whose latest run is any terminal status
+    /// (`finished`, `cancelled`, `expired`, `timed_out`, `failed`).
+    RemoveAll,
+}
+
+impl RetentionMode {
+    fn statuses(self) -> Vec<DeploymentStatus> {
+        match self {
+            RetentionMode::KeepForever => Vec::new(),
+            RetentionMode::RemoveFinished => vec![DeploymentStatus::Finished],
+            RetentionMode::RemoveAll => std::iter::once(DeploymentStatus::Finished)
+                .chain(DeploymentStatus::cancellation_like())
+                .collect(),
+        }
+    }
+}
+
+/// Delete every deployment - along with its `deployment_runs`,
+/// `deployment_metrics`, and `deployment_activities` rows, none of which
+/// cascade on their own - whose latest run matches one of `mode`'s statuses
+/// and whose terminal timestamp (finish or cancellation) is older than
+/// `retention_window`. Returns the number of deployments removed; a no-op
+/// under `RetentionMode::KeepForever`.
+pub async fn prune(client: &Pool<Postgres>, mode: RetentionMode, retention_window: Duration) -> Result<u64> {
+    let statuses = mode.statuses();
+    if statuses.is_empty() {
+        return Ok(0);
+    }
+
+    prune_statuses(client, statuses, retention_window).await
+}
+
+/// One-shot equivalent of `prune`, for `Mode::Prune` - meant to be invoked
+/// as a scheduled GitHub Action step rather than run continuously the way
+/// `Mode::Retention`'s `run` loop is. Unlike `RetentionMode`, which shares
+/// one `retention_window` across however many statuses `mode` selects, each
+/// variant here carries its own threshold, so e.g. cancelled/failed runs
+/// (usually low-value past a day) can be pruned sooner than finished ones
+/// (often kept longer for reporting).
+#[derive(Debug, Clone, Copy)]
+pub enum RetentionPolicy {
+    /// Don't delete anything.
+    KeepAll,
+    /// Delete deployments whose latest run is `finished` and older than
+    /// this.
+    RemoveFinishedAfter(Duration),
+    /// Delete deployments whose latest run is `cancelled`, `expired`,
+    /// `timed_out`, or `failed` (`DeploymentStatus::cancellation_like`), and
+    /// older than this.
+    RemoveCancelledAfter(Duration),
+}
+
+pub async fn prune_by_policy(client: &Pool<Postgres>, policy: RetentionPolicy) -> Result<u64> {
+    let (statuses, window) = match policy {
+        RetentionPolicy::KeepAll => return Ok(0),
+        RetentionPolicy::RemoveFinishedAfter(window) => (vec![DeploymentStatus::Finished], window),
+        RetentionPolicy::RemoveCancelledAfter(window) => {
+            (DeploymentStatus::cancellation_like().to_vec(), window)
+        }
+    };
+
+    prune_statuses(client, statuses, window).await
+}
+
+/// Shared by `prune` and `prune_by_policy`: delete every deployment - along
+/// with its `deployment_runs`, `deployment_metrics`, and
+/// `deployment_activities` rows, none of which cascade on their own - whose
+/// latest run's status is in `statuses` and whose terminal timestamp
+/// (finish or cancellation) is older than `retention_window`. Returns the
+/// number of deployments removed.
+///
+/// Candidate selection and both deletes happen in one statement via chained
+/// data-modifying CTEs, the same way `cancel::by_filter` scopes its update to
+/// a dynamically-built candidate set - so nothing can finish or get claimed
+/// in the window between "find old deployments" and "delete them."
+async fn prune_statuses(
+    client: &Pool<Postgres>,
+    statuses: Vec<DeploymentStatus>,
+    retention_window: Duration,
+) -> Result<u64> {
+    let cutoff = OffsetDateTime::now_utc() - retention_window;
+
+    let mut query = QueryBuilder::<Postgres>::new(
+        r#"WITH latest_runs AS (
+             SELECT DISTINCT ON (deployment_id)
+               deployment_id, status, COALESCE(finish_timestamp, cancellation_timestamp) AS terminal_at
+             FROM deployment_runs
+             ORDER BY deployment_id, attempt_number DESC
+           ),
+           candidates AS (
+             SELECT deployment_id FROM latest_runs
+             WHERE status = ANY("#,
+    );
+    query.push_bind(statuses);
+    query.push(") AND terminal_at < ");
+    query.push_bind(cutoff);
+    query.push(
+        r#"),
+           deleted_metrics AS (
+             DELETE FROM deployment_metrics WHERE deployment_id IN (SELECT deployment_id FROM candidates)
+           ),
+           deleted_activities AS (
+             DELETE FROM deployment_activities WHERE deployment_id IN (SELECT deployment_id FROM candidates)
+           ),
+           deleted_runs AS (
+             DELETE FROM deployment_runs WHERE deployment_id IN (SELECT deployment_id FROM candidates)
+           ),
+           deleted_deployments AS (
+             DELETE FROM deployments WHERE id IN (SELECT deployment_id FROM candidates)
+             RETURNING id
+           )
+           SELECT COUNT(*) FROM deleted_deployments"#,
+    );
+
+    let deleted: i64 = query
+        .build_query_scalar()
+        .fetch_one(client)
+        .await
+        .context("Failed to prune old deployments")?;
+
+    Ok(deleted as u64)
+}
+
+/// One tick of `run`: check for new outliers and prune old deployments.
+/// `seen_outlier_ids` is replaced with whatever's flagged this tick, so a
+/// deployment only gets alerted on again after it drops off (e.g. finishes)
+/// and later reappears as an outlier, rather than every single tick it stays
+/// slow.
+async fn tick_once(
+    client: &Pool<Postgres>,
+    history: &DeploymentHistory,
+    retention_mode: RetentionMode,
+    retention_window: Duration,
+    seen_outlier_ids: &mut HashSet<i64>,
+) -> Result<()> {
+    let outliers = fetch::outlier_deployments(client, history)
+        .await
+        .context("Failed to evaluate outliers")?;
+
+    let current_ids: HashSet<i64> = outliers.iter().map(|outlier| outlier.id).collect();
+    let new_outliers: Vec<_> = outliers
+        .into_iter()
+        .filter(|outlier| !seen_outlier_ids.contains(&outlier.id))
+        .collect();
+
+    if !new_outliers.is_empty() {
+        notify_outliers(&new_outliers)
+            .await
+            .context("Failed to notify about newly-flagged outliers")?;
+    }
+
+    // Only advance the seen set once notification succeeds, so a failed
+    // notify (e.g. a misconfigured notifier) leaves the newly-flagged
+    // outliers eligible for another attempt on the next tick instead of
+    // silently marking them seen.
+    *seen_outlier_ids = current_ids;
+
+    let pruned = prune(client, retention_mode, retention_window)
+        .await
+        .context("Failed to prune old deployments")?;
+    if pruned > 0 {
+        info!("Pruned {} old deployment(s) under retention mode {:?}", pruned, retention_mode);
+    }
+
+    Ok(())
+}
+
+/// Background janitor: on `tick_interval`, evaluates outliers (alerting on
+/// newly-flagged ones, deduped against the prior tick) and prunes deployments
+/// per `retention_mode`/`retention_window` so `deployments`/`deployment_runs`
+/// stay bounded. Runs until `shutdown` is cancelled.
+pub async fn run(
+    client: &Pool<Postgres>,
+    history: &DeploymentHistory,
+    tick_interval: std::time::Duration,
+    retention_mode: RetentionMode,
+    retention_window: Duration,
+    shutdown: CancellationToken,
+) -> Result<()> {
+    info!(
+        "Starting retention loop (tick interval: {}s, retention mode: {:?})",
+        tick_interval.as_secs(),
+        retention_mode
+    );
+
+    let mut interval = tokio::time::interval(tick_interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut seen_outlier_ids = HashSet::new();
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                if let Err(err) = tick_once(client, history, retention_mode, retention_window, &mut seen_outlier_ids).await {
+                    warn!("Retention tick failed: {:#}", err);
+                }
+            }
+            () = shutdown.cancelled() => {
+                info!("Retention loop shutting down");
+                return Ok(());
+            }
+        }
+    }
+}
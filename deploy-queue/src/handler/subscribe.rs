@@ -0,0 +1,180 @@
+use anyhow::{Context, Result};
+use futures::{Stream, StreamExt};
+use log::warn;
+use mutexbot_client::Backoff;
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgListener, Pool, Postgres};
+
+use crate::{
+    cli::OutputFormat,
+    constants::{MAX_LIST_LIMIT, SUBSCRIBE_RECONNECT_BASE, SUBSCRIBE_RECONNECT_CAP},
+    handler::{fetch, DeploymentFilter},
+    model::{Cell, DeploymentStatus},
+};
+
+/// The channel `enqueue_deployment` and the start/finish/cancel transitions
+/// notify on with a JSON-encoded `DeploymentEvent`, for `handler::subscribe`'s
+/// push-based consumers (a `watch` command, a dashboard) - distinct from
+/// `CHANGED_CHANNEL` (no payload, for waiters that just want to re-check
+/// their own condition) and `CANCELLATION_CHANNEL` (just a bare id).
+const EVENTS_CHANNEL: &str = "deployment_events";
+
+/// A deployment state transition, as broadcast over `EVENTS_CHANNEL` and
+/// consumed by `subscribe`. Unlike `notifier::DeploymentEvent` (which
+/// describes a transition to an already-in-process `Notifier` and is never
+/// serialized as a whole), this type round-trips through `NOTIFY`'s payload
+/// itself, so its shape is part of the wire contract between every
+/// `enqueue_deployment`/start/finish/cancel call site and every subscriber.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeploymentEvent {
+    pub deployment_id: i64,
+    pub component: String,
+    pub cell: Cell,
+    pub new_state: DeploymentStatus,
+}
+
+/// Best-effort NOTIFY of `event` on `EVENTS_CHANNEL`. Fired as its own
+/// statement right after the state-changing query commits, not inside the
+/// same CTE those queries already notify `deploy_queue_changed`/
+/// `deployment_cancelled` from - so a crash between the two could drop an
+/// event. `subscribe`'s catch-up fetch on reconnect exists to paper over
+/// exactly that kind of gap; callers should log and move on rather than
+/// fail the deployment operation over a missed notification.
+pub(crate) async fn notify(client: &Pool<Postgres>, event: &DeploymentEvent) -> Result<()> {
+    let payload = serde_json::to_string(event).context("Failed to serialize deployment event")?;
+    sqlx::query!("SELECT pg_notify($1, $2)", EVENTS_CHANNEL, payload)
+        .execute(client)
+        .await
+        .context("Failed to notify deployment_events")?;
+    Ok(())
+}
+
+/// Subscribe to deployment state transitions as they happen, instead of
+/// polling `active_outliers.sql` (or any other query) on a fixed interval.
+///
+/// Holds its own dedicated connection (via `PgListener`), separate from the
+/// pool used for ordinary queries, and reconnects with exponential backoff
+/// if that connection is lost. Every reconnect (including the first
+/// connection) is followed by a catch-up fetch of every non-terminal
+/// deployment, emitted as synthetic events ahead of any new ones - there's
+/// no event log to replay exact missed transitions from, but this still
+/// guarantees a subscriber's view converges to the true current state after
+/// an outage instead of staying stale on whatever it last saw.
+pub async fn subscribe(client: &Pool<Postgres>) -> Result<impl Stream<Item = DeploymentEvent>> {
+    let pool = client.clone();
+    let state = SubscribeState {
+        pool,
+        listener: None,
+        backoff: Backoff::new(SUBSCRIBE_RECONNECT_BASE, SUBSCRIBE_RECONNECT_CAP, usize::MAX),
+        pending: Vec::new(),
+    };
+
+    Ok(futures::stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(event) = state.pending.pop() {
+                return Some((event, state));
+            }
+
+            let Some(listener) = state.listener.as_mut() else {
+                match reconnect(&state.pool).await {
+                    Ok((listener, pending)) => {
+                        state.listener = Some(listener);
+                        state.backoff.reset();
+                        // Reversed so `pending.pop()` above yields them oldest-first.
+                        state.pending = pending.into_iter().rev().collect();
+                    }
+                    Err(err) => {
+                        warn!("Failed to (re)connect deployment_events listener: {err:#}");
+                        state.backoff.wait().await;
+                    }
+                }
+                continue;
+            };
+
+            match listener.recv().await {
+                Ok(notification) => match serde_json::from_str::<DeploymentEvent>(notification.payload()) {
+                    Ok(event) => return Some((event, state)),
+                    Err(err) => warn!(
+                        "Ignoring malformed deployment_events payload {:?}: {}",
+                        notification.payload(),
+                        err
+                    ),
+                },
+                Err(err) => {
+                    warn!("Deployment event listener connection lost, reconnecting: {err}");
+                    state.listener = None;
+                }
+            }
+        }
+    }))
+}
+
+/// Print every `subscribe` event to stdout until the listener is interrupted
+/// (Ctrl-C/SIGTERM), instead of polling `Outliers`/`Info` on a loop. Mirrors
+/// `list::outliers`'s `--format` handling: `Json` prints one compact
+/// document per line so a consumer can split on newlines, `Text` prints a
+/// human-readable summary built from `event.cell.location()`.
+pub async fn watch(client: &Pool<Postgres>, format: OutputFormat) -> Result<()> {
+    let mut events = Box::pin(subscribe(client).await.context("Failed to start deployment_events listener")?);
+
+    while let Some(event) = events.next().await {
+        match format {
+            OutputFormat::Json => {
+                let json_output = serde_json::to_string(&event).context("Failed to serialize deployment event")?;
+                println!("{}", json_output);
+            }
+            OutputFormat::Text => {
+                println!(
+                    "Deployment {} ({} @ {}) is now {}",
+                    event.deployment_id,
+                    event.component,
+                    event.cell.location(),
+                    event.new_state,
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+struct SubscribeState {
+    pool: Pool<Postgres>,
+    listener: Option<PgListener>,
+    backoff: Backoff,
+    pending: Vec<DeploymentEvent>,
+}
+
+/// Open a fresh `PgListener` on `EVENTS_CHANNEL` and fetch the catch-up
+/// batch of non-terminal deployments to emit ahead of whatever comes in
+/// live on the new connection.
+async fn reconnect(pool: &Pool<Postgres>) -> Result<(PgListener, Vec<DeploymentEvent>)> {
+    let mut listener = PgListener::connect_with(pool).await?;
+    listener.listen(EVENTS_CHANNEL).await?;
+
+    // Oldest-first and raised to MAX_LIST_LIMIT, same as `outlier_deployments`:
+    // the default (newest-first, DEFAULT_LIST_LIMIT) would silently drop
+    // older still-pending deployments past the cutoff on every reconnect,
+    // breaking the convergence guarantee above.
+    let catch_up = fetch::list(
+        pool,
+        DeploymentFilter {
+            statuses: vec![DeploymentStatus::Queued, DeploymentStatus::Blocked, DeploymentStatus::Running],
+            reverse: true,
+            limit: Some(MAX_LIST_LIMIT),
+            ..Default::default()
+        },
+    )
+    .await
+    .context("Failed to fetch catch-up deployments for deployment_events subscriber")?
+    .into_iter()
+    .map(|deployment| DeploymentEvent {
+        deployment_id: deployment.id,
+        component: deployment.component,
+        cell: deployment.cell,
+        new_state: deployment.status,
+    })
+    .collect();
+
+    Ok((listener, catch_up))
+}
@@ -0,0 +1,272 @@
+use anyhow::{bail, Result};
+use sqlx::{Pool, Postgres};
+use time::OffsetDateTime;
+
+use crate::{
+    constants::ACQUIRE_TIMEOUT,
+    model::DeploymentStatus,
+    util::instrument::Instrumented,
+};
+
+/// What a successful `transition` hands back: enough of the job and its
+/// timestamps for the caller to compute its own metrics (queue wait,
+/// deploy duration, ...) and fire its own `Notifier`/`subscribe::notify`
+/// events, without a second round-trip.
+pub struct TransitionRow {
+    pub old_status: DeploymentStatus,
+    pub created_at: OffsetDateTime,
+    pub start_timestamp: Option<OffsetDateTime>,
+    pub finish_timestamp: Option<OffsetDateTime>,
+    pub component: String,
+    pub version: Option<String>,
+    pub environment: String,
+    pub cloud_provider: String,
+    pub region: String,
+    pub cell_index: i32,
+}
+
+/// Move `deployment_id`'s latest run to `to`, atomically, but only if its
+/// current status is one of `from`. Centralizes what used to be a
+/// `WHERE latest.status IN (...)` guard hand-duplicated in `start_deployment`
+/// and `finish_deployment`: `DeploymentStatus::can_transition_to` is the one
+/// place the state machine's legal edges are written down, so every caller
+/// gets the same answer to "can a `running` deployment be finished?" instead
+/// of each handler re-deriving it in SQL.
+///
+/// `from` is still an explicit parameter (rather than this function trying
+/// every status and letting the database's `WHERE` reject the wrong ones)
+/// so a caller states its own precondition up front - `bail`s immediately,
+/// before issuing any query, if `to` isn't reachable from every status it
+/// listed, rather than just matching zero rows and leaving the caller to
+/// guess why.
+///
+/// Sets whichever timestamp column belongs to `to` (`start_timestamp` for
+/// `Running`, `finish_timestamp` for `Finished`, `cancellation_timestamp`
+/// for the cancellation-shaped terminal states) in the same `UPDATE` and
+/// fires `deploy_queue_changed`, same as the handlers this replaces.
+/// Returns `Ok(None)` if no run matched (not found, or already past `from`)
+/// rather than an error, leaving the "not found vs. wrong state" messaging
+/// to the caller, which knows which case it's in from context.
+///
+/// Doesn't cover `claim::claim_next`'s `queued -> running` move (that's a
+/// dequeue under `FOR UPDATE SKIP LOCKED`, not a single known id) or
+/// `cancel`/`reap`'s bulk transitions (those cancel every row matching a
+/// filter in one statement, not one id's current status) - both have
+/// different atomicity needs than "this one id, from this one status".
+///
+/// `operation` is the caller's own name (e.g. `"start_deployment"`), passed
+/// straight through to `Instrumented` - callers used to get their own
+/// `deploy_queue.query.*` series for free from each hand-written query, and
+/// centralizing the query here shouldn't merge those into one `"transition"`
+/// series and erase the start/finish split an operator's dashboard relies on.
+pub async fn transition(
+    client: &Pool<Postgres>,
+    deployment_id: i64,
+    from: &[DeploymentStatus],
+    to: DeploymentStatus,
+    operation: &'static str,
+) -> Result<Option<TransitionRow>> {
+    if from.is_empty() {
+        bail!("transition() requires at least one `from` status");
+    }
+    if let Some(illegal) = from.iter().find(|status| !status.can_transition_to(to)) {
+        bail!(
+            "Illegal transition requested for deployment {}: {:?} -> {:?}",
+            deployment_id, illegal, to
+        );
+    }
+
+    // The timestamp column to stamp depends on `to` and can't be bound as a
+    // query parameter, so unlike `deployment_id`/`to`/`from` it has to pick
+    // which static `sqlx::query!` call runs, rather than being spliced into
+    // the SQL text with `format!` - that traded away compile-time-checked
+    // SQL for a column name that's one of exactly three known values.
+    let row = if to == DeploymentStatus::Running {
+        sqlx::query!(
+            r#"WITH latest AS (
+                 SELECT id, status FROM deployment_runs
+                 WHERE deployment_id = $1
+                 ORDER BY attempt_number DESC
+                 LIMIT 1
+               ),
+               updated AS (
+                 UPDATE deployment_runs AS r
+                 SET status = $2, start_timestamp = NOW()
+                 FROM latest, deployments AS d
+                 WHERE r.id = latest.id
+                   AND d.id = $1
+                   AND latest.status = ANY($3)
+                 RETURNING
+                   latest.status AS "old_status: DeploymentStatus",
+                   r.created_at,
+                   r.start_timestamp,
+                   r.finish_timestamp,
+                   d.component,
+                   d.version,
+                   d.environment,
+                   d.cloud_provider,
+                   d.region,
+                   d.cell_index
+               ),
+               notified AS (
+                 SELECT *, pg_notify('deploy_queue_changed', $1::text) FROM updated
+               )
+               SELECT
+                 old_status AS "old_status!: DeploymentStatus",
+                 created_at,
+                 start_timestamp,
+                 finish_timestamp,
+                 component,
+                 version,
+                 environment,
+                 cloud_provider,
+                 region,
+                 cell_index
+               FROM notified"#,
+            deployment_id,
+            to as DeploymentStatus,
+            from as &[DeploymentStatus],
+        )
+        .fetch_optional(client)
+        .instrumented(operation, ACQUIRE_TIMEOUT)
+        .await?
+        .map(|row| TransitionRow {
+            old_status: row.old_status,
+            created_at: row.created_at,
+            start_timestamp: row.start_timestamp,
+            finish_timestamp: row.finish_timestamp,
+            component: row.component,
+            version: row.version,
+            environment: row.environment,
+            cloud_provider: row.cloud_provider,
+            region: row.region,
+            cell_index: row.cell_index,
+        })
+    } else if to == DeploymentStatus::Finished {
+        sqlx::query!(
+            r#"WITH latest AS (
+                 SELECT id, status FROM deployment_runs
+                 WHERE deployment_id = $1
+                 ORDER BY attempt_number DESC
+                 LIMIT 1
+               ),
+               updated AS (
+                 UPDATE deployment_runs AS r
+                 SET status = $2, finish_timestamp = NOW()
+                 FROM latest, deployments AS d
+                 WHERE r.id = latest.id
+                   AND d.id = $1
+                   AND latest.status = ANY($3)
+                 RETURNING
+                   latest.status AS "old_status: DeploymentStatus",
+                   r.created_at,
+                   r.start_timestamp,
+                   r.finish_timestamp,
+                   d.component,
+                   d.version,
+                   d.environment,
+                   d.cloud_provider,
+                   d.region,
+                   d.cell_index
+               ),
+               notified AS (
+                 SELECT *, pg_notify('deploy_queue_changed', $1::text) FROM updated
+               )
+               SELECT
+                 old_status AS "old_status!: DeploymentStatus",
+                 created_at,
+                 start_timestamp,
+                 finish_timestamp,
+                 component,
+                 version,
+                 environment,
+                 cloud_provider,
+                 region,
+                 cell_index
+               FROM notified"#,
+            deployment_id,
+            to as DeploymentStatus,
+            from as &[DeploymentStatus],
+        )
+        .fetch_optional(client)
+        .instrumented(operation, ACQUIRE_TIMEOUT)
+        .await?
+        .map(|row| TransitionRow {
+            old_status: row.old_status,
+            created_at: row.created_at,
+            start_timestamp: row.start_timestamp,
+            finish_timestamp: row.finish_timestamp,
+            component: row.component,
+            version: row.version,
+            environment: row.environment,
+            cloud_provider: row.cloud_provider,
+            region: row.region,
+            cell_index: row.cell_index,
+        })
+    } else if DeploymentStatus::cancellation_like().contains(&to) {
+        sqlx::query!(
+            r#"WITH latest AS (
+                 SELECT id, status FROM deployment_runs
+                 WHERE deployment_id = $1
+                 ORDER BY attempt_number DESC
+                 LIMIT 1
+               ),
+               updated AS (
+                 UPDATE deployment_runs AS r
+                 SET status = $2, cancellation_timestamp = NOW()
+                 FROM latest, deployments AS d
+                 WHERE r.id = latest.id
+                   AND d.id = $1
+                   AND latest.status = ANY($3)
+                 RETURNING
+                   latest.status AS "old_status: DeploymentStatus",
+                   r.created_at,
+                   r.start_timestamp,
+                   r.finish_timestamp,
+                   d.component,
+                   d.version,
+                   d.environment,
+                   d.cloud_provider,
+                   d.region,
+                   d.cell_index
+               ),
+               notified AS (
+                 SELECT *, pg_notify('deploy_queue_changed', $1::text) FROM updated
+               )
+               SELECT
+                 old_status AS "old_status!: DeploymentStatus",
+                 created_at,
+                 start_timestamp,
+                 finish_timestamp,
+                 component,
+                 version,
+                 environment,
+                 cloud_provider,
+                 region,
+                 cell_index
+               FROM notified"#,
+            deployment_id,
+            to as DeploymentStatus,
+            from as &[DeploymentStatus],
+        )
+        .fetch_optional(client)
+        .instrumented(operation, ACQUIRE_TIMEOUT)
+        .await?
+        .map(|row| TransitionRow {
+            old_status: row.old_status,
+            created_at: row.created_at,
+            start_timestamp: row.start_timestamp,
+            finish_timestamp: row.finish_timestamp,
+            component: row.component,
+            version: row.version,
+            environment: row.environment,
+            cloud_provider: row.cloud_provider,
+            region: row.region,
+            cell_index: row.cell_index,
+        })
+    } else {
+        bail!("transition() doesn't know which timestamp column belongs to {:?}", to)
+    };
+
+    Ok(row)
+}
@@ -0,0 +1,482 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use time::Duration;
+use tokio::time::MissedTickBehavior;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    model::Deployment,
+    notifier::Notifier,
+    util::duration::DurationExt,
+};
+use sqlx::{Pool, Postgres};
+
+use super::claim;
+
+/// How often the per-claim canceller polls `deployment_runs.cancellation_timestamp`
+/// for the run it is watching.
+const CANCELLATION_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Turns a claimed `Deployment` into a finished one. Implementations do the
+/// actual work (running a script, calling out to another service, ...);
+/// `handler::worker::run` only owns claiming, retrying, and bookkeeping.
+///
+/// `cancellation` is cancelled the moment any `handler::cancel::*` call
+/// stamps this run's `cancellation_timestamp` - whether that's a direct
+/// `cancel::deployment`, or a `cancel::by_component_version`/`by_location`
+/// sweep that happens to catch this run. Long-running implementations
+/// should poll or select on it and bail out promptly instead of running to
+/// completion.
+#[async_trait]
+pub trait DeploymentProcessor: Send + Sync {
+    async fn process(&self, deployment: &Deployment, cancellation: &CancellationToken) -> Result<()>;
+}
+
+/// Tuning knobs for `run`'s claim/retry loop.
+#[derive(Debug, Clone)]
+pub struct WorkerConfig {
+    /// Identifies this worker in `deployment_runs.run_host`; see `claim::claim_next`.
+    pub worker_id: String,
+    /// How often to poll for a claimable run when the queue is empty.
+    pub poll_interval: Duration,
+    /// How long to push `next_run_at` out by after a processor failure.
+    pub backoff: BackoffPolicy,
+    /// Number of processor failures a run tolerates before it is given up
+    /// on and moved to `failed`.
+    pub max_attempts: i32,
+}
+
+/// How `reschedule_or_give_up` (and so `fail_deployment`, and
+/// `reaper::sweep_once`) spaces out retries of a run, as a function of its
+/// (pre-increment) `attempts` count. Both variants are capped at `max` so a
+/// run that fails many times doesn't end up waiting arbitrarily long for its
+/// next attempt.
+#[derive(Debug, Clone, Copy)]
+pub enum BackoffPolicy {
+    /// `base + increment * attempts`.
+    Linear {
+        base: Duration,
+        increment: Duration,
+        max: Duration,
+    },
+    /// `base * factor ^ attempts`.
+    Exponential {
+        base: Duration,
+        factor: f64,
+        max: Duration,
+    },
+}
+
+impl BackoffPolicy {
+    /// Pure-Rust counterpart to `reschedule_or_give_up`'s in-SQL version of
+    /// this same formula, for callers that need the delay before issuing a
+    /// query rather than as part of one - see
+    /// `handler::retry_stale_heartbeat_deployment`, which computes a
+    /// deployment-level retry's `not_before` this way.
+    pub fn delay(&self, attempts: i32) -> Duration {
+        let (delay, max) = match *self {
+            BackoffPolicy::Linear { base, increment, max } => (base + increment * attempts, max),
+            BackoffPolicy::Exponential { base, factor, max } => (
+                Duration::seconds_f64(base.as_seconds_f64() * factor.powi(attempts)),
+                max,
+            ),
+        };
+        delay.min(max)
+    }
+}
+
+/// Claim runnable deployments one at a time and drive them through
+/// `processor`, until `shutdown` is cancelled (or `processor.process` panics
+/// / the pool is closed out from under it).
+///
+/// A processor failure does not fail this loop - the run is rescheduled per
+/// `config.backoff` (or given up on after `config.max_attempts`) via
+/// `fail_deployment`, and `run` moves on to polling for the next claimable
+/// run. A cancellation observed while `processor.process` is running is a
+/// separate, terminal outcome from a failure: `cancel::*` already moved the
+/// run to `cancelled` and handled its own notification and resource release,
+/// so `run` just stops - it never reschedules or re-notifies a cancelled run
+/// as `failed`.
+///
+/// Most callers should use `spawn`/`Handle::shutdown` instead of calling
+/// this directly - see their docs for why a bare `CancellationToken::new()`
+/// isn't enough to drain a worker cleanly.
+pub async fn run(
+    client: &Pool<Postgres>,
+    processor: &dyn DeploymentProcessor,
+    config: WorkerConfig,
+    notifiers: &[Box<dyn Notifier>],
+    shutdown: CancellationToken,
+) -> Result<()> {
+    let mut interval = tokio::time::interval(config.poll_interval.to_std_duration()?);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            () = shutdown.cancelled() => {
+                log::info!("Worker {} shutting down; no longer claiming new deployments", config.worker_id);
+                return Ok(());
+            }
+        }
+
+        let Some(deployment) = claim::claim_next(client, &config.worker_id, notifiers).await?
+        else {
+            continue;
+        };
+
+        let deployment_id = deployment.id;
+        // A child token: a DB-level cancellation of just this run cancels
+        // only the child, but `shutdown` cancelling cascades down to it too
+        // - so `processor.process` only needs to watch the one token it's
+        // handed, regardless of which of the two triggered it.
+        let cancellation = shutdown.child_token();
+        let canceller = spawn_cancellation_watcher(client.clone(), deployment_id, cancellation.clone());
+
+        let outcome = processor.process(&deployment, &cancellation).await;
+        canceller.abort();
+
+        if shutdown.is_cancelled() {
+            // Shutting down mid-processing: leave this run's row and the
+            // processor's outcome alone. `Handle::shutdown` resets any row
+            // still `running` for this worker once its timeout elapses, so
+            // a future worker re-claims it instead of racing this one's now
+            // moot result.
+            log::info!(
+                "Deployment {} finished processing during shutdown; leaving its outcome for the next worker",
+                deployment_id
+            );
+            return Ok(());
+        }
+
+        if cancellation.is_cancelled() {
+            // `cancel::*` already stamped this run `cancelled`, notified,
+            // and released any reservation - suppress the processor's own
+            // outcome entirely so a late success doesn't resurrect it as
+            // `finished` and a late failure doesn't demote it to `failed`.
+            log::info!(
+                "Deployment {} was cancelled while processing; not recording the processor's own outcome",
+                deployment_id
+            );
+            continue;
+        }
+
+        match outcome {
+            Ok(()) => {
+                super::finish_deployment(client, deployment_id, notifiers).await?;
+            }
+            Err(err) => {
+                log::warn!(
+                    "Processor failed for deployment {} (attempt {}): {}",
+                    deployment_id,
+                    deployment.attempt_number,
+                    err
+                );
+                fail_deployment(
+                    client,
+                    deployment_id,
+                    &format!("Processor failed: {err}"),
+                    config.backoff,
+                    config.max_attempts,
+                    notifiers,
+                )
+                .await?;
+            }
+        }
+    }
+}
+
+/// Handle to a `run` loop spawned via `spawn`. Dropping this without calling
+/// `shutdown` leaves the loop running in the background - call `shutdown`
+/// to drain it cleanly before the process exits.
+pub struct Handle {
+    worker_id: String,
+    shutdown: CancellationToken,
+    join_handle: tokio::task::JoinHandle<Result<()>>,
+    client: Pool<Postgres>,
+}
+
+/// Spawn `run` as a background task and return a `Handle` for shutting it
+/// down later. `processor` is `Arc`-wrapped since the task outlives this
+/// function call.
+pub fn spawn(
+    client: Pool<Postgres>,
+    processor: Arc<dyn DeploymentProcessor>,
+    config: WorkerConfig,
+    notifiers: Vec<Box<dyn Notifier>>,
+) -> Handle {
+    let worker_id = config.worker_id.clone();
+    let shutdown = CancellationToken::new();
+
+    let task_client = client.clone();
+    let task_shutdown = shutdown.clone();
+    let join_handle = tokio::spawn(async move {
+        run(&task_client, processor.as_ref(), config, &notifiers, task_shutdown).await
+    });
+
+    Handle {
+        worker_id,
+        shutdown,
+        join_handle,
+        client,
+    }
+}
+
+impl Handle {
+    /// Stop claiming new deployments, let any in-flight one finish on its
+    /// own, and wait up to `timeout` for the loop to exit. If it hasn't
+    /// exited by then, reset every row still `running` under this worker's
+    /// id back to `queued` (clearing its run host and start timestamp) so a
+    /// future worker can re-claim it instead of it staying wedged forever.
+    pub async fn shutdown(self, timeout: std::time::Duration) -> Result<()> {
+        self.shutdown.cancel();
+
+        match tokio::time::timeout(timeout, self.join_handle).await {
+            Ok(join_result) => join_result.context("worker loop panicked")?,
+            Err(_) => {
+                log::warn!(
+                    "Worker {} did not stop within {:?} of shutdown; resetting its still-running row(s)",
+                    self.worker_id,
+                    timeout
+                );
+                reset_stuck_running_runs(&self.client, &self.worker_id).await
+            }
+        }
+    }
+}
+
+/// Move every run still `running` under `worker_id` back to `queued`,
+/// clearing its start timestamp and run host, so a future worker can claim
+/// it again instead of it staying wedged after an unclean shutdown.
+async fn reset_stuck_running_runs(client: &Pool<Postgres>, worker_id: &str) -> Result<()> {
+    let reset = sqlx::query!(
+        r#"UPDATE deployment_runs
+           SET status = 'queued', start_timestamp = NULL, run_host = NULL
+           WHERE run_host = $1 AND status = 'running'"#,
+        worker_id
+    )
+    .execute(client)
+    .await?
+    .rows_affected();
+
+    if reset > 0 {
+        log::warn!(
+            "Reset {} run(s) stuck running under worker {} back to queued",
+            reset,
+            worker_id
+        );
+    }
+
+    Ok(())
+}
+
+/// Poll `deployment_runs.cancellation_timestamp` for `deployment_id`'s
+/// latest run and cancel `token` the moment it is set. Aborted (not
+/// gracefully stopped) once `process` returns, same as
+/// `handler::watch_cancellations`'s listener task - it never holds a
+/// transaction open, so there's nothing an abort can leave half-done.
+fn spawn_cancellation_watcher(
+    client: Pool<Postgres>,
+    deployment_id: i64,
+    token: CancellationToken,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CANCELLATION_POLL_INTERVAL);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+
+            let cancelled = sqlx::query!(
+                r#"SELECT cancellation_timestamp FROM deployment_runs
+                   WHERE deployment_id = $1
+                   ORDER BY attempt_number DESC
+                   LIMIT 1"#,
+                deployment_id
+            )
+            .fetch_optional(&client)
+            .await
+            .ok()
+            .flatten()
+            .is_some_and(|row| row.cancellation_timestamp.is_some());
+
+            if cancelled {
+                token.cancel();
+                return;
+            }
+        }
+    })
+}
+
+/// Record a failure (`reason`) against the deployment's latest run: bump
+/// `attempts`, push `next_run_at` out by `backoff` (evaluated against the
+/// attempt count before this increment), and give up (`status = 'failed'`)
+/// once `attempts` reaches `max_attempts`. Returns whether the deployment was
+/// given up on (`true`) or rescheduled for another attempt (`false`).
+///
+/// This is the library-level counterpart to `super::finish_deployment` for
+/// the unhappy path - `run`'s own processor-failure handling is just a
+/// caller of this, but it is equally usable by anything that observes a
+/// deployment failing outside of a `DeploymentProcessor` (e.g. a CI step
+/// reporting its own failure back).
+pub async fn fail_deployment(
+    client: &Pool<Postgres>,
+    deployment_id: i64,
+    reason: &str,
+    backoff: BackoffPolicy,
+    max_attempts: i32,
+    notifiers: &[Box<dyn Notifier>],
+) -> Result<bool> {
+    reschedule_or_give_up(
+        client,
+        deployment_id,
+        reason,
+        backoff,
+        max_attempts,
+        crate::model::DeploymentStatus::Failed,
+        notifiers,
+    )
+    .await
+}
+
+/// Shared core of `fail_deployment`: bump `attempts`, push `next_run_at` out
+/// by `backoff`, and give up (moving to `give_up_status`) once `attempts`
+/// reaches `max_attempts`. `give_up_status` lets callers outside this module
+/// (e.g. `reaper::sweep_once`, whose "gave up" outcome is `timed_out` rather
+/// than `failed`) reuse the same retry/backoff bookkeeping without being
+/// forced into `fail_deployment`'s terminal state.
+///
+/// Fires `deploy_queue_changed` itself once it gives up: that's a terminal
+/// transition that frees the deployment's `concurrency_key` for good, and
+/// every caller of this (the worker's own processor-failure path, the
+/// reaper) needs a `handler::wait_for_blocking_deployments` waiter to learn
+/// about that right away, not just on `reaper::sweep_once`'s next interval.
+/// Doesn't notify on a plain requeue - the deployment itself still owns its
+/// `concurrency_key` for the next attempt, so there's no new state for a
+/// waiter to react to yet.
+pub(crate) async fn reschedule_or_give_up(
+    client: &Pool<Postgres>,
+    deployment_id: i64,
+    reason: &str,
+    backoff: BackoffPolicy,
+    max_attempts: i32,
+    give_up_status: crate::model::DeploymentStatus,
+    notifiers: &[Box<dyn Notifier>],
+) -> Result<bool> {
+    let (base, max, exponential, factor, increment) = match backoff {
+        BackoffPolicy::Linear { base, increment, max } => (base, max, false, 1.0, increment),
+        BackoffPolicy::Exponential { base, factor, max } => (base, max, true, factor, Duration::ZERO),
+    };
+    let base = base.to_pg_interval()?;
+    let max = max.to_pg_interval()?;
+    let increment = increment.to_pg_interval()?;
+
+    let row = sqlx::query!(
+        r#"WITH latest AS (
+             SELECT id, attempts, status FROM deployment_runs
+             WHERE deployment_id = $1
+             ORDER BY attempt_number DESC
+             LIMIT 1
+           )
+           UPDATE deployment_runs AS r
+           SET attempts = latest.attempts + 1,
+               status = CASE
+                 WHEN latest.attempts + 1 >= $2 THEN $9
+                 ELSE 'queued'
+               END,
+               next_run_at = NOW() + LEAST(
+                 CASE
+                   WHEN $6 THEN $3::interval * POWER($7, latest.attempts)
+                   ELSE $3::interval + $8::interval * latest.attempts
+                 END,
+                 $4::interval
+               ),
+               cancellation_timestamp = CASE
+                 WHEN latest.attempts + 1 >= $2 THEN NOW()
+                 ELSE NULL
+               END,
+               cancellation_note = CASE
+                 WHEN latest.attempts + 1 >= $2 THEN $5
+                 ELSE NULL
+               END
+           FROM latest, deployments AS d
+           WHERE r.id = latest.id
+             AND d.id = $1
+             AND latest.status = 'running'
+           RETURNING
+             (latest.attempts + 1 >= $2) AS "gave_up!",
+             d.component,
+             d.version,
+             d.environment,
+             d.cloud_provider,
+             d.region,
+             d.cell_index"#,
+        deployment_id,
+        max_attempts,
+        base,
+        max,
+        reason,
+        exponential,
+        factor,
+        increment,
+        give_up_status as crate::model::DeploymentStatus,
+    )
+    .fetch_optional(client)
+    .await?;
+
+    let Some(row) = row else {
+        anyhow::bail!("Deployment {} has no running run to fail", deployment_id);
+    };
+
+    let new_state = if row.gave_up {
+        give_up_status
+    } else {
+        crate::model::DeploymentStatus::Queued
+    };
+
+    if row.gave_up {
+        log::warn!(
+            "Deployment {} exhausted its retry budget ({} attempts); giving up ({})",
+            deployment_id,
+            max_attempts,
+            give_up_status
+        );
+        if let Err(err) = super::mutexbot::release_if_reserved(client, deployment_id).await {
+            log::warn!(
+                "Failed to release reserved resource for deployment {}: {}",
+                deployment_id,
+                err
+            );
+        }
+
+        sqlx::query!(
+            "SELECT pg_notify('deploy_queue_changed', $1::text)",
+            deployment_id.to_string()
+        )
+        .execute(client)
+        .await?;
+    }
+
+    crate::notifier::notify_all(
+        notifiers,
+        &crate::notifier::DeploymentEvent {
+            deployment_id,
+            component: row.component,
+            version: row.version,
+            location: crate::model::Cell {
+                environment: row.environment,
+                cloud_provider: row.cloud_provider,
+                region: row.region,
+                index: row.cell_index,
+            }
+            .location(),
+            old_state: Some(crate::model::DeploymentStatus::Running),
+            new_state,
+            note: Some(reason.to_string()),
+        },
+    )
+    .await;
+
+    Ok(row.gave_up)
+}
@@ -0,0 +1,198 @@
+//! SQLite-backed duration history for `Outliers`, kept alongside the real
+//! (Postgres) queue rather than inside it: one row per `deployment_id`,
+//! written by `Start`/`Finish`/`Cancel`, read back as a per-`(component,
+//! environment, region)` baseline. Postgres remains the source of truth for
+//! a deployment's current state; this only remembers how long past runs
+//! took.
+
+use anyhow::{Context, Result};
+use sqlx::{Pool, Sqlite};
+use time::Duration;
+
+use crate::{constants::OUTLIER_MAD_EPSILON, model::Cell};
+
+/// Migrations for this store live separately from both `migrations/`
+/// (Postgres) and `migrations_sqlite/` (the `SqliteQueue` backend) - this
+/// schema serves neither of those, just the `deployment_durations` table.
+pub(crate) static MIGRATOR: sqlx::migrate::Migrator = sqlx::migrate!("migrations_history");
+
+pub struct DeploymentHistory {
+    pool: Pool<Sqlite>,
+}
+
+impl DeploymentHistory {
+    pub async fn connect(database_url: &str) -> Result<Self> {
+        let pool = Pool::<Sqlite>::connect(database_url)
+            .await
+            .with_context(|| format!("Failed to connect to deployment history database at {database_url}"))?;
+
+        MIGRATOR
+            .run(&pool)
+            .await
+            .context("Failed to run deployment history migrations")?;
+
+        Ok(Self { pool })
+    }
+
+    /// Record that `deployment_id` has started running. Upserts on
+    /// `deployment_id` and clears any previous `finish_timestamp`, so a
+    /// retried deployment (same ID, new run) starts a fresh history entry
+    /// instead of keeping a stale finish time from an earlier attempt.
+    pub async fn record_start(&self, deployment_id: i64, component: &str, cell: &Cell) -> Result<()> {
+        sqlx::query!(
+            r#"INSERT INTO deployment_durations
+                 (deployment_id, component, environment, cloud_provider, region, cell_index, start_timestamp, finish_timestamp)
+               VALUES (?, ?, ?, ?, ?, ?, CURRENT_TIMESTAMP, NULL)
+               ON CONFLICT (deployment_id) DO UPDATE SET
+                 component = excluded.component,
+                 environment = excluded.environment,
+                 cloud_provider = excluded.cloud_provider,
+                 region = excluded.region,
+                 cell_index = excluded.cell_index,
+                 start_timestamp = excluded.start_timestamp,
+                 finish_timestamp = NULL"#,
+            deployment_id,
+            component,
+            cell.environment,
+            cell.cloud_provider,
+            cell.region,
+            cell.index,
+        )
+        .execute(&self.pool)
+        .await
+        .with_context(|| format!("Failed to record start of deployment {deployment_id} in history"))?;
+
+        Ok(())
+    }
+
+    /// Record that `deployment_id` finished, so its duration counts toward
+    /// future baselines. A no-op if we never saw its start (e.g. it started
+    /// before this store existed).
+    pub async fn record_finish(&self, deployment_id: i64) -> Result<()> {
+        sqlx::query!(
+            "UPDATE deployment_durations SET finish_timestamp = CURRENT_TIMESTAMP WHERE deployment_id = ?",
+            deployment_id
+        )
+        .execute(&self.pool)
+        .await
+        .with_context(|| format!("Failed to record finish of deployment {deployment_id} in history"))?;
+
+        Ok(())
+    }
+
+    /// Drop `deployment_id`'s row - a cancelled run never finished, and this
+    /// store's whole purpose is "how long does this usually take," so there's
+    /// nothing useful to keep. Only called for single-deployment cancellation
+    /// today; a bulk cancel (by component/version or by location) leaves its
+    /// rows behind unfinished, which is harmless - `baseline` only counts rows
+    /// with a `finish_timestamp` - just not tidy.
+    pub async fn record_cancel(&self, deployment_id: i64) -> Result<()> {
+        sqlx::query!(
+            "DELETE FROM deployment_durations WHERE deployment_id = ?",
+            deployment_id
+        )
+        .execute(&self.pool)
+        .await
+        .with_context(|| format!("Failed to remove cancelled deployment {deployment_id} from history"))?;
+
+        Ok(())
+    }
+
+    /// Median and median absolute deviation (MAD) of finished-run durations
+    /// for `(component, environment, region)`. `None` if no finished run has
+    /// ever been recorded for that triple.
+    pub async fn baseline(&self, component: &str, environment: &str, region: &str) -> Result<Option<Baseline>> {
+        let rows = sqlx::query!(
+            r#"SELECT
+                 start_timestamp as "start_timestamp!",
+                 finish_timestamp as "finish_timestamp!"
+               FROM deployment_durations
+               WHERE component = ? AND environment = ? AND region = ? AND finish_timestamp IS NOT NULL"#,
+            component,
+            environment,
+            region,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .with_context(|| format!("Failed to load duration history for {component} in {environment}/{region}"))?;
+
+        // Worked in seconds-as-f64 rather than `time::Duration` throughout -
+        // median/MAD only need ordering and averaging, which floats give us
+        // directly instead of having to special-case odd/even counts by hand.
+        let seconds: Vec<f64> = rows
+            .into_iter()
+            .map(|row| (row.finish_timestamp - row.start_timestamp).as_seconds_f64())
+            .filter(|seconds| *seconds > 0.0)
+            .collect();
+
+        if seconds.is_empty() {
+            return Ok(None);
+        }
+
+        let (median, mad) = median_and_mad(&seconds);
+
+        Ok(Some(Baseline {
+            median: Duration::seconds_f64(median),
+            mad: Duration::seconds_f64(mad),
+            sample_count: seconds.len(),
+        }))
+    }
+}
+
+/// Median of an already-sorted slice.
+fn median_of(sorted: &[f64]) -> f64 {
+    let mid = sorted.len() / 2;
+    if sorted.len() % 2 == 0 {
+        (sorted[mid - 1] + sorted[mid]) / 2.0
+    } else {
+        sorted[mid]
+    }
+}
+
+/// Median and median absolute deviation (MAD) of `seconds`, which need not be
+/// sorted going in. Shared with `OutlierDeployment::detect`'s sample-based
+/// computation, which needs the same statistic outside of this module's
+/// SQLite-backed flow - both call sites filter out non-positive durations
+/// themselves first, since what counts as "not a real sample" differs (a
+/// finished run's duration here, a caller-supplied sample there).
+pub(crate) fn median_and_mad(seconds: &[f64]) -> (f64, f64) {
+    let mut seconds = seconds.to_vec();
+    seconds.sort_by(f64::total_cmp);
+    let median = median_of(&seconds);
+
+    let mut deviations: Vec<f64> = seconds.iter().map(|value| (value - median).abs()).collect();
+    deviations.sort_by(f64::total_cmp);
+    let mad = median_of(&deviations);
+
+    (median, mad)
+}
+
+/// A `(component, environment, region)` group's duration baseline, robust to
+/// the occasional very slow or very fast run in a way a mean/stddev isn't.
+#[derive(Debug, Clone, Copy)]
+pub struct Baseline {
+    pub median: Duration,
+    pub mad: Duration,
+    pub sample_count: usize,
+}
+
+impl Baseline {
+    /// Threshold past which a deployment counts as an outlier: `median + k *
+    /// 1.4826 * MAD`. `1.4826` is the constant that makes MAD a consistent
+    /// estimator of the standard deviation for normally-distributed data -
+    /// the same one `scipy.stats.median_abs_deviation` uses.
+    ///
+    /// Falls back to `OUTLIER_MAD_EPSILON` in place of the MAD-derived spread
+    /// when `mad` is zero (every finished run so far took the same time) -
+    /// otherwise the threshold would sit exactly on the median and flag the
+    /// very next run that's a hair slower, which isn't really an outlier.
+    pub fn threshold(&self, k: f64) -> Duration {
+        let mad_seconds = self.mad.as_seconds_f64();
+        let spread = if mad_seconds > 0.0 {
+            1.4826 * mad_seconds
+        } else {
+            OUTLIER_MAD_EPSILON.as_secs_f64()
+        };
+        Duration::seconds_f64(self.median.as_seconds_f64() + k * spread)
+    }
+}
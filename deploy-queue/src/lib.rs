@@ -1,11 +1,21 @@
 use anyhow::{Context, Result};
 use clap::Parser;
+use tokio_util::sync::CancellationToken;
+
+use crate::util::duration::DurationExt;
 
 pub mod cli;
 pub mod constants;
 pub mod handler;
+pub mod history;
 pub mod model;
+pub mod notifier;
+pub mod predict;
+pub mod prometheus;
+pub mod queue;
+pub mod stats;
 pub mod util;
+pub mod workflow;
 
 /// Main entry point for the deploy-queue application
 pub async fn main() -> Result<()> {
@@ -13,57 +23,153 @@ pub async fn main() -> Result<()> {
     env_logger::Builder::from_env(log_env).init();
     let args = cli::Cli::parse();
 
-    run_deploy_queue(args.mode, args.skip_migrations).await
+    run_deploy_queue(args.mode, args.skip_migrations, args.notifier_config, args.format).await
 }
 
-pub async fn run_deploy_queue(mode: cli::Mode, skip_migrations: bool) -> Result<()> {
+pub async fn run_deploy_queue(
+    mode: cli::Mode,
+    skip_migrations: bool,
+    notifier_config: Option<std::path::PathBuf>,
+    format: cli::OutputFormat,
+) -> Result<()> {
     // Create a connection pool for talking to the database
     let db_client = util::database::connect(skip_migrations).await?;
 
+    // A small SQLite side-store of past deployment durations, used only to
+    // build the baseline `Mode::List { entity: Outliers }` judges in-flight
+    // deployments against.
+    let history = util::database::connect_history()
+        .await
+        .context("Failed to connect to deployment history database")?;
+
+    // Loaded once per invocation; an absent --notifier-config yields an
+    // empty, no-op set.
+    let notifiers = notifier::load(notifier_config.as_deref())
+        .context("Failed to load notifier configuration")?;
+
     match mode {
         cli::Mode::Start(deployment) => {
-            // Insert deployment record into database
-            let deployment_id = handler::enqueue_deployment(&db_client, deployment.clone().into())
+            // Coalesce concurrent identical `Start` calls within this process
+            // onto a single leader, instead of racing duplicate inserts.
+            let deployment_id = handler::dedup::coalesce(&deployment, || async {
+                // Insert deployment record into database
+                let deployment_id = handler::enqueue_deployment(
+                    &db_client,
+                    deployment.clone().into(),
+                    &notifiers,
+                )
                 .await
                 .context("Faild to enqueue deployment")?;
 
-            // Start heartbeat loop in the background so we can abort it after starting
-            let heartbeat_handle = handler::start_heartbeat_background(&db_client, deployment_id);
+                // Start heartbeat loop in the background so we can stop it once started
+                let heartbeat_handle = handler::spawn_heartbeat(db_client.clone(), deployment_id);
 
-            // Wait for all blocking deployments to finish
-            handler::wait_for_blocking_deployments(&db_client, deployment_id)
-                .await
-                .with_context(|| format!("Failed to wait for blocks of {deployment_id}"))?;
+                // Wait for all blocking deployments to finish
+                handler::wait_for_blocking_deployments(&db_client, deployment_id, &notifiers)
+                    .await
+                    .with_context(|| format!("Failed to wait for blocks of {deployment_id}"))?;
 
-            // Mark deployment as started
-            handler::start_deployment(&db_client, deployment_id)
-                .await
-                .with_context(|| format!("Failed to start deployment {deployment_id}"))?;
+                // Hold the real-world mutex for exactly the `running` window:
+                // reserve it now, right before the deployment starts, so a
+                // failure here aborts the deploy instead of starting
+                // unprotected.
+                if let Some(resource) = deployment.reserve_resource.as_deref() {
+                    handler::mutexbot::reserve(
+                        &db_client,
+                        deployment_id,
+                        resource,
+                        deployment.isolation_channel.as_deref(),
+                    )
+                    .await
+                    .with_context(|| {
+                        format!("Failed to reserve resource {resource} for deployment {deployment_id}")
+                    })?;
+                }
+
+                // Mark deployment as started
+                handler::start_deployment(&db_client, deployment_id, &notifiers)
+                    .await
+                    .with_context(|| format!("Failed to start deployment {deployment_id}"))?;
+
+                // Record the start in the duration-history store so a future
+                // `Outliers` run has this deployment's timing to draw on.
+                history
+                    .record_start(
+                        deployment_id,
+                        &deployment.component,
+                        &model::Cell {
+                            environment: deployment.environment.to_string(),
+                            cloud_provider: deployment.cloud_provider.clone(),
+                            region: deployment.region.clone(),
+                            index: deployment.cell_index,
+                        },
+                    )
+                    .await
+                    .with_context(|| format!("Failed to record start of deployment {deployment_id} in history"))?;
 
-            // Stop the heartbeat loop now that the deployment has started
-            heartbeat_handle.abort();
-            let _ = heartbeat_handle.await;
+                // Stop the heartbeat loop now that the deployment has started
+                heartbeat_handle
+                    .shutdown(constants::HEARTBEAT_SHUTDOWN_TIMEOUT)
+                    .await
+                    .with_context(|| format!("Failed to stop heartbeat loop for deployment {deployment_id}"))?;
+
+                Ok(deployment_id)
+            })
+            .await
+            .context("Failed to start deployment")?;
+
+            // Write deployment ID to GitHub outputs, whether we led the
+            // deployment or coalesced onto an identical one already in flight.
+            util::github::write_output("deployment-id", || Ok(deployment_id.to_string()))?;
         }
         cli::Mode::Finish { deployment_id } => {
-            handler::finish_deployment(&db_client, deployment_id)
+            handler::finish_deployment(&db_client, deployment_id, &notifiers)
                 .await
                 .with_context(|| format!("Failed to finish deployment {deployment_id}"))?;
+
+            history
+                .record_finish(deployment_id)
+                .await
+                .with_context(|| format!("Failed to record finish of deployment {deployment_id} in history"))?;
+
+            handler::mutexbot::release_if_reserved(&db_client, deployment_id)
+                .await
+                .with_context(|| {
+                    format!("Failed to release reserved resource for deployment {deployment_id}")
+                })?;
         }
         cli::Mode::Cancel {
             cancellation_note,
             target,
         } => match target {
             cli::CancelTarget::Deployment { deployment_id } => {
-                handler::cancel::deployment(&db_client, deployment_id, cancellation_note)
+                handler::cancel::deployment(
+                    &db_client,
+                    deployment_id,
+                    cancellation_note,
+                    &notifiers,
+                )
+                .await
+                .with_context(|| format!("Failed to cancel deployment {deployment_id}"))?;
+
+                history
+                    .record_cancel(deployment_id)
                     .await
-                    .with_context(|| format!("Failed to cancel deployment {deployment_id}"))?;
+                    .with_context(|| format!("Failed to remove cancelled deployment {deployment_id} from history"))?;
             }
             cli::CancelTarget::Version { component, version } => {
+                // Unlike the single-deployment branch above, this doesn't
+                // call `history.record_cancel` for each affected deployment:
+                // `by_component_version` only reports a count, not which IDs
+                // it cancelled. Those rows are harmless to leave behind -
+                // `finish_timestamp` stays NULL forever, so `baseline` never
+                // counts them - just not cleaned up.
                 handler::cancel::by_component_version(
                     &db_client,
                     component,
                     version,
                     cancellation_note,
+                    &notifiers,
                 )
                 .await
                 .context("Failed to cancel deployments matching the given component and version")?;
@@ -74,6 +180,9 @@ pub async fn run_deploy_queue(mode: cli::Mode, skip_migrations: bool) -> Result<
                 region,
                 cell_index,
             } => {
+                // Same caveat as the `Version` arm above: `by_location` only
+                // reports a count, so the history rows for these deployments
+                // are left behind rather than cleaned up.
                 handler::cancel::by_location(
                     &db_client,
                     environment.as_ref(),
@@ -81,20 +190,48 @@ pub async fn run_deploy_queue(mode: cli::Mode, skip_migrations: bool) -> Result<
                     &region,
                     cell_index,
                     cancellation_note.as_deref(),
+                    &notifiers,
                 )
                 .await
                 .context("Failed to cancel deployments matching the given location")?;
             }
         },
         cli::Mode::Info { deployment_id } => {
-            handler::show_deployment_info(&db_client, deployment_id)
+            handler::show_deployment_info(&db_client, deployment_id, format)
                 .await
                 .with_context(|| format!("Failed to show info for deployment {deployment_id}"))?;
         }
+        cli::Mode::Metrics { since } => {
+            let since = since
+                .to_duration()
+                .context("Failed to convert --since to a time::Duration")?;
+            handler::list::metrics(&db_client, since)
+                .await
+                .context("Failed to report queue-health metrics")?;
+        }
+        cli::Mode::Stats { lookback } => {
+            let lookback = lookback
+                .to_duration()
+                .context("Failed to convert --lookback to a time::Duration")?;
+            handler::list::stats(&db_client, lookback, format)
+                .await
+                .context("Failed to report per-component deployment stats")?;
+        }
+        cli::Mode::Prometheus => {
+            handler::list::prometheus_snapshot(&db_client)
+                .await
+                .context("Failed to render Prometheus snapshot")?;
+        }
+        cli::Mode::Retry { deployment_id } => {
+            let run_id = handler::retry_deployment(&db_client, deployment_id, &notifiers)
+                .await
+                .with_context(|| format!("Failed to retry deployment {deployment_id}"))?;
+            util::github::write_output("run-id", || Ok(run_id.to_string()))?;
+        }
         cli::Mode::List {
             entity: cli::ListEntity::Outliers,
         } => {
-            handler::list::outliers(&db_client)
+            handler::list::outliers(&db_client, &history, format)
                 .await
                 .context("Failed to list outliers")?;
         }
@@ -105,26 +242,123 @@ pub async fn run_deploy_queue(mode: cli::Mode, skip_migrations: bool) -> Result<
                 .await
                 .context("Failed to list cells")?;
         }
-        cli::Mode::Heartbeat { target } => match target {
-            cli::HeartbeatTarget::Deployment { deployment_id } => {
-                handler::run_heartbeat_loop(&db_client, deployment_id)
-                    .await
-                    .with_context(|| {
-                        format!("Failed to run heartbeat loop for deployment {deployment_id}")
-                    })?;
-            }
-            cli::HeartbeatTarget::Url { url } => {
-                let deployment_id = handler::fetch::deployment_id_by_url(&db_client, &url)
+        cli::Mode::Heartbeat { target } => {
+            let deployment_id = match target {
+                cli::HeartbeatTarget::Deployment { deployment_id } => deployment_id,
+                cli::HeartbeatTarget::Url { url } => handler::fetch::deployment_id_by_url(&db_client, &url)
                     .await?
-                    .ok_or_else(|| anyhow::anyhow!("No deployment found with URL: {}", url))?;
+                    .ok_or_else(|| anyhow::anyhow!("No deployment found with URL: {}", url))?,
+            };
 
-                handler::run_heartbeat_loop(&db_client, deployment_id)
-                    .await
-                    .with_context(|| {
-                        format!("Failed to run heartbeat loop for deployment {deployment_id}")
-                    })?;
-            }
-        },
+            // This invocation owns the whole process and the pool below is
+            // never reused once the loop returns, so the same "nothing else
+            // would signal a graceful shutdown" token as the Reaper mode
+            // applies - Ctrl-C/SIGTERM tears the process down the usual way.
+            handler::run_heartbeat_loop(&db_client, deployment_id, CancellationToken::new())
+                .await
+                .with_context(|| format!("Failed to run heartbeat loop for deployment {deployment_id}"))?;
+
+            db_client.close().await;
+        }
+        cli::Mode::Watch => {
+            handler::subscribe::watch(&db_client, format)
+                .await
+                .context("Failed to watch deployment events")?;
+        }
+        cli::Mode::Retention {
+            tick_interval,
+            retention_mode,
+            retention_window,
+        } => {
+            let retention_mode = match retention_mode {
+                cli::RetentionMode::KeepForever => handler::retention::RetentionMode::KeepForever,
+                cli::RetentionMode::RemoveFinished => handler::retention::RetentionMode::RemoveFinished,
+                cli::RetentionMode::RemoveAll => handler::retention::RetentionMode::RemoveAll,
+            };
+            let retention_window = retention_window
+                .to_duration()
+                .context("Failed to convert --retention-window to a time::Duration")?;
+
+            // This invocation owns the whole process, same as Reap/Reaper -
+            // nothing else would signal a graceful shutdown, so Ctrl-C/SIGTERM
+            // tears the process down the usual way.
+            handler::retention::run(
+                &db_client,
+                &history,
+                tick_interval,
+                retention_mode,
+                retention_window,
+                CancellationToken::new(),
+            )
+            .await
+            .context("Retention loop exited")?;
+        }
+        cli::Mode::Reap { stale_after } => {
+            let stale_after = stale_after
+                .to_duration()
+                .context("Failed to convert --stale-after to a time::Duration")?;
+            let reaped = handler::reap::stale_deployments(&db_client, stale_after, &notifiers)
+                .await
+                .context("Failed to reap stale deployments")?;
+            util::github::write_output("reaped-count", || Ok(reaped.to_string()))?;
+            println!("Reaped {} deployment(s)", reaped);
+        }
+        cli::Mode::Prune { policy, after } => {
+            let after = after
+                .to_duration()
+                .context("Failed to convert --after to a time::Duration")?;
+            let policy = match policy {
+                cli::PrunePolicy::KeepAll => handler::retention::RetentionPolicy::KeepAll,
+                cli::PrunePolicy::RemoveFinishedAfter => {
+                    handler::retention::RetentionPolicy::RemoveFinishedAfter(after)
+                }
+                cli::PrunePolicy::RemoveCancelledAfter => {
+                    handler::retention::RetentionPolicy::RemoveCancelledAfter(after)
+                }
+            };
+
+            let pruned = handler::retention::prune_by_policy(&db_client, policy)
+                .await
+                .context("Failed to prune deployments")?;
+            util::github::write_output("pruned-count", || Ok(pruned.to_string()))?;
+            println!("Pruned {} deployment(s)", pruned);
+        }
+        cli::Mode::Reaper {
+            lease_timeout,
+            max_attempts,
+            base_backoff,
+            backoff_factor,
+            max_backoff,
+        } => {
+            let lease_timeout = lease_timeout
+                .to_duration()
+                .context("Failed to convert --lease-timeout to a time::Duration")?;
+            let backoff = handler::worker::BackoffPolicy::Exponential {
+                base: base_backoff
+                    .to_duration()
+                    .context("Failed to convert --base-backoff to a time::Duration")?,
+                factor: backoff_factor,
+                max: max_backoff
+                    .to_duration()
+                    .context("Failed to convert --max-backoff to a time::Duration")?,
+            };
+            // This invocation owns the whole process, so there's nothing
+            // else that would ever signal a graceful shutdown - keep the
+            // sender alive for the duration of the loop and let Ctrl-C/SIGTERM
+            // tear the process down the same way every other long-running
+            // Mode here does.
+            let (_shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+            handler::reaper::run(
+                &db_client,
+                lease_timeout,
+                backoff,
+                max_attempts,
+                &notifiers,
+                shutdown_rx,
+            )
+            .await
+            .context("Reaper loop exited")?;
+        }
     }
 
     Ok(())
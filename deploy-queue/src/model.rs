@@ -1,10 +1,10 @@
 use anyhow::{Context, Result};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use time::{Duration, OffsetDateTime};
 
 use crate::{cli::StartDeployment, util::duration::DurationExt};
 
-#[derive(Default, Debug, Clone, Serialize)]
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Cell {
     pub environment: String,
     pub cloud_provider: String,
@@ -12,9 +12,25 @@ pub struct Cell {
     pub index: i32,
 }
 
+impl Cell {
+    /// Compact "environment/provider/region/cell" identifier, used anywhere
+    /// a location needs to be printed or reported in one field.
+    pub fn location(&self) -> String {
+        format!(
+            "{}/{}/{}/{}",
+            self.environment, self.cloud_provider, self.region, self.index
+        )
+    }
+}
+
 // We don't read all of the fields
+//
+// A deployment is a job (intent: component, version, location) with one or
+// more runs (attempts). This struct is the flattened read-model most of the
+// codebase works with: job fields plus whichever run `attempt_number` was
+// loaded for it - almost always the latest one.
 #[allow(dead_code)]
-#[derive(Default, Debug, Clone)]
+#[derive(Default, Debug, Clone, Serialize)]
 pub struct Deployment {
     pub id: i64,
     pub cell: Cell,
@@ -22,15 +38,61 @@ pub struct Deployment {
     pub version: Option<String>,
     pub url: Option<String>,
     pub note: Option<String>,
+    #[serde(serialize_with = "serialize_timestamp")]
     pub start_timestamp: Option<OffsetDateTime>,
+    #[serde(serialize_with = "serialize_timestamp")]
     pub finish_timestamp: Option<OffsetDateTime>,
+    #[serde(serialize_with = "serialize_timestamp")]
     pub cancellation_timestamp: Option<OffsetDateTime>,
     pub cancellation_note: Option<String>,
     pub concurrency_key: Option<String>,
+    #[serde(serialize_with = "serialize_duration_humantime")]
     pub buffer_time: Duration,
+    pub status: DeploymentStatus,
+    /// Which attempt this row's run fields belong to (1-indexed).
+    pub attempt_number: i32,
+    /// Total number of runs this job has had so far, including this one.
+    pub run_count: i64,
+    /// How many times this deployment (not this run - see `attempt_number`)
+    /// may still be automatically re-enqueued as a fresh deployment after a
+    /// stale-heartbeat cancellation. Carried forward unchanged from a
+    /// deployment to its retries, so the chain gives up once it's been
+    /// retried this many times in total.
+    pub max_retries: i32,
+    /// The deployment this one replaced, if this is a retry created by
+    /// `handler::cancel_stale_heartbeat_deployments`. `None` for an original
+    /// deployment.
+    pub retry_of: Option<i64>,
+    /// How deep into a `retry_of` chain this deployment is (0 for an
+    /// original deployment, 1 for its first retry, and so on).
+    pub retry_attempt: i32,
+    /// When this deployment becomes eligible to run, if it's a retry
+    /// delayed by backoff. Checked by `fetch::blocking_deployments` (so a
+    /// not-yet-due retry doesn't count as blocking another deployment) and
+    /// `claim::claim_next` (so it isn't claimed early). `None` means
+    /// eligible immediately.
+    #[serde(serialize_with = "serialize_timestamp")]
+    pub not_before: Option<OffsetDateTime>,
 }
 
-/// Minimal view of a deployment for stale-heartbeat checks
+/// A single attempt at a deployment job, with its own lifecycle and
+/// heartbeat, so a failed run and its retry remain distinguishable.
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct DeploymentRun {
+    pub id: i64,
+    pub deployment_id: i64,
+    pub attempt_number: i32,
+    pub status: DeploymentStatus,
+    pub start_timestamp: Option<OffsetDateTime>,
+    pub finish_timestamp: Option<OffsetDateTime>,
+    pub cancellation_timestamp: Option<OffsetDateTime>,
+    pub cancellation_note: Option<String>,
+    pub heartbeat_timestamp: OffsetDateTime,
+    pub run_host: Option<String>,
+}
+
+/// Minimal view of a deployment run for stale-heartbeat checks
 pub struct StaleHeartbeatDeployment {
     pub id: i64,
     pub component: String,
@@ -39,6 +101,95 @@ pub struct StaleHeartbeatDeployment {
     pub time_since_heartbeat: Duration,
 }
 
+impl StaleHeartbeatDeployment {
+    /// The highest-severity tier `policy` has a threshold for at or below
+    /// `time_since_heartbeat`, or `None` if it hasn't even reached the
+    /// lowest tier yet.
+    pub fn severity(&self, policy: &HeartbeatPolicy) -> Option<Severity> {
+        policy.severity_for(self.time_since_heartbeat)
+    }
+
+    /// Render e.g. "deployment 42 component api heartbeat stale 7m30s
+    /// [ALERT]", or without a bracketed tag if `time_since_heartbeat` hasn't
+    /// reached `policy`'s lowest tier.
+    pub fn summary(&self, policy: &HeartbeatPolicy) -> String {
+        let mut summary = format!(
+            "deployment {} component {} heartbeat stale {}",
+            self.id,
+            self.component,
+            self.time_since_heartbeat.format_human()
+        );
+
+        if let Some(severity) = self.severity(policy) {
+            summary.push_str(&format!(" [{}]", severity.tag()));
+        }
+
+        summary
+    }
+}
+
+/// Ordered escalation tiers for a stale heartbeat, most severe first -
+/// `severity_for` walks them in this order so a deployment past the `Page`
+/// threshold is reported as `Page`, not also `Warn`/`Alert`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Warn,
+    Alert,
+    Page,
+}
+
+impl Severity {
+    /// Upper-case tag for `StaleHeartbeatDeployment::summary`, e.g. "ALERT".
+    fn tag(&self) -> &'static str {
+        match self {
+            Severity::Warn => "WARN",
+            Severity::Alert => "ALERT",
+            Severity::Page => "PAGE",
+        }
+    }
+}
+
+/// Elapsed-time thresholds for escalating a stale heartbeat from a quiet log
+/// line to something that pages someone. Each field is the minimum
+/// `time_since_heartbeat` for that tier to apply; unlike
+/// `constants::HEARTBEAT_TIMEOUT` (which only gates whether a deployment
+/// counts as stale at all, for `fetch::stale_heartbeat_deployments`), this
+/// layers further tiers on top of an already-stale deployment so an operator
+/// can tell "just went quiet" from "clearly dead."
+#[derive(Debug, Clone, Copy)]
+pub struct HeartbeatPolicy {
+    pub warn_after: Duration,
+    pub alert_after: Duration,
+    pub page_after: Duration,
+}
+
+impl Default for HeartbeatPolicy {
+    fn default() -> Self {
+        Self {
+            warn_after: Duration::minutes(2),
+            alert_after: Duration::minutes(5),
+            page_after: Duration::minutes(10),
+        }
+    }
+}
+
+impl HeartbeatPolicy {
+    /// The highest tier whose threshold `elapsed` has reached, or `None` if
+    /// `elapsed` hasn't reached even `warn_after` yet.
+    pub fn severity_for(&self, elapsed: Duration) -> Option<Severity> {
+        if elapsed >= self.page_after {
+            Some(Severity::Page)
+        } else if elapsed >= self.alert_after {
+            Some(Severity::Alert)
+        } else if elapsed >= self.warn_after {
+            Some(Severity::Warn)
+        } else {
+            None
+        }
+    }
+}
+
 impl Deployment {
     /// Generate a compact summary of this deployment's information
     pub fn summary(&self) -> String {
@@ -57,6 +208,20 @@ impl Deployment {
             self.version.as_deref().unwrap_or("unknown")
         );
 
+        if self.run_count > 1 {
+            summary.push_str(&format!(
+                " [run {} of {}]",
+                self.attempt_number, self.run_count
+            ));
+        }
+
+        if let Some(retry_of) = self.retry_of {
+            summary.push_str(&format!(
+                " [retry {} of {} of deployment {}]",
+                self.retry_attempt, self.max_retries, retry_of
+            ));
+        }
+
         if let Some(ref note) = self.note {
             summary.push_str(&format!(": ({})", note));
         }
@@ -81,6 +246,9 @@ impl From<StartDeployment> for Deployment {
             url,
             note,
             concurrency_key,
+            reserve_resource: _,
+            isolation_channel: _,
+            max_retries,
         }: StartDeployment,
     ) -> Self {
         Deployment {
@@ -95,11 +263,102 @@ impl From<StartDeployment> for Deployment {
             url,
             note,
             concurrency_key,
+            max_retries,
             ..Default::default()
         }
     }
 }
 
+/// Authoritative deployment status, backed by the `deployment_status` Postgres ENUM.
+///
+/// Unlike `DeploymentState` (which is derived from nullable timestamp columns),
+/// this is a real column so transitions can be validated and raced updates rejected.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, sqlx::Type, Serialize, Deserialize)]
+#[sqlx(type_name = "deployment_status", rename_all = "lowercase")]
+#[serde(rename_all = "snake_case")]
+pub enum DeploymentStatus {
+    #[default]
+    Queued,
+    Blocked,
+    Running,
+    Finished,
+    Cancelled,
+    Expired,
+    /// A `running` run whose worker went silent: its heartbeat lease expired
+    /// before it reached `finished`. `reaper::sweep_once` requeues it with
+    /// backoff first, same as a processor failure, and only moves it here
+    /// once it has done that `max_attempts` times - otherwise it would stay
+    /// stuck `running` forever.
+    TimedOut,
+    /// A run that exhausted `handler::worker::run`'s retry budget: its
+    /// `DeploymentProcessor` returned an error `max_attempts` times.
+    Failed,
+}
+
+impl std::fmt::Display for DeploymentStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeploymentStatus::Queued => write!(f, "queued"),
+            DeploymentStatus::Blocked => write!(f, "blocked"),
+            DeploymentStatus::Running => write!(f, "running"),
+            DeploymentStatus::Finished => write!(f, "finished"),
+            DeploymentStatus::Cancelled => write!(f, "cancelled"),
+            DeploymentStatus::Expired => write!(f, "expired"),
+            DeploymentStatus::TimedOut => write!(f, "timed_out"),
+            DeploymentStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+impl DeploymentStatus {
+    /// A status is terminal once reached no further transition is legal.
+    pub fn is_terminal(self) -> bool {
+        matches!(
+            self,
+            DeploymentStatus::Finished
+                | DeploymentStatus::Cancelled
+                | DeploymentStatus::Expired
+                | DeploymentStatus::TimedOut
+                | DeploymentStatus::Failed
+        )
+    }
+
+    /// The subset of terminal statuses that share `cancellation_timestamp`
+    /// rather than a dedicated timestamp column of their own - used by
+    /// `handler::transition` to pick that column and by
+    /// `handler::retention::RetentionPolicy::RemoveCancelledAfter` and
+    /// `RetentionMode::RemoveAll` to select candidates, so all three agree
+    /// on the same grouping.
+    pub fn cancellation_like() -> [DeploymentStatus; 4] {
+        [
+            DeploymentStatus::Cancelled,
+            DeploymentStatus::Expired,
+            DeploymentStatus::TimedOut,
+            DeploymentStatus::Failed,
+        ]
+    }
+
+    /// Whether moving from `self` to `next` is a legal state machine edge.
+    /// Terminal states cannot be left; cancellation is allowed from any
+    /// non-terminal state so it always wins a race against start/finish.
+    pub fn can_transition_to(self, next: DeploymentStatus) -> bool {
+        use DeploymentStatus::*;
+
+        if self.is_terminal() {
+            return false;
+        }
+
+        match next {
+            Cancelled | Expired => true,
+            Blocked | Running => matches!(self, Queued | Blocked),
+            Finished => matches!(self, Running),
+            Failed => matches!(self, Queued | Running),
+            TimedOut => matches!(self, Running),
+            Queued => false,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum DeploymentState {
     Queued,
@@ -143,6 +402,92 @@ impl DeploymentState {
     }
 }
 
+/// How many `Deployment`s fall into each `DeploymentState` - shared by
+/// `stats::component_stats` and `prometheus::render_state_gauges` so a
+/// future `DeploymentState` variant only needs handling in this one match.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DeploymentStateCounts {
+    pub queued: usize,
+    pub running: usize,
+    pub finished: usize,
+    pub cancelled: usize,
+}
+
+impl DeploymentStateCounts {
+    pub fn tally<'a>(deployments: impl IntoIterator<Item = &'a Deployment>) -> Self {
+        let mut counts = Self::default();
+        for deployment in deployments {
+            match DeploymentState::from_timestamps(
+                deployment.start_timestamp,
+                deployment.finish_timestamp,
+                deployment.cancellation_timestamp,
+            ) {
+                DeploymentState::Queued => counts.queued += 1,
+                DeploymentState::Running => counts.running += 1,
+                DeploymentState::Finished => counts.finished += 1,
+                DeploymentState::Cancelled => counts.cancelled += 1,
+            }
+        }
+        counts
+    }
+}
+
+/// Rational approximation of the probit function (the inverse standard-
+/// normal CDF), by Peter Acklam - accurate to about 1.15e-9, which is far
+/// more precision than a deployment duration estimate needs, but it's a
+/// single self-contained formula rather than a lookup table with a "what do
+/// we do for p99.5" gap. `BlockingDeployment::remaining_time_at_percentile`
+/// uses this to turn a percentile (e.g. `0.9` for p90) into the z-score
+/// `stddev_duration` gets scaled by - `probit(0.5) == 0.0`, `probit(0.9)
+/// ~= 1.2816`, `probit(0.95) ~= 1.6449`.
+fn probit(p: f64) -> f64 {
+    const LOW: f64 = 0.02425;
+    const A: [f64; 6] = [
+        -3.969683028665376e+01,
+        2.209460984245205e+02,
+        -2.759285104469687e+02,
+        1.383577518672690e+02,
+        -3.066479806614716e+01,
+        2.506628277459239e+00,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e+01,
+        1.615858368580409e+02,
+        -1.556989798598866e+02,
+        6.680131188771972e+01,
+        -1.328068155288572e+01,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-03,
+        -3.223964580411365e-01,
+        -2.400758277161838e+00,
+        -2.549732539343734e+00,
+        4.374664141464968e+00,
+        2.938163982698783e+00,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-03,
+        3.224671290700398e-01,
+        2.445134137142996e+00,
+        3.754408661907416e+00,
+    ];
+
+    if p < LOW {
+        let q = (-2.0 * p.ln()).sqrt();
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    } else if p <= 1.0 - LOW {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    } else {
+        let q = (-2.0 * (1.0 - p).ln()).sqrt();
+        -(((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    }
+}
+
 /// Represents a blocking deployment with analytics data for ETA calculation
 #[derive(Debug, Clone)]
 pub struct BlockingDeployment {
@@ -201,6 +546,40 @@ impl BlockingDeployment {
         }
     }
 
+    /// `remaining_time`'s percentile-aware sibling: models completion time
+    /// as `N(avg_duration, stddev_duration)` and estimates the `p`-th
+    /// percentile of remaining time (`avg + probit(p) * stddev`) instead of
+    /// just the mean, so a caller can render a band ("p50 - p90") rather
+    /// than a single optimistic point estimate. `p` is a percentile in
+    /// `(0, 1)`, e.g. `0.9` for p90.
+    ///
+    /// For a `Running` deployment this subtracts elapsed time from the
+    /// estimate and clamps at zero, same as `remaining_time`. Returns `None`
+    /// if `stddev_duration` is unavailable, or the deployment isn't
+    /// `Queued`/`Running` - a finished or cancelled deployment has no
+    /// "remaining time" distribution left to estimate.
+    pub fn remaining_time_at_percentile(&self, p: f64) -> Option<Duration> {
+        let avg = self.avg_duration?;
+        let stddev = self.stddev_duration?;
+        let estimate = avg + stddev * probit(p);
+
+        let state = DeploymentState::from_timestamps(
+            self.deployment.start_timestamp,
+            self.deployment.finish_timestamp,
+            self.deployment.cancellation_timestamp,
+        );
+
+        match state {
+            DeploymentState::Queued => Some(estimate.max(Duration::ZERO)),
+            DeploymentState::Running => {
+                let start_time = self.deployment.start_timestamp?;
+                let elapsed = OffsetDateTime::now_utc() - start_time;
+                Some((estimate - elapsed).max(Duration::ZERO))
+            }
+            DeploymentState::Finished | DeploymentState::Cancelled => None,
+        }
+    }
+
     /// Generate a compact summary with ETA information
     pub fn summary(&self) -> Result<String> {
         let state = DeploymentState::from_timestamps(
@@ -231,7 +610,21 @@ impl BlockingDeployment {
                 // Have analytics data for deployment time
                 let total_time = deployment_time + buffer_time;
                 if total_time > Duration::ZERO {
-                    summary.push_str(&format!(": ~{} remaining", total_time.format_human()));
+                    match (
+                        self.remaining_time_at_percentile(0.5),
+                        self.remaining_time_at_percentile(0.9),
+                    ) {
+                        (Some(p50), Some(p90)) => {
+                            summary.push_str(&format!(
+                                ": ~{} (p50) - ~{} (p90) remaining",
+                                (p50 + buffer_time).format_human(),
+                                (p90 + buffer_time).format_human()
+                            ));
+                        }
+                        _ => {
+                            summary.push_str(&format!(": ~{} remaining", total_time.format_human()));
+                        }
+                    }
                     if buffer_time > Duration::ZERO {
                         summary.push_str(&format!(
                             " (includes ~{} buffer)",
@@ -282,6 +675,57 @@ impl BlockingDeployment {
     }
 }
 
+/// Duration statistics for a (component, region, environment) group, read
+/// from `deployment_duration_analytics`. `avg_duration`/`stddev_duration`
+/// describe the typical case; the percentiles describe the tail, which is
+/// what matters when picking a deploy timeout.
+#[derive(Debug, Clone)]
+pub struct DurationAnalytics {
+    pub deployment_count: i64,
+    pub avg_duration: Duration,
+    pub stddev_duration: Duration,
+    pub p50_duration: Duration,
+    pub p90_duration: Duration,
+    pub p95_duration: Duration,
+    pub p99_duration: Duration,
+}
+
+/// Policy for how much history `deployment_duration_analytics` draws on:
+/// `lookback` bounds how far back finished runs are considered, and
+/// `row_cap` bounds how many of the most recent runs per group are kept
+/// once that window is applied. The default mirrors the window this view
+/// shipped with; a low-frequency component that rarely sees `row_cap`
+/// finished runs within `lookback` should widen `lookback` rather than
+/// raise `row_cap`, since a wider cap over a short window just admits
+/// staler, less representative runs.
+#[derive(Debug, Clone, Copy)]
+pub struct AnalyticsConfig {
+    pub lookback: Duration,
+    pub row_cap: i64,
+}
+
+impl Default for AnalyticsConfig {
+    fn default() -> Self {
+        Self {
+            lookback: Duration::days(90),
+            row_cap: 100,
+        }
+    }
+}
+
+/// Occupancy/throughput statistics for a (component, region, environment)
+/// group over a trailing window, read from `deployment_occupancy_analytics`.
+/// `occupancy_fraction` is how much of the window that group spent with at
+/// least one `running`/`blocked` deployment in flight (1.0 means it was
+/// never idle); `throughput_count` is how many runs finished in the window.
+#[derive(Debug, Clone)]
+pub struct OccupancyAnalytics {
+    pub occupancy_fraction: f64,
+    pub throughput_count: i64,
+    pub p50_duration: Option<Duration>,
+    pub p95_duration: Option<Duration>,
+}
+
 /// Represents a deployment that is taking substantially longer than expected
 #[derive(Debug, Clone, Serialize)]
 pub struct OutlierDeployment {
@@ -299,15 +743,122 @@ pub struct OutlierDeployment {
     pub version: Option<String>,
     #[serde(serialize_with = "serialize_duration_humantime")]
     pub current_duration: Duration,
+    /// The group's median finished-run duration, or the fixed fallback
+    /// threshold's baseline point (zero) when too few samples exist - see
+    /// `history::Baseline`.
     #[serde(serialize_with = "serialize_duration_humantime")]
-    pub avg_duration: Duration,
+    pub median_duration: Duration,
+    /// The group's median absolute deviation (MAD), or zero when too few
+    /// samples exist to build a baseline - see `history::Baseline`.
     #[serde(serialize_with = "serialize_duration_humantime")]
-    pub stddev_duration: Duration,
+    pub mad_duration: Duration,
+    /// The computed threshold `current_duration` had to exceed to flag this
+    /// deployment - `median_duration` plus `OUTLIER_MAD_K` MADs, or the flat
+    /// `OUTLIER_FALLBACK_THRESHOLD` when too few samples exist.
+    #[serde(serialize_with = "serialize_duration_humantime")]
+    pub threshold_duration: Duration,
+    /// How far `current_duration` is over the threshold that flagged this
+    /// deployment as an outlier.
+    #[serde(serialize_with = "serialize_duration_humantime")]
+    pub overage: Duration,
+}
+
+/// The constant that turns a MAD into a consistent estimator of the standard
+/// deviation for normally-distributed data (`1 / 1.4826`, the same constant
+/// `history::Baseline::threshold` applies in the other direction) - used by
+/// `OutlierDeployment::detect` to turn a raw MAD into the modified z-score
+/// Iglesias & Hoaglin's rule of thumb is expressed in.
+const MODIFIED_Z_SCORE_CONSTANT: f64 = 0.6745;
+
+/// Whether `value_seconds` is a modified-z-score outlier against a `median`/
+/// `mad` baseline (both in seconds) at `threshold` - the statistic
+/// `OutlierDeployment::detect` flags a single sample with, factored out so
+/// `stats::rollup` can judge every duration in a window against one
+/// median/MAD computed up front instead of paying `detect`'s per-call
+/// median/MAD sort for each one. Falls back to flagging anything strictly
+/// greater than `median` when `mad == 0` - see `detect`'s doc comment for
+/// why there's no fixed epsilon floor to fall back to instead.
+pub(crate) fn is_outlier_z_score(value_seconds: f64, median: f64, mad: f64, threshold: f64) -> bool {
+    if mad > 0.0 {
+        MODIFIED_Z_SCORE_CONSTANT * (value_seconds - median) / mad > threshold
+    } else {
+        value_seconds > median
+    }
+}
+
+impl OutlierDeployment {
+    /// Flag `deployment` as an outlier from a raw sample of recent durations,
+    /// rather than `history::Baseline`'s SQL-aggregated median/MAD - for
+    /// callers that already have comparable durations in hand (e.g. a
+    /// caller batching several groups' runs in memory) and would rather not
+    /// round-trip through `DeploymentHistory` per group. Computes the
+    /// median `m` and median absolute deviation (MAD) of `samples`, then
+    /// flags `current` when its modified z-score `0.6745 * (current - m) /
+    /// MAD` exceeds `threshold` (Iglesias & Hoaglin's rule of thumb
+    /// recommends `3.5`). Falls back to flagging anything strictly greater
+    /// than `m` when `MAD == 0` (every sample took the same time, so the
+    /// z-score is undefined) - same fallback shape as
+    /// `history::Baseline::threshold`'s `OUTLIER_MAD_EPSILON`, minus the
+    /// epsilon floor, since there's no fixed unit to floor it by here.
+    ///
+    /// Returns `None` when `samples` is empty (nothing to compare against)
+    /// or `current` doesn't clear the threshold.
+    pub fn detect(deployment: &Deployment, current: Duration, samples: &[Duration], threshold: f64) -> Option<Self> {
+        // Same non-positive filter `history::DeploymentHistory::baseline`
+        // applies to its finished-run durations - a zero or negative sample
+        // (clock skew, an instantly-cancelled run slipping through) would
+        // otherwise drag the median toward zero and make `current` look like
+        // an outlier by comparison to nothing real.
+        let seconds: Vec<f64> = samples
+            .iter()
+            .map(Duration::as_seconds_f64)
+            .filter(|seconds| *seconds > 0.0)
+            .collect();
+        if seconds.is_empty() {
+            return None;
+        }
+        let (median, mad) = crate::history::median_and_mad(&seconds);
+
+        let current_seconds = current.as_seconds_f64();
+        if !is_outlier_z_score(current_seconds, median, mad, threshold) {
+            return None;
+        }
+
+        // Per-spec fallback for a perfectly uniform history: with no spread to
+        // measure, flag anything strictly slower than the median rather than
+        // applying a fixed epsilon floor (contrast `history::Baseline::threshold`,
+        // which has a fixed-duration epsilon to fall back to because it always
+        // works in real seconds; this helper's `threshold` is a unitless z-score,
+        // so there's no analogous constant to borrow).
+        let threshold_seconds = if mad > 0.0 {
+            median + (threshold / MODIFIED_Z_SCORE_CONSTANT) * mad
+        } else {
+            median
+        };
+        let threshold_duration = Duration::seconds_f64(threshold_seconds);
+
+        Some(Self {
+            id: deployment.id,
+            env: deployment.cell.environment.clone(),
+            cloud_provider: deployment.cell.cloud_provider.clone(),
+            region: deployment.cell.region.clone(),
+            cell_index: deployment.cell.index,
+            component: deployment.component.clone(),
+            url: deployment.url.clone(),
+            note: deployment.note.clone(),
+            version: deployment.version.clone(),
+            current_duration: current,
+            median_duration: Duration::seconds_f64(median),
+            mad_duration: Duration::seconds_f64(mad),
+            threshold_duration,
+            overage: current - threshold_duration,
+        })
+    }
 }
 
 /// Convert time::Duration to std::time::Duration for humantime serialization
 /// Rounds to whole seconds for cleaner output
-fn serialize_duration_humantime<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+pub(crate) fn serialize_duration_humantime<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: serde::Serializer,
 {
@@ -316,3 +867,15 @@ where
         .map_err(|e| serde::ser::Error::custom(e.to_string()))?;
     humantime_serde::serialize(&std_duration, serializer)
 }
+
+/// Serialize an optional timestamp as its default `OffsetDateTime` display
+/// (an RFC 3339 string) or `null`, for `Deployment`'s `--format json` output.
+fn serialize_timestamp<S>(timestamp: &Option<OffsetDateTime>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match timestamp {
+        Some(timestamp) => serializer.collect_str(timestamp),
+        None => serializer.serialize_none(),
+    }
+}
@@ -0,0 +1,84 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use super::{DeploymentEvent, Notifier};
+use crate::model::DeploymentStatus;
+
+#[derive(Debug, Deserialize)]
+pub struct GithubStatusConfig {
+    /// "owner/repo" to post commit statuses to
+    pub repo: String,
+    pub token: String,
+}
+
+pub struct GithubStatusNotifier {
+    client: reqwest::Client,
+    config: GithubStatusConfig,
+}
+
+impl GithubStatusNotifier {
+    pub fn new(config: GithubStatusConfig) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            config,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct CommitStatusPayload<'a> {
+    state: &'a str,
+    description: &'a str,
+    context: &'a str,
+}
+
+fn github_state(status: DeploymentStatus) -> &'static str {
+    match status {
+        DeploymentStatus::Finished => "success",
+        DeploymentStatus::Cancelled | DeploymentStatus::Expired => "failure",
+        DeploymentStatus::Queued | DeploymentStatus::Blocked | DeploymentStatus::Running => {
+            "pending"
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for GithubStatusNotifier {
+    async fn notify(&self, event: &DeploymentEvent) -> Result<()> {
+        // `version` is expected to be the commit SHA (or a ref GitHub can
+        // resolve to one) of the component being deployed.
+        let Some(sha) = event.version.as_deref() else {
+            return Ok(());
+        };
+
+        let url = format!(
+            "https://api.github.com/repos/{}/statuses/{sha}",
+            self.config.repo
+        );
+        let description = event.summary();
+
+        let response = self
+            .client
+            .post(&url)
+            .bearer_auth(&self.config.token)
+            .header("User-Agent", "deploy-queue")
+            .json(&CommitStatusPayload {
+                state: github_state(event.new_state),
+                description: &description,
+                context: &format!("deploy-queue/{}", event.component),
+            })
+            .send()
+            .await
+            .context("Failed to send GitHub commit status request")?;
+
+        if !response.status().is_success() {
+            anyhow::bail!(
+                "GitHub commit status API returned status {}",
+                response.status()
+            );
+        }
+
+        Ok(())
+    }
+}
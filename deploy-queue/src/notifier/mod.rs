@@ -0,0 +1,108 @@
+pub mod github_status;
+pub mod slack;
+pub mod webhook;
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use crate::model::DeploymentStatus;
+
+/// A deployment state transition worth telling someone about.
+#[derive(Debug, Clone)]
+pub struct DeploymentEvent {
+    pub deployment_id: i64,
+    pub component: String,
+    pub version: Option<String>,
+    pub location: String,
+    pub old_state: Option<DeploymentStatus>,
+    pub new_state: DeploymentStatus,
+    pub note: Option<String>,
+}
+
+impl DeploymentEvent {
+    /// One-line human-readable summary, used by notifiers that just want a message body.
+    pub fn summary(&self) -> String {
+        let transition = match self.old_state {
+            Some(old_state) => format!("{old_state} -> {}", self.new_state),
+            None => self.new_state.to_string(),
+        };
+
+        let mut summary = format!(
+            "Deployment {} ({}@{}) in {}: {}",
+            self.deployment_id,
+            self.component,
+            self.version.as_deref().unwrap_or("unknown"),
+            self.location,
+            transition
+        );
+
+        if let Some(ref note) = self.note {
+            summary.push_str(&format!(" ({note})"));
+        }
+
+        summary
+    }
+}
+
+/// Something that wants to hear about deployment state transitions. A
+/// failure to deliver should never fail the deployment itself - callers log
+/// and move on.
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    async fn notify(&self, event: &DeploymentEvent) -> Result<()>;
+}
+
+/// One entry in a `--notifier-config` file.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum NotifierEntry {
+    Slack(slack::SlackConfig),
+    GithubCommitStatus(github_status::GithubStatusConfig),
+    Webhook(webhook::WebhookConfig),
+}
+
+#[derive(Debug, Deserialize)]
+struct NotifierConfigFile {
+    notifiers: Vec<NotifierEntry>,
+}
+
+/// Load the notifiers configured at `path`. Notifications are opt-in, so a
+/// missing `--notifier-config` flag (`path` is `None`) yields an empty set
+/// rather than an error.
+pub fn load(path: Option<&Path>) -> Result<Vec<Box<dyn Notifier>>> {
+    let Some(path) = path else {
+        return Ok(Vec::new());
+    };
+
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read notifier config at {}", path.display()))?;
+    let config: NotifierConfigFile = serde_json::from_str(&contents)
+        .with_context(|| format!("Failed to parse notifier config at {}", path.display()))?;
+
+    Ok(config
+        .notifiers
+        .into_iter()
+        .map(|entry| -> Box<dyn Notifier> {
+            match entry {
+                NotifierEntry::Slack(config) => Box::new(slack::SlackNotifier::new(config)),
+                NotifierEntry::GithubCommitStatus(config) => {
+                    Box::new(github_status::GithubStatusNotifier::new(config))
+                }
+                NotifierEntry::Webhook(config) => Box::new(webhook::WebhookNotifier::new(config)),
+            }
+        })
+        .collect())
+}
+
+/// Deliver `event` to every configured notifier. Individual failures are
+/// logged and otherwise swallowed so one broken webhook can't fail a deploy.
+pub async fn notify_all(notifiers: &[Box<dyn Notifier>], event: &DeploymentEvent) {
+    for notifier in notifiers {
+        if let Err(err) = notifier.notify(event).await {
+            log::warn!("Notifier failed to deliver deployment event: {err:#}");
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use super::{DeploymentEvent, Notifier};
+
+#[derive(Debug, Deserialize)]
+pub struct SlackConfig {
+    pub webhook_url: String,
+}
+
+pub struct SlackNotifier {
+    client: reqwest::Client,
+    config: SlackConfig,
+}
+
+impl SlackNotifier {
+    pub fn new(config: SlackConfig) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            config,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct SlackMessage<'a> {
+    text: &'a str,
+}
+
+#[async_trait]
+impl Notifier for SlackNotifier {
+    async fn notify(&self, event: &DeploymentEvent) -> Result<()> {
+        let summary = event.summary();
+        let response = self
+            .client
+            .post(&self.config.webhook_url)
+            .json(&SlackMessage { text: &summary })
+            .send()
+            .await
+            .context("Failed to send Slack webhook request")?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("Slack webhook returned status {}", response.status());
+        }
+
+        Ok(())
+    }
+}
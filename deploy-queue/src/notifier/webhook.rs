@@ -0,0 +1,64 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use super::{DeploymentEvent, Notifier};
+
+#[derive(Debug, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+}
+
+pub struct WebhookNotifier {
+    client: reqwest::Client,
+    config: WebhookConfig,
+}
+
+impl WebhookNotifier {
+    pub fn new(config: WebhookConfig) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            config,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct WebhookPayload<'a> {
+    deployment_id: i64,
+    component: &'a str,
+    version: Option<&'a str>,
+    location: &'a str,
+    old_state: Option<String>,
+    new_state: String,
+    note: Option<&'a str>,
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    async fn notify(&self, event: &DeploymentEvent) -> Result<()> {
+        let payload = WebhookPayload {
+            deployment_id: event.deployment_id,
+            component: &event.component,
+            version: event.version.as_deref(),
+            location: &event.location,
+            old_state: event.old_state.map(|state| state.to_string()),
+            new_state: event.new_state.to_string(),
+            note: event.note.as_deref(),
+        };
+
+        let response = self
+            .client
+            .post(&self.config.url)
+            .json(&payload)
+            .send()
+            .await
+            .context("Failed to send webhook request")?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("Webhook returned status {}", response.status());
+        }
+
+        Ok(())
+    }
+}
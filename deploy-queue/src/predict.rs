@@ -0,0 +1,86 @@
+use anyhow::{Context, Result};
+use sqlx::{Pool, Postgres};
+use time::OffsetDateTime;
+
+use crate::{
+    constants::ETA_CONFIDENCE_K,
+    handler::fetch,
+    model::{AnalyticsConfig, Deployment},
+};
+
+/// An ETA estimate for an in-flight deployment, derived from its group's
+/// `deployment_duration_analytics` row: `eta` is `start_timestamp + mu`,
+/// `lower_bound`/`upper_bound` are `eta ± k * sigma`, and `is_anomalous` is
+/// set once the deployment has already run longer than `upper_bound`.
+#[derive(Debug, Clone, Copy)]
+pub struct EtaPrediction {
+    pub eta: OffsetDateTime,
+    pub lower_bound: OffsetDateTime,
+    pub upper_bound: OffsetDateTime,
+    pub is_anomalous: bool,
+}
+
+/// Predict `deployment`'s finish time and flag it as anomalous if it has
+/// already overrun its confidence band.
+///
+/// Returns `None` (rather than fabricating a band) in either cold-start
+/// case: the group has fewer than 2 finished runs in
+/// `deployment_duration_analytics` - the view only reports groups that
+/// have *any* finished runs at all, so a `deployment_count` of exactly 1
+/// still means the standard deviation carries no information - or the
+/// reported `stddev_duration` is zero/negative, which would otherwise
+/// collapse or invert the band.
+///
+/// `deployment` must be currently running: `start_timestamp` set and
+/// `finish_timestamp` unset. `config` bounds the history `eta` draws on -
+/// widen it for a low-frequency component that rarely has `row_cap`
+/// finished runs within `lookback`.
+pub async fn eta(
+    client: &Pool<Postgres>,
+    deployment: &Deployment,
+    config: &AnalyticsConfig,
+) -> Result<Option<EtaPrediction>> {
+    let start_timestamp = deployment.start_timestamp.with_context(|| {
+        format!(
+            "Cannot predict an ETA for deployment {}: it has not started",
+            deployment.id
+        )
+    })?;
+    if deployment.finish_timestamp.is_some() {
+        anyhow::bail!(
+            "Cannot predict an ETA for deployment {}: it has already finished",
+            deployment.id
+        );
+    }
+
+    let Some(analytics) = fetch::duration_analytics(
+        client,
+        &deployment.component,
+        &deployment.cell.region,
+        &deployment.cell.environment,
+        config,
+    )
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    if analytics.deployment_count < 2 || analytics.stddev_duration <= time::Duration::ZERO {
+        return Ok(None);
+    }
+
+    let mu = analytics.avg_duration;
+    let band = analytics.stddev_duration * ETA_CONFIDENCE_K;
+
+    let eta = start_timestamp + mu;
+    let lower_bound = eta - band;
+    let upper_bound = eta + band;
+    let is_anomalous = OffsetDateTime::now_utc() > upper_bound;
+
+    Ok(Some(EtaPrediction {
+        eta,
+        lower_bound,
+        upper_bound,
+        is_anomalous,
+    }))
+}
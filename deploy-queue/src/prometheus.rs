@@ -0,0 +1,161 @@
+//! Prometheus text-exposition rendering of the live deployment picture,
+//! computed directly from already-fetched `Deployment`/`StaleHeartbeatDeployment`
+//! values - unlike the `metrics` facade used elsewhere in this crate (see
+//! `util::instrument`, `handler::mod`), which pushes point samples into
+//! whatever recorder the embedding binary installs, this builds a full
+//! snapshot on demand, for `cli::Mode::Prometheus` to print to stdout for a
+//! scrape job to capture without any recorder configured at all.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use crate::{
+    constants::DURATION_HISTOGRAM_BUCKETS_SECONDS,
+    model::{Deployment, DeploymentState, DeploymentStateCounts, StaleHeartbeatDeployment},
+};
+
+/// Render the Prometheus text-exposition format for one snapshot:
+/// `deploy_queue_deployments` (gauge, per `DeploymentState`),
+/// `deploy_queue_deployment_duration_seconds` (histogram of `Finished`
+/// durations, per component), `deploy_queue_cancellations_total` (counter,
+/// per component), `deploy_queue_blocking_deployments` (gauge, per cell -
+/// same label `handler::wait_for_blocking_deployments` sets it with via the
+/// `metrics` facade, but counting every still-active (`Queued`/`Running`)
+/// deployment occupying a cell rather than blockers of one specific target,
+/// since there's no standalone "every currently-blocking deployment" fetch
+/// to hand this a `&[BlockingDeployment]` - only `fetch::blocking_deployments`,
+/// which is scoped to one target deployment at a time) and
+/// `deploy_queue_stale_heartbeat_seconds` (gauge, one series per stale
+/// deployment).
+pub fn render(deployments: &[Deployment], stale: &[StaleHeartbeatDeployment]) -> String {
+    let mut out = String::new();
+
+    render_state_gauges(&mut out, deployments);
+    render_duration_histogram(&mut out, deployments);
+    render_cancellation_counter(&mut out, deployments);
+    render_blocking_gauge(&mut out, deployments);
+    render_stale_heartbeat_gauge(&mut out, stale);
+
+    out
+}
+
+fn render_state_gauges(out: &mut String, deployments: &[Deployment]) {
+    let counts = DeploymentStateCounts::tally(deployments);
+
+    let _ = writeln!(out, "# HELP deploy_queue_deployments Number of deployments currently in each state.");
+    let _ = writeln!(out, "# TYPE deploy_queue_deployments gauge");
+    let _ = writeln!(out, "deploy_queue_deployments{{state=\"queued\"}} {}", counts.queued);
+    let _ = writeln!(out, "deploy_queue_deployments{{state=\"running\"}} {}", counts.running);
+    let _ = writeln!(out, "deploy_queue_deployments{{state=\"finished\"}} {}", counts.finished);
+    let _ = writeln!(out, "deploy_queue_deployments{{state=\"cancelled\"}} {}", counts.cancelled);
+}
+
+fn render_duration_histogram(out: &mut String, deployments: &[Deployment]) {
+    // Same non-positive filter `history::DeploymentHistory::baseline` and
+    // `OutlierDeployment::detect` apply to their own duration samples.
+    let mut by_component: BTreeMap<&str, Vec<f64>> = BTreeMap::new();
+    for deployment in deployments {
+        if let (Some(start), Some(finish)) = (deployment.start_timestamp, deployment.finish_timestamp) {
+            let seconds = (finish - start).as_seconds_f64();
+            if seconds > 0.0 {
+                by_component.entry(deployment.component.as_str()).or_default().push(seconds);
+            }
+        }
+    }
+
+    let _ = writeln!(
+        out,
+        "# HELP deploy_queue_deployment_duration_seconds Duration of finished deployments, bucketed per component."
+    );
+    let _ = writeln!(out, "# TYPE deploy_queue_deployment_duration_seconds histogram");
+
+    for (component, durations) in &by_component {
+        let component = escape_label_value(component);
+
+        for &bound in DURATION_HISTOGRAM_BUCKETS_SECONDS {
+            let cumulative = durations.iter().filter(|&&d| d <= bound).count();
+            let _ = writeln!(
+                out,
+                "deploy_queue_deployment_duration_seconds_bucket{{component=\"{component}\",le=\"{bound}\"}} {cumulative}"
+            );
+        }
+        let total = durations.len();
+        let sum: f64 = durations.iter().sum();
+        let _ = writeln!(
+            out,
+            "deploy_queue_deployment_duration_seconds_bucket{{component=\"{component}\",le=\"+Inf\"}} {total}"
+        );
+        let _ = writeln!(out, "deploy_queue_deployment_duration_seconds_sum{{component=\"{component}\"}} {sum}");
+        let _ = writeln!(out, "deploy_queue_deployment_duration_seconds_count{{component=\"{component}\"}} {total}");
+    }
+}
+
+fn render_cancellation_counter(out: &mut String, deployments: &[Deployment]) {
+    let mut by_component: BTreeMap<&str, usize> = BTreeMap::new();
+    for deployment in deployments {
+        let state = DeploymentState::from_timestamps(
+            deployment.start_timestamp,
+            deployment.finish_timestamp,
+            deployment.cancellation_timestamp,
+        );
+        if state == DeploymentState::Cancelled {
+            *by_component.entry(deployment.component.as_str()).or_default() += 1;
+        }
+    }
+
+    let _ = writeln!(out, "# HELP deploy_queue_cancellations_total Number of cancelled deployments per component.");
+    let _ = writeln!(out, "# TYPE deploy_queue_cancellations_total counter");
+    for (component, count) in &by_component {
+        let component = escape_label_value(component);
+        let _ = writeln!(out, "deploy_queue_cancellations_total{{component=\"{component}\"}} {count}");
+    }
+}
+
+fn render_blocking_gauge(out: &mut String, deployments: &[Deployment]) {
+    let mut by_cell: BTreeMap<String, usize> = BTreeMap::new();
+    for deployment in deployments {
+        let state = DeploymentState::from_timestamps(
+            deployment.start_timestamp,
+            deployment.finish_timestamp,
+            deployment.cancellation_timestamp,
+        );
+        if matches!(state, DeploymentState::Queued | DeploymentState::Running) {
+            *by_cell.entry(deployment.cell.location()).or_default() += 1;
+        }
+    }
+
+    let _ = writeln!(
+        out,
+        "# HELP deploy_queue_blocking_deployments Number of still-active deployments occupying a cell."
+    );
+    let _ = writeln!(out, "# TYPE deploy_queue_blocking_deployments gauge");
+    for (cell, count) in &by_cell {
+        let cell = escape_label_value(cell);
+        let _ = writeln!(out, "deploy_queue_blocking_deployments{{cell=\"{cell}\"}} {count}");
+    }
+}
+
+fn render_stale_heartbeat_gauge(out: &mut String, stale: &[StaleHeartbeatDeployment]) {
+    let _ = writeln!(
+        out,
+        "# HELP deploy_queue_stale_heartbeat_seconds Elapsed time since the last heartbeat for a deployment flagged stale."
+    );
+    let _ = writeln!(out, "# TYPE deploy_queue_stale_heartbeat_seconds gauge");
+    for deployment in stale {
+        let component = escape_label_value(&deployment.component);
+        let _ = writeln!(
+            out,
+            "deploy_queue_stale_heartbeat_seconds{{deployment_id=\"{}\",component=\"{component}\"}} {}",
+            deployment.id,
+            deployment.time_since_heartbeat.as_seconds_f64()
+        );
+    }
+}
+
+/// Escape a label value per the Prometheus text-exposition format: backslash
+/// and double-quote need escaping so the value can't break out of its
+/// surrounding quotes, and a literal newline needs escaping since the format
+/// is line-oriented.
+fn escape_label_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
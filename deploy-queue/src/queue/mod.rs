@@ -0,0 +1,106 @@
+//! Backend-agnostic front door for the deployment lifecycle.
+//!
+//! Everything in `handler` is hard-wired to `sqlx::Pool<Postgres>` and makes
+//! heavy use of Postgres-only features: the `deployment_status` ENUM,
+//! `DISTINCT ON`, `FOR UPDATE SKIP LOCKED`, `pg_notify`/`PgListener`, and
+//! `PERCENTILE_CONT`. None of those have a SQLite equivalent, so this trait
+//! only covers the subset of the lifecycle that a local/dev setup or a unit
+//! test actually needs - enqueue, fetch, start, finish, cancel (singly or by
+//! location), claim, and list. `SqliteQueue`'s implementations of these trade
+//! away concurrency-key blocking, retries, and worker tracking for a single
+//! flat table; see its own docs. `reaper::sweep_once`, `watch_cancellations`
+//! and the analytics queries remain Postgres-only for now; `PgQueue` is the
+//! only implementation that can be wired up to the full CLI.
+//!
+//! This is the same shape a generic `DeployStore` trait (with `PgStore`/
+//! `MemoryStore` implementations, `handler` made generic over it) would have
+//! bought: a backend unit tests can swap in without a live Postgres. Making
+//! `handler`'s free functions themselves generic over this trait isn't
+//! adopted here, though - they're written directly against
+//! `sqlx::query!`'s compile-time-checked Postgres SQL (ENUM casts,
+//! `DISTINCT ON`, `FOR UPDATE SKIP LOCKED`, CTEs `RETURNING` straight into a
+//! typed row), and a generic `T: DeploymentQueue` parameter would either
+//! lose that compile-time checking or need every query re-expressed against
+//! some least-common-denominator query builder. `PgQueue` stays a thin facade
+//! calling straight through to `handler` (see its own docs) so that checking
+//! isn't given up, and `SqliteQueue` implements the trait directly against
+//! its own schema instead of trying to satisfy `handler`'s Postgres-specific
+//! queries - `queue_sqlite_tests.rs` already exercises `claim_next`/`list`/
+//! etc. end to end against `sqlite::memory:`, with no live Postgres
+//! instance, for exactly the fast/deterministic cases a from-scratch
+//! `MemoryStore` would target. Plain `handler`-level tests (most of
+//! `deploy-queue/tests`) still need the real `PgQueue` path, since they
+//! exercise Postgres-only behavior `SqliteQueue` doesn't implement.
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::{handler::DeploymentFilter, model::Deployment, notifier::Notifier};
+
+/// The operations every backend has to support. Matches the shape of the
+/// free functions in `handler`, just grouped behind one object so callers
+/// (tests, above all) can swap Postgres for an in-process SQLite database
+/// without touching call sites.
+#[async_trait]
+pub trait DeploymentQueue: Send + Sync {
+    /// Insert a new deployment job and its first run; returns the new job ID.
+    async fn enqueue_deployment(
+        &self,
+        deployment: Deployment,
+        notifiers: &[Box<dyn Notifier>],
+    ) -> Result<i64>;
+
+    /// Look up a deployment by ID, if it exists.
+    async fn fetch_deployment(&self, deployment_id: i64) -> Result<Option<Deployment>>;
+
+    /// Mark a deployment's latest run as started.
+    async fn start_deployment(&self, deployment_id: i64, notifiers: &[Box<dyn Notifier>])
+        -> Result<()>;
+
+    /// Mark a deployment's latest run as finished.
+    async fn finish_deployment(
+        &self,
+        deployment_id: i64,
+        notifiers: &[Box<dyn Notifier>],
+    ) -> Result<()>;
+
+    /// Cancel a deployment's latest run, if it isn't already terminal.
+    async fn cancel_deployment(
+        &self,
+        deployment_id: i64,
+        cancellation_note: Option<String>,
+        notifiers: &[Box<dyn Notifier>],
+    ) -> Result<()>;
+
+    /// Cancel every non-terminal deployment at a location, optionally
+    /// narrowed to one `cell_index`. Returns the number of deployments
+    /// cancelled.
+    async fn cancel_by_location(
+        &self,
+        environment: &str,
+        cloud_provider: &str,
+        region: &str,
+        cell_index: Option<i32>,
+        cancellation_note: Option<String>,
+        notifiers: &[Box<dyn Notifier>],
+    ) -> Result<u64>;
+
+    /// Atomically claim and start the oldest runnable queued deployment, if
+    /// any. `worker_id` identifies the caller for backends that track which
+    /// worker is running what; backends that don't may ignore it.
+    async fn claim_next(
+        &self,
+        worker_id: &str,
+        notifiers: &[Box<dyn Notifier>],
+    ) -> Result<Option<Deployment>>;
+
+    /// List deployments matching `filter`.
+    async fn list(&self, filter: DeploymentFilter) -> Result<Vec<Deployment>>;
+}
+
+pub mod pg;
+pub mod sqlite;
+pub mod supervisor;
+
+pub use pg::PgQueue;
+pub use sqlite::SqliteQueue;
+pub use supervisor::DeployQueue;
@@ -0,0 +1,97 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::{Pool, Postgres};
+
+use crate::{
+    handler::{self, DeploymentFilter},
+    model::Deployment,
+    notifier::Notifier,
+};
+
+use super::DeploymentQueue;
+
+/// The production backend - a thin `DeploymentQueue` facade over the
+/// existing `handler` free functions, which already do the real work
+/// against `deployments`/`deployment_runs`.
+pub struct PgQueue {
+    pool: Pool<Postgres>,
+}
+
+impl PgQueue {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl DeploymentQueue for PgQueue {
+    async fn enqueue_deployment(
+        &self,
+        deployment: Deployment,
+        notifiers: &[Box<dyn Notifier>],
+    ) -> Result<i64> {
+        handler::enqueue_deployment(&self.pool, deployment, notifiers).await
+    }
+
+    async fn fetch_deployment(&self, deployment_id: i64) -> Result<Option<Deployment>> {
+        handler::fetch::deployment(&self.pool, deployment_id).await
+    }
+
+    async fn start_deployment(
+        &self,
+        deployment_id: i64,
+        notifiers: &[Box<dyn Notifier>],
+    ) -> Result<()> {
+        handler::start_deployment(&self.pool, deployment_id, notifiers).await
+    }
+
+    async fn finish_deployment(
+        &self,
+        deployment_id: i64,
+        notifiers: &[Box<dyn Notifier>],
+    ) -> Result<()> {
+        handler::finish_deployment(&self.pool, deployment_id, notifiers).await
+    }
+
+    async fn cancel_deployment(
+        &self,
+        deployment_id: i64,
+        cancellation_note: Option<String>,
+        notifiers: &[Box<dyn Notifier>],
+    ) -> Result<()> {
+        handler::cancel::deployment(&self.pool, deployment_id, cancellation_note, notifiers).await
+    }
+
+    async fn cancel_by_location(
+        &self,
+        environment: &str,
+        cloud_provider: &str,
+        region: &str,
+        cell_index: Option<i32>,
+        cancellation_note: Option<String>,
+        notifiers: &[Box<dyn Notifier>],
+    ) -> Result<u64> {
+        handler::cancel::by_location(
+            &self.pool,
+            environment,
+            cloud_provider,
+            region,
+            cell_index,
+            cancellation_note,
+            notifiers,
+        )
+        .await
+    }
+
+    async fn claim_next(
+        &self,
+        worker_id: &str,
+        notifiers: &[Box<dyn Notifier>],
+    ) -> Result<Option<Deployment>> {
+        handler::claim::claim_next(&self.pool, worker_id, notifiers).await
+    }
+
+    async fn list(&self, filter: DeploymentFilter) -> Result<Vec<Deployment>> {
+        handler::list(&self.pool, filter).await
+    }
+}
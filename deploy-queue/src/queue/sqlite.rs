@@ -0,0 +1,473 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use sqlx::{Pool, QueryBuilder, Sqlite};
+use time::OffsetDateTime;
+
+use crate::{
+    handler::DeploymentFilter,
+    model::{Cell, Deployment, DeploymentStatus},
+    notifier::{self, DeploymentEvent, Notifier},
+};
+
+use super::DeploymentQueue;
+
+/// Migrations for the SQLite backend live separately from `migrations/`
+/// (Postgres-only: ENUM types, `DISTINCT ON`, `FOR UPDATE SKIP LOCKED`)
+/// since the two backends don't share a schema.
+pub(crate) static MIGRATOR: sqlx::migrate::Migrator = sqlx::migrate!("migrations_sqlite");
+
+/// A lightweight backend for local/dev use and fast unit tests: one job per
+/// row, no retries, no concurrency-key blocking, no metrics. Whatever
+/// doesn't fit in a single-table, single-connection SQLite database is out
+/// of scope - see the module docs on `DeploymentQueue`.
+pub struct SqliteQueue {
+    pool: Pool<Sqlite>,
+}
+
+impl SqliteQueue {
+    pub async fn connect(database_url: &str) -> Result<Self> {
+        let pool = Pool::<Sqlite>::connect(database_url)
+            .await
+            .with_context(|| format!("Failed to connect to SQLite database at {database_url}"))?;
+
+        MIGRATOR
+            .run(&pool)
+            .await
+            .context("Failed to run SQLite migrations")?;
+
+        Ok(Self { pool })
+    }
+}
+
+#[async_trait]
+impl DeploymentQueue for SqliteQueue {
+    async fn enqueue_deployment(
+        &self,
+        deployment: Deployment,
+        notifiers: &[Box<dyn Notifier>],
+    ) -> Result<i64> {
+        let id = sqlx::query!(
+            r#"INSERT INTO deployments
+                 (environment, cloud_provider, region, cell_index, component, version, url, note, concurrency_key, status)
+               VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, 'queued')"#,
+            deployment.cell.environment,
+            deployment.cell.cloud_provider,
+            deployment.cell.region,
+            deployment.cell.index,
+            deployment.component,
+            deployment.version,
+            deployment.url,
+            deployment.note,
+            deployment.concurrency_key,
+        )
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+
+        notifier::notify_all(
+            notifiers,
+            &DeploymentEvent {
+                deployment_id: id,
+                component: deployment.component,
+                version: deployment.version,
+                location: deployment.cell.location(),
+                old_state: None,
+                new_state: DeploymentStatus::Queued,
+                note: deployment.note,
+            },
+        )
+        .await;
+
+        Ok(id)
+    }
+
+    async fn fetch_deployment(&self, deployment_id: i64) -> Result<Option<Deployment>> {
+        let row = sqlx::query!(
+            r#"SELECT
+                 id as "id!", environment, cloud_provider, region, cell_index, component, version,
+                 url, note, concurrency_key, start_timestamp, finish_timestamp,
+                 cancellation_timestamp, cancellation_note,
+                 status as "status: DeploymentStatus"
+               FROM deployments
+               WHERE id = ?"#,
+            deployment_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| Deployment {
+            id: row.id,
+            cell: Cell {
+                environment: row.environment,
+                cloud_provider: row.cloud_provider,
+                region: row.region,
+                index: row.cell_index as i32,
+            },
+            component: row.component,
+            version: row.version,
+            url: row.url,
+            note: row.note,
+            concurrency_key: row.concurrency_key,
+            start_timestamp: row.start_timestamp,
+            finish_timestamp: row.finish_timestamp,
+            cancellation_timestamp: row.cancellation_timestamp,
+            cancellation_note: row.cancellation_note,
+            status: row.status,
+            ..Default::default()
+        }))
+    }
+
+    async fn start_deployment(
+        &self,
+        deployment_id: i64,
+        notifiers: &[Box<dyn Notifier>],
+    ) -> Result<()> {
+        let row = sqlx::query!(
+            r#"UPDATE deployments
+               SET status = 'running', start_timestamp = CURRENT_TIMESTAMP
+               WHERE id = ? AND status = 'queued'
+               RETURNING component, version, environment, cloud_provider, region, cell_index"#,
+            deployment_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let Some(row) = row else {
+            anyhow::bail!(
+                "Deployment {} cannot be started (not found, or not queued)",
+                deployment_id
+            );
+        };
+
+        notifier::notify_all(
+            notifiers,
+            &DeploymentEvent {
+                deployment_id,
+                component: row.component,
+                version: row.version,
+                location: Cell {
+                    environment: row.environment,
+                    cloud_provider: row.cloud_provider,
+                    region: row.region,
+                    index: row.cell_index as i32,
+                }
+                .location(),
+                old_state: Some(DeploymentStatus::Queued),
+                new_state: DeploymentStatus::Running,
+                note: None,
+            },
+        )
+        .await;
+
+        Ok(())
+    }
+
+    async fn finish_deployment(
+        &self,
+        deployment_id: i64,
+        notifiers: &[Box<dyn Notifier>],
+    ) -> Result<()> {
+        let row = sqlx::query!(
+            r#"UPDATE deployments
+               SET status = 'finished', finish_timestamp = CURRENT_TIMESTAMP
+               WHERE id = ? AND status = 'running'
+               RETURNING component, version, environment, cloud_provider, region, cell_index"#,
+            deployment_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let Some(row) = row else {
+            anyhow::bail!(
+                "Deployment {} cannot be finished (not found, or not running)",
+                deployment_id
+            );
+        };
+
+        notifier::notify_all(
+            notifiers,
+            &DeploymentEvent {
+                deployment_id,
+                component: row.component,
+                version: row.version,
+                location: Cell {
+                    environment: row.environment,
+                    cloud_provider: row.cloud_provider,
+                    region: row.region,
+                    index: row.cell_index as i32,
+                }
+                .location(),
+                old_state: Some(DeploymentStatus::Running),
+                new_state: DeploymentStatus::Finished,
+                note: None,
+            },
+        )
+        .await;
+
+        Ok(())
+    }
+
+    async fn cancel_deployment(
+        &self,
+        deployment_id: i64,
+        cancellation_note: Option<String>,
+        notifiers: &[Box<dyn Notifier>],
+    ) -> Result<()> {
+        let row = sqlx::query!(
+            r#"UPDATE deployments
+               SET status = 'cancelled', cancellation_timestamp = CURRENT_TIMESTAMP, cancellation_note = ?
+               WHERE id = ? AND status NOT IN ('finished', 'cancelled', 'expired', 'timed_out')
+               RETURNING component, version, environment, cloud_provider, region, cell_index"#,
+            cancellation_note,
+            deployment_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let Some(row) = row else {
+            anyhow::bail!(
+                "Deployment {} cannot be cancelled (not found, or already in a terminal state)",
+                deployment_id
+            );
+        };
+
+        notifier::notify_all(
+            notifiers,
+            &DeploymentEvent {
+                deployment_id,
+                component: row.component,
+                version: row.version,
+                location: Cell {
+                    environment: row.environment,
+                    cloud_provider: row.cloud_provider,
+                    region: row.region,
+                    index: row.cell_index as i32,
+                }
+                .location(),
+                old_state: None,
+                new_state: DeploymentStatus::Cancelled,
+                note: cancellation_note,
+            },
+        )
+        .await;
+
+        Ok(())
+    }
+
+    async fn cancel_by_location(
+        &self,
+        environment: &str,
+        cloud_provider: &str,
+        region: &str,
+        cell_index: Option<i32>,
+        cancellation_note: Option<String>,
+        notifiers: &[Box<dyn Notifier>],
+    ) -> Result<u64> {
+        let mut query = QueryBuilder::<Sqlite>::new(
+            r#"UPDATE deployments
+               SET status = 'cancelled', cancellation_timestamp = CURRENT_TIMESTAMP, cancellation_note = "#,
+        );
+        query.push_bind(cancellation_note.clone());
+        query.push(" WHERE environment = ").push_bind(environment.to_string());
+        query.push(" AND cloud_provider = ").push_bind(cloud_provider.to_string());
+        query.push(" AND region = ").push_bind(region.to_string());
+        if let Some(cell_index) = cell_index {
+            query.push(" AND cell_index = ").push_bind(cell_index);
+        }
+        query.push(" AND status NOT IN ('finished', 'cancelled', 'expired', 'timed_out', 'failed')");
+        query.push(" RETURNING id, component, version, environment, cloud_provider, region, cell_index");
+
+        let rows = query
+            .build_query_as::<CancelledRow>()
+            .fetch_all(&self.pool)
+            .await?;
+
+        let cancelled = rows.len() as u64;
+
+        for row in rows {
+            notifier::notify_all(
+                notifiers,
+                &DeploymentEvent {
+                    deployment_id: row.id,
+                    component: row.component,
+                    version: row.version,
+                    location: Cell {
+                        environment: row.environment,
+                        cloud_provider: row.cloud_provider,
+                        region: row.region,
+                        index: row.cell_index as i32,
+                    }
+                    .location(),
+                    old_state: None,
+                    new_state: DeploymentStatus::Cancelled,
+                    note: cancellation_note.clone(),
+                },
+            )
+            .await;
+        }
+
+        Ok(cancelled)
+    }
+
+    async fn claim_next(
+        &self,
+        _worker_id: &str,
+        notifiers: &[Box<dyn Notifier>],
+    ) -> Result<Option<Deployment>> {
+        // No `concurrency_key` blocking or worker tracking here - see the
+        // module docs on `SqliteQueue` - so claiming is just "the oldest
+        // still-queued row".
+        let row = sqlx::query!(
+            r#"UPDATE deployments
+               SET status = 'running', start_timestamp = CURRENT_TIMESTAMP
+               WHERE id = (SELECT id FROM deployments WHERE status = 'queued' ORDER BY created_at LIMIT 1)
+               RETURNING
+                 id as "id!", component, version, url, note, concurrency_key,
+                 environment, cloud_provider, region, cell_index"#
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let deployment = Deployment {
+            id: row.id,
+            cell: Cell {
+                environment: row.environment,
+                cloud_provider: row.cloud_provider,
+                region: row.region,
+                index: row.cell_index as i32,
+            },
+            component: row.component,
+            version: row.version,
+            url: row.url,
+            note: row.note,
+            concurrency_key: row.concurrency_key,
+            status: DeploymentStatus::Running,
+            ..Default::default()
+        };
+
+        notifier::notify_all(
+            notifiers,
+            &DeploymentEvent {
+                deployment_id: deployment.id,
+                component: deployment.component.clone(),
+                version: deployment.version.clone(),
+                location: deployment.cell.location(),
+                old_state: Some(DeploymentStatus::Queued),
+                new_state: DeploymentStatus::Running,
+                note: None,
+            },
+        )
+        .await;
+
+        Ok(Some(deployment))
+    }
+
+    async fn list(&self, filter: DeploymentFilter) -> Result<Vec<Deployment>> {
+        let mut query = QueryBuilder::<Sqlite>::new(
+            r#"SELECT
+                 id, environment, cloud_provider, region, cell_index, component, version,
+                 url, note, concurrency_key, start_timestamp, finish_timestamp,
+                 cancellation_timestamp, cancellation_note, status
+               FROM deployments
+               WHERE 1 = 1"#,
+        );
+
+        if let Some(environment) = filter.environment {
+            query.push(" AND environment = ").push_bind(environment);
+        }
+        if let Some(cloud_provider) = filter.cloud_provider {
+            query.push(" AND cloud_provider = ").push_bind(cloud_provider);
+        }
+        if let Some(region) = filter.region {
+            query.push(" AND region = ").push_bind(region);
+        }
+        if let Some(cell_index) = filter.cell_index {
+            query.push(" AND cell_index = ").push_bind(cell_index);
+        }
+        if let Some(component) = filter.component {
+            query.push(" AND component = ").push_bind(component);
+        }
+        if let Some(version) = filter.version {
+            query.push(" AND version = ").push_bind(version);
+        }
+        if !filter.statuses.is_empty() {
+            query.push(" AND status IN (");
+            let mut separated = query.separated(", ");
+            for status in &filter.statuses {
+                separated.push_bind(status.to_string());
+            }
+            separated.push_unseparated(")");
+        }
+
+        query.push(" ORDER BY id ");
+        query.push(if filter.reverse { "ASC" } else { "DESC" });
+        let limit = filter
+            .limit
+            .unwrap_or(crate::constants::DEFAULT_LIST_LIMIT)
+            .min(crate::constants::MAX_LIST_LIMIT);
+        query.push(" LIMIT ").push_bind(limit);
+        if let Some(offset) = filter.offset {
+            query.push(" OFFSET ").push_bind(offset);
+        }
+
+        let rows = query.build_query_as::<ListRow>().fetch_all(&self.pool).await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| Deployment {
+                id: row.id,
+                cell: Cell {
+                    environment: row.environment,
+                    cloud_provider: row.cloud_provider,
+                    region: row.region,
+                    index: row.cell_index as i32,
+                },
+                component: row.component,
+                version: row.version,
+                url: row.url,
+                note: row.note,
+                concurrency_key: row.concurrency_key,
+                start_timestamp: row.start_timestamp,
+                finish_timestamp: row.finish_timestamp,
+                cancellation_timestamp: row.cancellation_timestamp,
+                cancellation_note: row.cancellation_note,
+                status: row.status,
+                ..Default::default()
+            })
+            .collect())
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct CancelledRow {
+    id: i64,
+    component: String,
+    version: Option<String>,
+    environment: String,
+    cloud_provider: String,
+    region: String,
+    cell_index: i64,
+}
+
+#[derive(sqlx::FromRow)]
+struct ListRow {
+    id: i64,
+    environment: String,
+    cloud_provider: String,
+    region: String,
+    cell_index: i64,
+    component: String,
+    version: Option<String>,
+    url: Option<String>,
+    note: Option<String>,
+    concurrency_key: Option<String>,
+    start_timestamp: Option<OffsetDateTime>,
+    finish_timestamp: Option<OffsetDateTime>,
+    cancellation_timestamp: Option<OffsetDateTime>,
+    cancellation_note: Option<String>,
+    status: DeploymentStatus,
+}
@@ -0,0 +1,179 @@
+use anyhow::{Context, Result};
+use futures::StreamExt;
+use sqlx::{Pool, Postgres};
+use time::Duration;
+use tokio::{sync::watch, task::JoinHandle};
+
+use crate::{
+    constants::HEALTH_CHECK_INTERVAL, handler, handler::worker::BackoffPolicy, notifier::Notifier,
+    util,
+};
+
+/// Owns the pool plus the long-lived background tasks (the reaper janitor,
+/// the cancellation listener, and the pool health check) that the CLI's
+/// one-shot invocations don't need, but a long-running process embedding
+/// this crate does.
+///
+/// `terminate()` is the one place that shuts all of it down: it signals the
+/// reaper and health check to stop after their current iteration, awaits
+/// every background task instead of aborting it mid-query, and only then
+/// closes the pool. This is the ordering qorb got wrong - tasks spawned onto
+/// a runtime that's already closing its pool just unwrap a connection error
+/// instead of exiting cleanly.
+pub struct DeployQueue {
+    pool: Pool<Postgres>,
+    shutdown_tx: watch::Sender<bool>,
+    reaper_handle: Option<JoinHandle<()>>,
+    listener_handle: Option<JoinHandle<()>>,
+    health_check_handle: Option<JoinHandle<()>>,
+    terminated: bool,
+}
+
+impl DeployQueue {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        let (shutdown_tx, _) = watch::channel(false);
+        Self {
+            pool,
+            shutdown_tx,
+            reaper_handle: None,
+            listener_handle: None,
+            health_check_handle: None,
+            terminated: false,
+        }
+    }
+
+    /// Build the pool from `DEPLOY_QUEUE_DATABASE_URL` (via
+    /// `util::database::connect`, which applies `CONNECTION_TIMEOUT`,
+    /// `ACQUIRE_TIMEOUT` and `IDLE_TIMEOUT`) and wrap it in a fresh
+    /// `DeployQueue`. Most callers should use this instead of `new` so the
+    /// pool's lifecycle is owned by the same value from the start.
+    pub async fn connect(skip_migrations: bool) -> Result<Self> {
+        let pool = util::database::connect(skip_migrations).await?;
+        Ok(Self::new(pool))
+    }
+
+    pub fn pool(&self) -> &Pool<Postgres> {
+        &self.pool
+    }
+
+    /// Start the background pool health check. A no-op if it's already
+    /// running.
+    ///
+    /// `sqlx::Pool` already reconnects lazily the next time a caller
+    /// acquires a connection, so this isn't needed to keep the pool usable -
+    /// its job is to surface a dead database proactively, every
+    /// `HEALTH_CHECK_INTERVAL`, as a log line instead of waiting for it to
+    /// show up as a failure on whatever query happens to run next.
+    pub fn spawn_health_check(&mut self) {
+        if self.health_check_handle.is_some() {
+            return;
+        }
+
+        let pool = self.pool.clone();
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+        self.health_check_handle = Some(tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = tokio::time::sleep(HEALTH_CHECK_INTERVAL) => {
+                        if let Err(err) = sqlx::query("SELECT 1").execute(&pool).await {
+                            log::warn!("Database pool health check failed: {}", err);
+                        }
+                    }
+                    result = shutdown_rx.changed() => {
+                        if result.is_err() || *shutdown_rx.borrow() {
+                            return;
+                        }
+                    }
+                }
+            }
+        }));
+    }
+
+    /// Start the reaper janitor loop in the background. A no-op if it's
+    /// already running.
+    pub fn spawn_reaper(
+        &mut self,
+        lease_timeout: Duration,
+        backoff: BackoffPolicy,
+        max_attempts: i32,
+        notifiers: Vec<Box<dyn Notifier>>,
+    ) {
+        if self.reaper_handle.is_some() {
+            return;
+        }
+
+        let pool = self.pool.clone();
+        let shutdown_rx = self.shutdown_tx.subscribe();
+        self.reaper_handle = Some(tokio::spawn(async move {
+            if let Err(err) = handler::reaper::run(
+                &pool,
+                lease_timeout,
+                backoff,
+                max_attempts,
+                &notifiers,
+                shutdown_rx,
+            )
+            .await
+            {
+                log::warn!("Reaper loop exited: {}", err);
+            }
+        }));
+    }
+
+    /// Start the cancellation listener in the background. A no-op if it's
+    /// already running.
+    pub async fn spawn_cancellation_listener(&mut self) -> Result<()> {
+        if self.listener_handle.is_some() {
+            return Ok(());
+        }
+
+        let mut cancellations = Box::pin(
+            handler::watch_cancellations(&self.pool)
+                .await
+                .context("Failed to start cancellation listener")?,
+        );
+
+        self.listener_handle = Some(tokio::spawn(async move {
+            while let Some(deployment_id) = cancellations.next().await {
+                log::info!("Deployment {} was cancelled", deployment_id);
+            }
+        }));
+
+        Ok(())
+    }
+
+    /// Shut everything down: signal the reaper and health check, await
+    /// every background task, then close the pool. Safe to call more than
+    /// once - later calls are no-ops.
+    pub async fn terminate(&mut self) -> Result<()> {
+        if self.terminated {
+            return Ok(());
+        }
+        self.terminated = true;
+
+        // Ignore the send error: it only means the reaper/health check
+        // already exited on its own, which is fine.
+        let _ = self.shutdown_tx.send(true);
+
+        if let Some(handle) = self.reaper_handle.take() {
+            handle.await.context("Reaper task panicked")?;
+        }
+
+        if let Some(handle) = self.health_check_handle.take() {
+            handle.await.context("Health check task panicked")?;
+        }
+
+        // The listener blocks on `PgListener::recv`, which has no
+        // cooperative checkpoint to poll a shutdown signal at - aborting it
+        // is safe here because it never holds a transaction open, unlike
+        // the reaper's sweeps.
+        if let Some(handle) = self.listener_handle.take() {
+            handle.abort();
+            let _ = handle.await;
+        }
+
+        self.pool.close().await;
+
+        Ok(())
+    }
+}
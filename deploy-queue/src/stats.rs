@@ -0,0 +1,127 @@
+//! Per-component rollup of a fleet's `Deployment`s over a trailing window -
+//! counts by `DeploymentState`, completed-duration mean/median/stddev, and
+//! how many completed runs look like outliers against the rest of the same
+//! window. Gives an operator a "how has component Y been trending this
+//! week" view, serializable as JSON for a dashboard, rather than only the
+//! point-in-time summaries `Deployment::summary`/`BlockingDeployment::
+//! summary` produce.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use time::{Duration, OffsetDateTime};
+
+use crate::{
+    constants::STATS_OUTLIER_THRESHOLD,
+    history,
+    model::{self, Deployment, DeploymentStateCounts},
+};
+
+/// One component's rollup over the window `rollup` was asked for.
+#[derive(Debug, Clone, Serialize)]
+pub struct DeploymentStats {
+    pub component: String,
+    /// How many of `component`'s deployments fall in the window, across
+    /// every `DeploymentState`.
+    pub total: usize,
+    pub queued: usize,
+    pub running: usize,
+    pub finished: usize,
+    pub cancelled: usize,
+    /// Mean of the window's `Finished` durations; zero if none finished.
+    #[serde(serialize_with = "model::serialize_duration_humantime")]
+    pub mean_duration: Duration,
+    #[serde(serialize_with = "model::serialize_duration_humantime")]
+    pub median_duration: Duration,
+    #[serde(serialize_with = "model::serialize_duration_humantime")]
+    pub stddev_duration: Duration,
+    /// How many of the window's `Finished` durations `OutlierDeployment::
+    /// detect` flags against the rest of that same window, judged at
+    /// `STATS_OUTLIER_THRESHOLD`.
+    pub outlier_count: usize,
+}
+
+/// Build one `DeploymentStats` per distinct `component` among `deployments`
+/// whose `start_timestamp`, `finish_timestamp`, or `cancellation_timestamp`
+/// falls within `lookback` of `now` - a still-`Queued` deployment (all three
+/// `None`) always counts, since it has no timestamp to judge the window by
+/// and hasn't happened yet to become stale. `now` is a parameter rather than
+/// `OffsetDateTime::now_utc()` so a caller can re-run this deterministically
+/// against a fixed snapshot, same reasoning as `AnalyticsConfig::lookback`.
+pub fn rollup(deployments: &[Deployment], lookback: Duration, now: OffsetDateTime) -> Vec<DeploymentStats> {
+    let cutoff = now - lookback;
+    let in_window = |deployment: &Deployment| {
+        let timestamps = [
+            deployment.start_timestamp,
+            deployment.finish_timestamp,
+            deployment.cancellation_timestamp,
+        ];
+        timestamps.iter().all(Option::is_none) || timestamps.iter().flatten().any(|&ts| ts >= cutoff)
+    };
+
+    let mut by_component: HashMap<&str, Vec<&Deployment>> = HashMap::new();
+    for deployment in deployments.iter().filter(|d| in_window(d)) {
+        by_component.entry(&deployment.component).or_default().push(deployment);
+    }
+
+    let mut stats: Vec<DeploymentStats> = by_component
+        .into_iter()
+        .map(|(component, group)| component_stats(component.to_string(), &group))
+        .collect();
+    stats.sort_by(|a, b| a.component.cmp(&b.component));
+    stats
+}
+
+fn component_stats(component: String, group: &[&Deployment]) -> DeploymentStats {
+    let mut stats = DeploymentStats {
+        component,
+        total: group.len(),
+        queued: 0,
+        running: 0,
+        finished: 0,
+        cancelled: 0,
+        mean_duration: Duration::ZERO,
+        median_duration: Duration::ZERO,
+        stddev_duration: Duration::ZERO,
+        outlier_count: 0,
+    };
+
+    let counts = DeploymentStateCounts::tally(group.iter().copied());
+    stats.queued = counts.queued;
+    stats.running = counts.running;
+    stats.finished = counts.finished;
+    stats.cancelled = counts.cancelled;
+
+    // Same non-positive filter `OutlierDeployment::detect` and
+    // `history::DeploymentHistory::baseline` apply to their own duration
+    // samples - a zero/negative duration (clock skew) would otherwise drag
+    // the mean/median toward zero and make `serialize_duration_humantime`
+    // fail outright on a negative result.
+    let seconds: Vec<f64> = group
+        .iter()
+        .filter_map(|d| match (d.start_timestamp, d.finish_timestamp) {
+            (Some(start), Some(finish)) => Some((finish - start).as_seconds_f64()),
+            _ => None,
+        })
+        .filter(|seconds| *seconds > 0.0)
+        .collect();
+
+    if seconds.is_empty() {
+        return stats;
+    }
+
+    let mean = seconds.iter().sum::<f64>() / seconds.len() as f64;
+    let variance = seconds.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / seconds.len() as f64;
+    let (median, mad) = history::median_and_mad(&seconds);
+
+    stats.mean_duration = Duration::seconds_f64(mean);
+    stats.median_duration = Duration::seconds_f64(median);
+    stats.stddev_duration = Duration::seconds_f64(variance.sqrt());
+
+    stats.outlier_count = seconds
+        .iter()
+        .filter(|&&value| model::is_outlier_z_score(value, median, mad, STATS_OUTLIER_THRESHOLD))
+        .count();
+
+    stats
+}
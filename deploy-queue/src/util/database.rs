@@ -6,7 +6,13 @@ use log::{info, warn};
 use sqlx::{Pool, Postgres, postgres::PgPoolOptions};
 use tokio::time::timeout;
 
-use crate::constants::{ACQUIRE_TIMEOUT, CONNECTION_TIMEOUT, IDLE_TIMEOUT};
+use crate::{
+    constants::{
+        ACQUIRE_TIMEOUT, ACQUIRE_TIMEOUT_ENV, CONNECTION_TIMEOUT, DEFAULT_MAX_CONNECTIONS, IDLE_TIMEOUT,
+        MAX_CONNECTIONS_ENV,
+    },
+    history::DeploymentHistory,
+};
 
 pub async fn connect(skip_migrations: bool) -> Result<Pool<Postgres>> {
     let pool = create_db_connection().await?;
@@ -18,14 +24,49 @@ pub async fn connect(skip_migrations: bool) -> Result<Pool<Postgres>> {
     Ok(pool)
 }
 
+/// Connect to the `Outliers` duration-history store at
+/// `DEPLOY_QUEUE_HISTORY_DATABASE_URL`, running its migrations. Separate
+/// from the main Postgres pool above since it's a SQLite side-store, not an
+/// alternate home for the same data.
+pub async fn connect_history() -> Result<DeploymentHistory> {
+    let database_url = std::env::var("DEPLOY_QUEUE_HISTORY_DATABASE_URL")
+        .context("DEPLOY_QUEUE_HISTORY_DATABASE_URL environment variable is not set")?;
+
+    DeploymentHistory::connect(&database_url).await
+}
+
+/// Reads `MAX_CONNECTIONS_ENV`, falling back to `default` when it's unset or
+/// doesn't parse as a `u32`. `pub` so the test pool helpers in
+/// `tests/common/test_db_setup.rs` share this parsing instead of duplicating
+/// it with their own (smaller) default.
+pub fn configured_max_connections(default: u32) -> u32 {
+    std::env::var(MAX_CONNECTIONS_ENV)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+/// Reads `ACQUIRE_TIMEOUT_ENV` (whole seconds), falling back to `default`
+/// when it's unset or doesn't parse as a `u64`. `pub` for the same reason as
+/// `configured_max_connections`.
+pub fn configured_acquire_timeout(default: StdDuration) -> StdDuration {
+    std::env::var(ACQUIRE_TIMEOUT_ENV)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .map(StdDuration::from_secs)
+        .unwrap_or(default)
+}
+
 async fn create_db_connection() -> Result<Pool<Postgres>> {
     let database_url = std::env::var("DEPLOY_QUEUE_DATABASE_URL")
         .context("DEPLOY_QUEUE_DATABASE_URL environment variable is not set")?;
+    let max_connections = configured_max_connections(DEFAULT_MAX_CONNECTIONS);
+    let acquire_timeout = configured_acquire_timeout(ACQUIRE_TIMEOUT);
 
     (async || {
         let connect_future = PgPoolOptions::new()
-            .max_connections(10)
-            .acquire_timeout(ACQUIRE_TIMEOUT)
+            .max_connections(max_connections)
+            .acquire_timeout(acquire_timeout)
             .idle_timeout(Some(IDLE_TIMEOUT))
             .connect(&database_url);
 
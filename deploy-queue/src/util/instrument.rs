@@ -0,0 +1,62 @@
+use std::{future::Future, panic::Location, time::Duration as StdDuration};
+
+use anyhow::{Result, bail};
+use log::warn;
+use metrics::{counter, histogram};
+use tokio::time::Instant;
+
+use crate::constants::SLOW_QUERY_WARN_THRESHOLD;
+
+/// Extension trait wrapping a query future with timing/outcome metrics and a
+/// hard timeout, so a stuck query surfaces as a `deploy_queue.query.timeout`
+/// counter instead of hanging its caller indefinitely. Mirrors the
+/// `tokio::time::timeout` already wrapped around pool connection setup in
+/// `util::database`, just applied per-query rather than once at startup.
+/// `#[track_caller]` so the slow-query warning below names the call site,
+/// not just the line inside this trait.
+///
+/// `operation` is the metric tag (e.g. `"enqueue_deployment"`) - keep it a
+/// `&'static str` naming the call site, not the SQL itself. Implemented for
+/// any query future (not just ones already returning `anyhow::Result`) so it
+/// drops directly onto a bare `sqlx::query!(..).fetch_one(client)` call.
+pub trait Instrumented<T, E>: Future<Output = std::result::Result<T, E>> + Sized
+where
+    E: Into<anyhow::Error>,
+{
+    fn instrumented(self, operation: &'static str, timeout: StdDuration) -> impl Future<Output = Result<T>>;
+}
+
+impl<T, E, F> Instrumented<T, E> for F
+where
+    F: Future<Output = std::result::Result<T, E>>,
+    E: Into<anyhow::Error>,
+{
+    #[track_caller]
+    async fn instrumented(self, operation: &'static str, timeout: StdDuration) -> Result<T> {
+        let caller = Location::caller();
+        let start = Instant::now();
+        let outcome = tokio::time::timeout(timeout, self).await;
+        let elapsed = start.elapsed();
+        histogram!("deploy_queue.query.duration", "operation" => operation).record(elapsed.as_secs_f64());
+        if elapsed > SLOW_QUERY_WARN_THRESHOLD {
+            warn!(
+                "Query '{operation}' ({caller}) held its connection for {elapsed:?}, over the {SLOW_QUERY_WARN_THRESHOLD:?} threshold"
+            );
+        }
+
+        match outcome {
+            Ok(Ok(value)) => {
+                counter!("deploy_queue.query.success", "operation" => operation).increment(1);
+                Ok(value)
+            }
+            Ok(Err(err)) => {
+                counter!("deploy_queue.query.error", "operation" => operation).increment(1);
+                Err(err.into())
+            }
+            Err(_) => {
+                counter!("deploy_queue.query.timeout", "operation" => operation).increment(1);
+                bail!("Query '{}' timed out after {:?}", operation, timeout);
+            }
+        }
+    }
+}
@@ -0,0 +1,193 @@
+//! Durable, resumable deployments: a `Workflow` is an ordered list of named
+//! activities, each recorded in `deployment_activities` as it completes.
+//! Replaying a deployment (after a crash, or a retried failure picked back
+//! up by `handler::worker::run`) walks the same activity list from the top
+//! but short-circuits anything already marked complete, handing back its
+//! cached `output` instead of re-running it - so an activity only ever runs
+//! to completion once per deployment. This makes
+//! `handler::worker::DeploymentProcessor` implementations built from a
+//! `Workflow` safe to hand to a worker that retries on failure: the retried
+//! attempt resumes instead of repeating finished side effects.
+//!
+//! Activities must be deterministic given their inputs (the `Deployment`)
+//! for replay to be sound - anything non-deterministic (a generated ID, a
+//! timestamp that matters) belongs in the activity's recorded `output`, not
+//! recomputed on the next replay.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use sqlx::{Pool, Postgres};
+use tokio_util::sync::CancellationToken;
+
+use crate::{handler::worker::DeploymentProcessor, model::Deployment};
+
+/// What an activity closure is given to work with.
+pub struct ActivityContext {
+    pub deployment: Deployment,
+    pub cancellation: CancellationToken,
+}
+
+type ActivityFn = Box<dyn Fn(ActivityContext) -> BoxFuture<'static, Result<serde_json::Value>> + Send + Sync>;
+
+struct Activity {
+    name: String,
+    run: ActivityFn,
+}
+
+/// An ordered list of named activities, built with `WorkflowBuilder`.
+pub struct Workflow {
+    activities: Vec<Activity>,
+}
+
+/// Builds a `Workflow` one activity at a time, in the order they should run.
+///
+/// ```ignore
+/// let workflow = WorkflowBuilder::new()
+///     .activity("migrate-schema", |ctx| async move {
+///         run_migration(&ctx.deployment.version).await?;
+///         Ok(serde_json::json!({ "migrated": true }))
+///     })
+///     .activity("roll-out", |ctx| async move {
+///         roll_out(&ctx.deployment).await?;
+///         Ok(serde_json::Value::Null)
+///     })
+///     .build();
+/// ```
+#[derive(Default)]
+pub struct WorkflowBuilder {
+    activities: Vec<Activity>,
+}
+
+impl WorkflowBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn activity<F, Fut>(mut self, name: impl Into<String>, run: F) -> Self
+    where
+        F: Fn(ActivityContext) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Result<serde_json::Value>> + Send + 'static,
+    {
+        self.activities.push(Activity {
+            name: name.into(),
+            run: Box::new(move |ctx| Box::pin(run(ctx))),
+        });
+        self
+    }
+
+    pub fn build(self) -> Workflow {
+        Workflow {
+            activities: self.activities,
+        }
+    }
+}
+
+/// Has `deployment_id` already completed `activity_name`? If so, returns its
+/// cached output instead of making the caller re-run the activity.
+async fn completed_output(
+    client: &Pool<Postgres>,
+    deployment_id: i64,
+    activity_name: &str,
+) -> Result<Option<serde_json::Value>> {
+    let row = sqlx::query!(
+        "SELECT output FROM deployment_activities WHERE deployment_id = $1 AND activity_name = $2",
+        deployment_id,
+        activity_name
+    )
+    .fetch_optional(client)
+    .await?;
+
+    Ok(row.map(|row| row.output))
+}
+
+/// Record that `activity_name` completed for `deployment_id` with `output`.
+/// Idempotent: replaying an activity that raced another replay of the same
+/// deployment and lost is a no-op, not an error.
+async fn record_completion(
+    client: &Pool<Postgres>,
+    deployment_id: i64,
+    activity_name: &str,
+    output: &serde_json::Value,
+) -> Result<()> {
+    sqlx::query!(
+        "INSERT INTO deployment_activities (deployment_id, activity_name, output)
+         VALUES ($1, $2, $3)
+         ON CONFLICT (deployment_id, activity_name) DO NOTHING",
+        deployment_id,
+        activity_name,
+        output
+    )
+    .execute(client)
+    .await?;
+
+    Ok(())
+}
+
+/// Run `workflow` against `deployment`, skipping any activity already
+/// recorded complete and replaying the rest in order.
+pub async fn run(
+    client: &Pool<Postgres>,
+    workflow: &Workflow,
+    deployment: &Deployment,
+    cancellation: &CancellationToken,
+) -> Result<()> {
+    for activity in &workflow.activities {
+        if let Some(output) = completed_output(client, deployment.id, &activity.name).await? {
+            log::info!(
+                "Deployment {}: activity {:?} already complete, replaying its cached output ({})",
+                deployment.id,
+                activity.name,
+                output
+            );
+            continue;
+        }
+
+        if cancellation.is_cancelled() {
+            anyhow::bail!(
+                "Deployment {} cancelled before activity {:?} ran",
+                deployment.id,
+                activity.name
+            );
+        }
+
+        log::info!("Deployment {}: running activity {:?}", deployment.id, activity.name);
+
+        let ctx = ActivityContext {
+            deployment: deployment.clone(),
+            cancellation: cancellation.clone(),
+        };
+        let output = (activity.run)(ctx).await.with_context(|| {
+            format!(
+                "Activity {:?} failed for deployment {}",
+                activity.name, deployment.id
+            )
+        })?;
+
+        record_completion(client, deployment.id, &activity.name, &output).await?;
+    }
+
+    Ok(())
+}
+
+/// Adapts a `Workflow` into a `handler::worker::DeploymentProcessor`, so
+/// `handler::worker::run` can drive it like any other processor: a failed
+/// activity fails the claim and gets retried with backoff same as before,
+/// except the retry resumes the workflow instead of starting it over.
+pub struct WorkflowProcessor {
+    pool: Pool<Postgres>,
+    workflow: Workflow,
+}
+
+impl WorkflowProcessor {
+    pub fn new(pool: Pool<Postgres>, workflow: Workflow) -> Self {
+        Self { pool, workflow }
+    }
+}
+
+#[async_trait]
+impl DeploymentProcessor for WorkflowProcessor {
+    async fn process(&self, deployment: &Deployment, cancellation: &CancellationToken) -> Result<()> {
+        run(&self.pool, &self.workflow, deployment, cancellation).await
+    }
+}
@@ -54,7 +54,7 @@ async fn assert_blocking_deployments(
 // aws, us-west-2, cell 1: deployment 1 should block deployment 2
 #[tokio::test]
 async fn test_blocked_by_running_component_same_region() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
     sqlx::query!(
         "INSERT INTO deployments (id, environment, cloud_provider, region, cell_index, component, version, url, note, start_timestamp) 
          VALUES 
@@ -72,7 +72,7 @@ async fn test_blocked_by_running_component_same_region() -> Result<()> {
 // aws, us-east-1, cell 1: deployment finished 5 minutes ago, but prod has 10min buffer
 #[tokio::test]
 async fn test_blocked_by_finished_component_within_buffer_time() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
     sqlx::query!(
         "INSERT INTO deployments (id, environment, cloud_provider, region, cell_index, component, version, url, note, start_timestamp, finish_timestamp) 
          VALUES 
@@ -90,7 +90,7 @@ async fn test_blocked_by_finished_component_within_buffer_time() -> Result<()> {
 // aws, eu-west-1, cell 1: deployment finished 15 minutes ago, outside 10min buffer
 #[tokio::test]
 async fn test_not_blocked_by_finished_component_outside_buffer_time() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
     sqlx::query!(
         "INSERT INTO deployments (id, environment, cloud_provider, region, cell_index, component, version, url, note, start_timestamp, finish_timestamp) 
          VALUES 
@@ -107,7 +107,7 @@ async fn test_not_blocked_by_finished_component_outside_buffer_time() -> Result<
 // Scenario 4a: Different regions (should NOT block each other)
 #[tokio::test]
 async fn test_not_blocked_by_running_component_different_region() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
     sqlx::query!(
         "INSERT INTO deployments (id, environment, cloud_provider, region, cell_index, component, version, note, start_timestamp) 
          VALUES 
@@ -124,7 +124,7 @@ async fn test_not_blocked_by_running_component_different_region() -> Result<()>
 // Scenario 4b: Different environments (should NOT block each other)
 #[tokio::test]
 async fn test_not_blocked_by_running_component_different_environment() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
     sqlx::query!(
         "INSERT INTO deployments (id, environment, cloud_provider, region, cell_index, component, version, note, start_timestamp) 
          VALUES 
@@ -141,7 +141,7 @@ async fn test_not_blocked_by_running_component_different_environment() -> Result
 // Scenario 4c: Different cell_index (should NOT block each other)
 #[tokio::test]
 async fn test_not_blocked_by_running_component_different_cell_index() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
     sqlx::query!(
         "INSERT INTO deployments (id, environment, cloud_provider, region, cell_index, component, version, note, start_timestamp) 
          VALUES 
@@ -158,7 +158,7 @@ async fn test_not_blocked_by_running_component_different_cell_index() -> Result<
 // Scenario 4d: Different cloud_provider (should NOT block each other)
 #[tokio::test]
 async fn test_not_blocked_by_running_component_different_cloud_provider() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
     sqlx::query!(
         "INSERT INTO deployments (id, environment, cloud_provider, region, cell_index, component, version, note, start_timestamp) 
          VALUES 
@@ -175,7 +175,7 @@ async fn test_not_blocked_by_running_component_different_cloud_provider() -> Res
 // Scenario 5: Cancelled deployment (should NOT block)
 #[tokio::test]
 async fn test_not_blocked_by_cancelled_deployment() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
     sqlx::query!(
         "INSERT INTO deployments (id, environment, cloud_provider, region, cell_index, component, version, note, start_timestamp, cancellation_timestamp, cancellation_note) 
          VALUES 
@@ -192,7 +192,7 @@ async fn test_not_blocked_by_cancelled_deployment() -> Result<()> {
 // Scenario 6: Dev environment (no buffer time)
 #[tokio::test]
 async fn test_not_blocked_in_dev_environment_no_buffer() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
     sqlx::query!(
         "INSERT INTO deployments (id, environment, cloud_provider, region, cell_index, component, version, note, start_timestamp, finish_timestamp) 
          VALUES 
@@ -209,7 +209,7 @@ async fn test_not_blocked_in_dev_environment_no_buffer() -> Result<()> {
 // Scenario 7: Same concurrency key (should NOT block each other)
 #[tokio::test]
 async fn test_not_blocked_by_same_concurrency_key() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
     sqlx::query!(
         "INSERT INTO deployments (id, environment, cloud_provider, region, cell_index, component, version, note, start_timestamp, concurrency_key) 
          VALUES 
@@ -226,7 +226,7 @@ async fn test_not_blocked_by_same_concurrency_key() -> Result<()> {
 // Scenario 8: Mixed concurrency keys (should block)
 #[tokio::test]
 async fn test_blocked_by_different_concurrency_keys() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
     sqlx::query!(
         "INSERT INTO deployments (id, environment, cloud_provider, region, cell_index, component, version, note, start_timestamp, concurrency_key) 
          VALUES 
@@ -244,7 +244,7 @@ async fn test_blocked_by_different_concurrency_keys() -> Result<()> {
 // ap-northeast-1 region: deployment with NULL concurrency key should block deployment with non-NULL key
 #[tokio::test]
 async fn test_null_vs_nonnull_concurrency_key_blocking() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
     sqlx::query!(
         "INSERT INTO deployments (id, environment, cloud_provider, region, cell_index, component, version, note, start_timestamp, concurrency_key) 
          VALUES 
@@ -262,7 +262,7 @@ async fn test_null_vs_nonnull_concurrency_key_blocking() -> Result<()> {
 // us-east-2 region: deployments block all subsequent deployments by ID order (both running and queued)
 #[tokio::test]
 async fn test_sequential_deployments_blocking_by_id_order() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
     sqlx::query!(
         "INSERT INTO deployments (id, environment, cloud_provider, region, cell_index, component, version, note, start_timestamp) 
          VALUES 
@@ -0,0 +1,35 @@
+use anyhow::Result;
+use deploy_queue::handler;
+use futures::StreamExt;
+
+#[path = "common/test_db_setup.rs"]
+mod database_helpers;
+
+#[path = "fixtures/deployment.rs"]
+mod deployment_fixtures;
+
+extern crate deploy_queue;
+
+#[tokio::test]
+async fn cancelling_a_deployment_notifies_subscribers() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+    let deployment_id = deployment_fixtures::create_test_deployment(&pool).await?;
+    sqlx::query!(
+        "INSERT INTO deployment_runs (deployment_id, attempt_number, status) VALUES ($1, 1, 'queued')",
+        deployment_id
+    )
+    .execute(&pool)
+    .await?;
+
+    let mut cancellations = Box::pin(handler::watch_cancellations(&pool).await?);
+
+    handler::cancel::deployment(&pool, deployment_id, None::<String>, &[]).await?;
+
+    let notified = tokio::time::timeout(std::time::Duration::from_secs(5), cancellations.next())
+        .await?
+        .expect("cancellation stream ended unexpectedly");
+
+    assert_eq!(notified, deployment_id);
+
+    Ok(())
+}
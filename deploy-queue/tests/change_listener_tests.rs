@@ -0,0 +1,139 @@
+use anyhow::Result;
+use deploy_queue::handler::{self, worker::BackoffPolicy};
+use time::{Duration as TimeDuration, OffsetDateTime};
+
+#[path = "common/test_db_setup.rs"]
+mod database_helpers;
+
+#[path = "fixtures/deployment.rs"]
+mod deployment_fixtures;
+
+extern crate deploy_queue;
+
+#[tokio::test]
+async fn enqueuing_a_deployment_wakes_a_change_listener() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+    let listener = handler::listen::ChangeListener::connect(&pool).await?;
+
+    let deployment = deploy_queue::model::Deployment {
+        component: "api".to_string(),
+        ..Default::default()
+    };
+    handler::enqueue_deployment(&pool, deployment, &[]).await?;
+
+    tokio::time::timeout(std::time::Duration::from_secs(5), listener.notified()).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn finishing_a_deployment_wakes_a_change_listener() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+    let deployment_id = deployment_fixtures::create_running_deployment(&pool).await?;
+    sqlx::query!(
+        "INSERT INTO deployment_runs (deployment_id, attempt_number, status, start_timestamp) VALUES ($1, 1, 'running', NOW())",
+        deployment_id
+    )
+    .execute(&pool)
+    .await?;
+
+    let listener = handler::listen::ChangeListener::connect(&pool).await?;
+
+    handler::finish_deployment(&pool, deployment_id, &[]).await?;
+
+    tokio::time::timeout(std::time::Duration::from_secs(5), listener.notified()).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn wait_for_blocking_deployments_wakes_up_as_soon_as_the_blocker_finishes() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+
+    let blocker_id = deployment_fixtures::create_running_deployment(&pool).await?;
+    sqlx::query!(
+        "INSERT INTO deployment_runs (deployment_id, attempt_number, status, start_timestamp) VALUES ($1, 1, 'running', NOW())",
+        blocker_id
+    )
+    .execute(&pool)
+    .await?;
+
+    let waiting_deployment = deployment_fixtures::create_test_deployment(&pool).await?;
+    sqlx::query!(
+        "INSERT INTO deployment_runs (deployment_id, attempt_number, status) VALUES ($1, 1, 'queued')",
+        waiting_deployment
+    )
+    .execute(&pool)
+    .await?;
+
+    let wait_pool = pool.clone();
+    let wait_handle = tokio::spawn(async move {
+        handler::wait_for_blocking_deployments(&wait_pool, waiting_deployment, &[]).await
+    });
+
+    // Give `wait_for_blocking_deployments` a chance to start its listener
+    // and fall into its first iteration before we finish the blocker.
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+    handler::finish_deployment(&pool, blocker_id, &[]).await?;
+
+    // If the listener wakeup didn't work, this would only resolve after a
+    // full `BUSY_RETRY` poll (several seconds) - well past this timeout.
+    tokio::time::timeout(std::time::Duration::from_secs(2), wait_handle).await???;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn reaping_a_stale_deployment_wakes_a_change_listener() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+    let deployment_id = deployment_fixtures::create_test_deployment(&pool).await?;
+    let stale_heartbeat = OffsetDateTime::now_utc() - TimeDuration::hours(1);
+    sqlx::query!(
+        "INSERT INTO deployment_runs (deployment_id, attempt_number, status, heartbeat_timestamp) VALUES ($1, 1, 'queued', $2)",
+        deployment_id,
+        stale_heartbeat
+    )
+    .execute(&pool)
+    .await?;
+
+    let listener = handler::listen::ChangeListener::connect(&pool).await?;
+
+    let reaped = handler::reap::stale_deployments(&pool, TimeDuration::minutes(2), &[]).await?;
+    assert_eq!(reaped, 1);
+
+    tokio::time::timeout(std::time::Duration::from_secs(5), listener.notified()).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn reaper_sweep_wakes_a_change_listener() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+    let deployment_id = deployment_fixtures::create_running_deployment(&pool).await?;
+    let stale_heartbeat = OffsetDateTime::now_utc() - TimeDuration::hours(1);
+    sqlx::query!(
+        "INSERT INTO deployment_runs (deployment_id, attempt_number, status, start_timestamp, heartbeat_timestamp) VALUES ($1, 1, 'running', NOW(), $2)",
+        deployment_id,
+        stale_heartbeat
+    )
+    .execute(&pool)
+    .await?;
+
+    let listener = handler::listen::ChangeListener::connect(&pool).await?;
+
+    // `max_attempts: 1` so this single sweep gives up immediately (moving
+    // straight to `timed_out`) rather than requeuing with backoff - that
+    // path is covered separately in `reaper_tests.rs`.
+    let backoff = BackoffPolicy::Exponential {
+        base: TimeDuration::milliseconds(1),
+        factor: 2.0,
+        max: TimeDuration::seconds(1),
+    };
+    let reaped = handler::reaper::sweep_once(&pool, TimeDuration::minutes(2), backoff, 1, &[]).await?;
+    assert_eq!(reaped, 1);
+
+    tokio::time::timeout(std::time::Duration::from_secs(5), listener.notified()).await?;
+
+    Ok(())
+}
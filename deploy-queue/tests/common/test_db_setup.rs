@@ -1,4 +1,5 @@
 use anyhow::{Context, Result};
+use deploy_queue::queue::DeployQueue;
 use sqlx::{Pool, Postgres, postgres::PgPoolOptions};
 use url::Url;
 
@@ -12,9 +13,77 @@ fn replace_database_name(database_url: &str, db_name: &str) -> Result<String> {
     Ok(url.to_string())
 }
 
-/// Helper to create a test database connection with unique database name
-/// Creates a unique database per test to allow parallel execution
-pub async fn create_test_db_connection() -> Result<Pool<Postgres>> {
+/// RAII guard returned alongside the pool from `create_test_db_connection`:
+/// on drop, forcibly terminates any backends still connected to the unique
+/// per-test database and drops it, so a test that panics or gets killed
+/// doesn't leak `test_deploy_queue_*` databases forever. Deliberately holds
+/// no `Pool` of its own (only the admin URL and database name needed for
+/// teardown) - a wrapper type implementing `Deref<Target = Pool<Postgres>>`
+/// would silently break every call site that passes `&pool` into one of
+/// sqlx's generic `Executor`-bound methods, since Deref coercion doesn't
+/// apply when satisfying a generic trait bound. Keep the real `Pool<Postgres>`
+/// flowing through call sites unchanged; just carry this alongside it and
+/// let it go out of scope at the end of the test.
+pub struct TestDb {
+    db_name: String,
+    admin_url: String,
+}
+
+impl Drop for TestDb {
+    fn drop(&mut self) {
+        let db_name = self.db_name.clone();
+        let admin_url = self.admin_url.clone();
+
+        // `Drop` can't be async, and calling back into the enclosing
+        // `#[tokio::test]` runtime from here would either deadlock
+        // (current-thread flavor) or race that runtime's own shutdown once
+        // the test function returns. Block this thread on a throwaway
+        // runtime on a dedicated thread instead, so teardown has finished
+        // by the time `drop` returns no matter how the test itself exited.
+        let teardown = std::thread::spawn(move || {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()?
+                .block_on(terminate_and_drop(&admin_url, &db_name))
+        })
+        .join();
+
+        if let Err(err) = teardown.unwrap_or_else(|_| Err(anyhow::anyhow!("test database teardown thread panicked"))) {
+            eprintln!("Failed to tear down test database {}: {:#}", self.db_name, err);
+        }
+    }
+}
+
+/// Forcibly disconnect every other backend on `db_name` before dropping it -
+/// a plain `DROP DATABASE` fails with "database is being accessed by other
+/// users" as long as the pool being torn down (or a background task built on
+/// top of it, like the reaper/cancellation listener in `DeployQueue`) still
+/// holds a connection open.
+async fn terminate_and_drop(admin_url: &str, db_name: &str) -> Result<()> {
+    let admin_pool = PgPoolOptions::new().connect(admin_url).await?;
+
+    sqlx::query(
+        "SELECT pg_terminate_backend(pid) FROM pg_stat_activity WHERE datname = $1 AND pid <> pg_backend_pid()",
+    )
+    .bind(db_name)
+    .execute(&admin_pool)
+    .await
+    .context("Failed to terminate backends on test database")?;
+
+    sqlx::query(&format!("DROP DATABASE IF EXISTS \"{}\"", db_name))
+        .execute(&admin_pool)
+        .await
+        .context("Failed to drop test database")?;
+
+    Ok(())
+}
+
+/// Helper to create a test database connection with unique database name.
+/// Creates a unique database per test to allow parallel execution. Returns
+/// the pool alongside a `TestDb` guard that drops the database once the
+/// caller is done with it - bind it to a name (not `_`) so it lives as long
+/// as the pool does.
+pub async fn create_test_db_connection() -> Result<(Pool<Postgres>, TestDb)> {
     use std::time::{SystemTime, UNIX_EPOCH};
 
     // Create unique database name for each test to allow parallel execution
@@ -43,19 +112,47 @@ pub async fn create_test_db_connection() -> Result<Pool<Postgres>> {
         .execute(&admin_pool)
         .await?;
 
-    // Now connect to our newly created database
-    let pool = PgPoolOptions::new().connect(&test_db_url).await?;
+    // Now connect to our newly created database. Small `max_connections` by
+    // default - CI creates one of these databases per test and runs many in
+    // parallel, so a production-sized pool per test database exhausts the
+    // server's connection limit fast. `MAX_CONNECTIONS_ENV`/`ACQUIRE_TIMEOUT_ENV`
+    // override these (and the production pool's in `util::database`), for a
+    // developer debugging a connection-exhaustion issue locally.
+    let max_connections =
+        deploy_queue::util::database::configured_max_connections(deploy_queue::constants::TEST_MAX_CONNECTIONS);
+    let acquire_timeout =
+        deploy_queue::util::database::configured_acquire_timeout(deploy_queue::constants::ACQUIRE_TIMEOUT);
+    let pool = PgPoolOptions::new()
+        .max_connections(max_connections)
+        .acquire_timeout(acquire_timeout)
+        .connect(&test_db_url)
+        .await?;
 
-    Ok(pool)
+    Ok((
+        pool,
+        TestDb {
+            db_name: unique_db_name,
+            admin_url,
+        },
+    ))
 }
 
 /// Helper to set up test database with migrations
-pub async fn setup_test_db() -> Result<Pool<Postgres>> {
-    let pool = create_test_db_connection().await?;
+pub async fn setup_test_db() -> Result<(Pool<Postgres>, TestDb)> {
+    let (pool, db) = create_test_db_connection().await?;
 
     // Run migrations - they're idempotent so safe to run multiple times
     // This will also insert the default 'dev' and 'prod' environments
     sqlx::migrate!().set_ignore_missing(true).run(&pool).await?;
 
-    Ok(pool)
+    Ok((pool, db))
+}
+
+/// Same as `setup_test_db`, but wrapped in a `DeployQueue` so a test that
+/// spawns background tasks (reaper, cancellation listener, health check)
+/// can tear them down deterministically via `DeployQueue::terminate`
+/// instead of leaving their fate to drop order at the end of the test.
+pub async fn setup_test_deploy_queue() -> Result<(DeployQueue, TestDb)> {
+    let (pool, db) = setup_test_db().await?;
+    Ok((DeployQueue::new(pool), db))
 }
@@ -0,0 +1,69 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use deploy_queue::{
+    cli::{Environment, StartDeployment},
+    handler::dedup,
+};
+use tokio::sync::Barrier;
+
+fn start() -> StartDeployment {
+    StartDeployment {
+        environment: Environment::Dev,
+        cloud_provider: "aws".to_string(),
+        region: "us-east-1".to_string(),
+        cell_index: 1,
+        component: "api".to_string(),
+        version: Some("v1".to_string()),
+        url: None,
+        note: None,
+        concurrency_key: None,
+        reserve_resource: None,
+        isolation_channel: None,
+        max_retries: 0,
+    }
+}
+
+/// Fires several concurrent `coalesce` calls for the same target on a
+/// multi-threaded runtime (so the check-and-insert genuinely races across
+/// OS threads, not just across cooperative yield points) and asserts only
+/// one of them actually runs `enqueue`. Pins the bug this exists to catch:
+/// a non-atomic check-then-insert lets two callers both observe no
+/// in-flight leader and both enqueue.
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn coalesce_runs_enqueue_exactly_once_for_concurrent_callers() {
+    let enqueue_calls = Arc::new(AtomicUsize::new(0));
+    let barrier = Arc::new(Barrier::new(8));
+
+    let handles: Vec<_> = (0..8)
+        .map(|_| {
+            let enqueue_calls = enqueue_calls.clone();
+            let barrier = barrier.clone();
+            tokio::spawn(async move {
+                barrier.wait().await;
+                dedup::coalesce(&start(), || async {
+                    enqueue_calls.fetch_add(1, Ordering::SeqCst);
+                    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                    Ok(42)
+                })
+                .await
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        let result = handle
+            .await
+            .expect("coalesce task panicked")
+            .expect("coalesce returned an error");
+        assert_eq!(result, 42);
+    }
+
+    assert_eq!(
+        enqueue_calls.load(Ordering::SeqCst),
+        1,
+        "only the leader should have run enqueue; every follower should have coalesced onto it"
+    );
+}
@@ -0,0 +1,41 @@
+use anyhow::Result;
+use deploy_queue::{handler::worker::BackoffPolicy, queue::DeployQueue};
+
+#[path = "common/test_db_setup.rs"]
+mod database_helpers;
+
+extern crate deploy_queue;
+
+#[tokio::test]
+async fn terminate_stops_the_reaper_and_is_idempotent() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+    let mut deploy_queue = DeployQueue::new(pool);
+
+    let backoff = BackoffPolicy::Exponential {
+        base: time::Duration::seconds(30),
+        factor: 2.0,
+        max: time::Duration::minutes(10),
+    };
+    deploy_queue.spawn_reaper(time::Duration::minutes(5), backoff, 3, Vec::new());
+    deploy_queue.spawn_cancellation_listener().await?;
+
+    deploy_queue.terminate().await?;
+    // Calling it again should not panic or hang - both background tasks
+    // have already been awaited and the pool already closed.
+    deploy_queue.terminate().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn terminate_stops_the_health_check_task() -> Result<()> {
+    let (mut deploy_queue, _db) = database_helpers::setup_test_deploy_queue().await?;
+
+    deploy_queue.spawn_health_check();
+    // A second call is a no-op rather than a second background task.
+    deploy_queue.spawn_health_check();
+
+    deploy_queue.terminate().await?;
+
+    Ok(())
+}
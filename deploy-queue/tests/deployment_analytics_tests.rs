@@ -122,7 +122,7 @@ async fn create_cancelled_deployment_with_details(
 
 #[tokio::test]
 async fn test_basic_analytics_calculation() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     let component = "test-component";
     let region = "test-region";
@@ -195,7 +195,7 @@ async fn test_basic_analytics_calculation() -> Result<()> {
 
 #[tokio::test]
 async fn test_time_filtering_three_months() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     let component = "time-test-component";
     let region = "time-test-region";
@@ -251,7 +251,7 @@ async fn test_time_filtering_three_months() -> Result<()> {
 
 #[tokio::test]
 async fn test_row_limiting_hundred_deployments() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     let component = "limit-test-component";
     let region = "limit-test-region";
@@ -318,7 +318,7 @@ async fn test_row_limiting_hundred_deployments() -> Result<()> {
 
 #[tokio::test]
 async fn test_cancelled_deployments_excluded() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     let component = "cancel-test-component";
     let region = "cancel-test-region";
@@ -365,7 +365,7 @@ async fn test_cancelled_deployments_excluded() -> Result<()> {
 
 #[tokio::test]
 async fn test_grouping_by_component_region_environment() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     // Create deployments for different groups
     create_finished_deployment_with_details(
@@ -421,7 +421,7 @@ async fn test_grouping_by_component_region_environment() -> Result<()> {
 
 #[tokio::test]
 async fn test_trigger_refreshes_on_deployment_finish() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     let component = "trigger-test-component";
     let region = "trigger-test-region";
@@ -472,7 +472,7 @@ async fn test_trigger_refreshes_on_deployment_finish() -> Result<()> {
 
 #[tokio::test]
 async fn test_incomplete_deployments_excluded() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     let component = "incomplete-test-component";
     let region = "incomplete-test-region";
@@ -516,7 +516,7 @@ async fn test_incomplete_deployments_excluded() -> Result<()> {
 
 #[tokio::test]
 async fn test_empty_results_when_no_deployments() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     // Should have no rows (view is created empty by migration)
     let count = sqlx::query!("SELECT COUNT(*) as total FROM deployment_duration_analytics")
@@ -0,0 +1,216 @@
+use anyhow::Result;
+use deploy_queue::{
+    handler::{self, DeploymentFilter},
+    model::{Cell, Deployment, DeploymentStatus},
+};
+
+#[path = "common/test_db_setup.rs"]
+mod database_helpers;
+
+extern crate deploy_queue;
+
+fn deployment_for(component: &str, region: &str) -> Deployment {
+    Deployment {
+        component: component.to_string(),
+        version: Some("v1".to_string()),
+        cell: Cell {
+            environment: "dev".to_string(),
+            cloud_provider: "aws".to_string(),
+            region: region.to_string(),
+            index: 1,
+        },
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn list_filters_by_component_across_regions() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+
+    handler::enqueue_deployment(&pool, deployment_for("api", "region-a"), &[]).await?;
+    handler::enqueue_deployment(&pool, deployment_for("api", "region-b"), &[]).await?;
+    handler::enqueue_deployment(&pool, deployment_for("worker", "region-a"), &[]).await?;
+
+    let matches = handler::list(
+        &pool,
+        DeploymentFilter {
+            component: Some("api".to_string()),
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    assert_eq!(matches.len(), 2);
+    assert!(matches.iter().all(|deployment| deployment.component == "api"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn list_honors_limit_and_reverse_order() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+
+    let first_id = handler::enqueue_deployment(&pool, deployment_for("api", "region-c"), &[]).await?;
+    handler::enqueue_deployment(&pool, deployment_for("api", "region-c"), &[]).await?;
+
+    let matches = handler::list(
+        &pool,
+        DeploymentFilter {
+            component: Some("api".to_string()),
+            region: Some("region-c".to_string()),
+            reverse: true,
+            limit: Some(1),
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].id, first_id);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn cancel_by_filter_only_cancels_matching_component() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+
+    let api_id = handler::enqueue_deployment(&pool, deployment_for("api", "region-d"), &[]).await?;
+    let worker_id = handler::enqueue_deployment(&pool, deployment_for("worker", "region-d"), &[]).await?;
+
+    let cancelled = handler::cancel::by_filter(
+        &pool,
+        DeploymentFilter {
+            component: Some("api".to_string()),
+            ..Default::default()
+        },
+        Some("cancelling api deployments"),
+        &[],
+    )
+    .await?;
+
+    assert_eq!(cancelled, vec![api_id]);
+
+    let api_status: String = sqlx::query_scalar(
+        "SELECT status::text FROM deployment_runs WHERE deployment_id = $1 ORDER BY attempt_number DESC LIMIT 1",
+    )
+    .bind(api_id)
+    .fetch_one(&pool)
+    .await?;
+    assert_eq!(api_status, "cancelled");
+
+    let worker_status: String = sqlx::query_scalar(
+        "SELECT status::text FROM deployment_runs WHERE deployment_id = $1 ORDER BY attempt_number DESC LIMIT 1",
+    )
+    .bind(worker_id)
+    .fetch_one(&pool)
+    .await?;
+    assert_eq!(worker_status, "queued");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn by_location_still_cancels_everything_at_that_location() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+
+    let api_id = handler::enqueue_deployment(&pool, deployment_for("api", "region-e"), &[]).await?;
+    let worker_id = handler::enqueue_deployment(&pool, deployment_for("worker", "region-e"), &[]).await?;
+
+    let cancelled =
+        handler::cancel::by_location(&pool, "dev", "aws", "region-e", Some(1), Some("draining region-e"), &[])
+            .await?;
+
+    assert_eq!(cancelled, 2);
+
+    for deployment_id in [api_id, worker_id] {
+        let status: String = sqlx::query_scalar(
+            "SELECT status::text FROM deployment_runs WHERE deployment_id = $1 ORDER BY attempt_number DESC LIMIT 1",
+        )
+        .bind(deployment_id)
+        .fetch_one(&pool)
+        .await?;
+        assert_eq!(status, "cancelled");
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn list_matches_any_status_in_a_set() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+
+    let queued_id = handler::enqueue_deployment(&pool, deployment_for("api", "region-f"), &[]).await?;
+    let cancelled_id = handler::enqueue_deployment(&pool, deployment_for("api", "region-f"), &[]).await?;
+    handler::cancel::deployment(&pool, cancelled_id, Some("no longer needed"), &[]).await?;
+    handler::enqueue_deployment(&pool, deployment_for("worker", "region-f"), &[]).await?;
+
+    let matches = handler::list(
+        &pool,
+        DeploymentFilter {
+            region: Some("region-f".to_string()),
+            statuses: vec![DeploymentStatus::Queued, DeploymentStatus::Cancelled],
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    let mut matched_ids: Vec<i64> = matches.iter().map(|d| d.id).collect();
+    matched_ids.sort();
+    let mut expected_ids = vec![queued_id, cancelled_id];
+    expected_ids.sort();
+    assert_eq!(matched_ids, expected_ids);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn list_pages_by_keyset_cursor_instead_of_offset() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+
+    let first_id = handler::enqueue_deployment(&pool, deployment_for("api", "region-g"), &[]).await?;
+    let second_id = handler::enqueue_deployment(&pool, deployment_for("api", "region-g"), &[]).await?;
+    let third_id = handler::enqueue_deployment(&pool, deployment_for("api", "region-g"), &[]).await?;
+
+    let first_page = handler::list(
+        &pool,
+        DeploymentFilter {
+            region: Some("region-g".to_string()),
+            reverse: true,
+            limit: Some(2),
+            ..Default::default()
+        },
+    )
+    .await?;
+    assert_eq!(
+        first_page.iter().map(|d| d.id).collect::<Vec<_>>(),
+        vec![first_id, second_id]
+    );
+
+    // `Deployment` doesn't carry its run's `created_at` itself, so the
+    // cursor for "everything past `second_id`" is looked up directly.
+    let created_at: time::OffsetDateTime =
+        sqlx::query_scalar("SELECT created_at FROM deployment_runs WHERE deployment_id = $1")
+            .bind(second_id)
+            .fetch_one(&pool)
+            .await?;
+
+    let second_page = handler::list(
+        &pool,
+        DeploymentFilter {
+            region: Some("region-g".to_string()),
+            reverse: true,
+            limit: Some(2),
+            after: Some((created_at, second_id)),
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    assert_eq!(
+        second_page.iter().map(|d| d.id).collect::<Vec<_>>(),
+        vec![third_id]
+    );
+
+    Ok(())
+}
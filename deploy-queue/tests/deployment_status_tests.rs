@@ -0,0 +1,71 @@
+use deploy_queue::model::DeploymentStatus;
+
+#[test]
+fn terminal_states_cannot_transition_further() {
+    for terminal in [
+        DeploymentStatus::Finished,
+        DeploymentStatus::Cancelled,
+        DeploymentStatus::Expired,
+        DeploymentStatus::TimedOut,
+        DeploymentStatus::Failed,
+    ] {
+        for next in [
+            DeploymentStatus::Queued,
+            DeploymentStatus::Blocked,
+            DeploymentStatus::Running,
+            DeploymentStatus::Finished,
+            DeploymentStatus::Cancelled,
+            DeploymentStatus::Expired,
+            DeploymentStatus::TimedOut,
+            DeploymentStatus::Failed,
+        ] {
+            assert!(
+                !terminal.can_transition_to(next),
+                "{:?} should not be able to transition to {:?}",
+                terminal,
+                next
+            );
+        }
+    }
+}
+
+#[test]
+fn queued_can_only_move_forward_or_be_cancelled() {
+    assert!(DeploymentStatus::Queued.can_transition_to(DeploymentStatus::Blocked));
+    assert!(DeploymentStatus::Queued.can_transition_to(DeploymentStatus::Running));
+    assert!(DeploymentStatus::Queued.can_transition_to(DeploymentStatus::Cancelled));
+    assert!(DeploymentStatus::Queued.can_transition_to(DeploymentStatus::Expired));
+    assert!(!DeploymentStatus::Queued.can_transition_to(DeploymentStatus::Finished));
+}
+
+#[test]
+fn finishing_requires_running() {
+    assert!(DeploymentStatus::Running.can_transition_to(DeploymentStatus::Finished));
+    assert!(!DeploymentStatus::Queued.can_transition_to(DeploymentStatus::Finished));
+    assert!(!DeploymentStatus::Blocked.can_transition_to(DeploymentStatus::Finished));
+}
+
+#[test]
+fn cancellation_always_wins_from_a_non_terminal_state() {
+    for state in [
+        DeploymentStatus::Queued,
+        DeploymentStatus::Blocked,
+        DeploymentStatus::Running,
+    ] {
+        assert!(state.can_transition_to(DeploymentStatus::Cancelled));
+    }
+}
+
+#[test]
+fn timing_out_requires_running() {
+    assert!(DeploymentStatus::Running.can_transition_to(DeploymentStatus::TimedOut));
+    assert!(!DeploymentStatus::Queued.can_transition_to(DeploymentStatus::TimedOut));
+    assert!(!DeploymentStatus::Blocked.can_transition_to(DeploymentStatus::TimedOut));
+}
+
+#[test]
+fn failing_requires_queued_or_running() {
+    assert!(DeploymentStatus::Queued.can_transition_to(DeploymentStatus::Failed));
+    assert!(DeploymentStatus::Running.can_transition_to(DeploymentStatus::Failed));
+    assert!(!DeploymentStatus::Blocked.can_transition_to(DeploymentStatus::Failed));
+}
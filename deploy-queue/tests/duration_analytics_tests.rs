@@ -0,0 +1,173 @@
+use anyhow::Result;
+use deploy_queue::{handler, model::AnalyticsConfig};
+use sqlx::{Pool, Postgres};
+use time::{Duration, OffsetDateTime};
+
+#[path = "common/test_db_setup.rs"]
+mod database_helpers;
+
+extern crate deploy_queue;
+
+/// Insert a finished deployment run lasting `duration`, which finished
+/// `finished_ago` in the past.
+async fn create_finished_run(
+    pool: &Pool<Postgres>,
+    component: &str,
+    region: &str,
+    environment: &str,
+    duration: Duration,
+    finished_ago: Duration,
+) -> Result<i64> {
+    let deployment_id = sqlx::query!(
+        "INSERT INTO deployments (environment, cloud_provider, region, cell_index, component, version, url, note, concurrency_key)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) RETURNING id",
+        environment,
+        "aws",
+        region,
+        1,
+        component,
+        "v1.0.0",
+        "https://github.com/test",
+        "test deployment",
+        None::<String>
+    )
+    .fetch_one(pool)
+    .await?
+    .id;
+
+    let finish = OffsetDateTime::now_utc() - finished_ago;
+    let start = finish - duration;
+    sqlx::query!(
+        "INSERT INTO deployment_runs (deployment_id, attempt_number, status, start_timestamp, finish_timestamp)
+         VALUES ($1, 1, 'finished', $2, $3)",
+        deployment_id,
+        start,
+        finish
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(deployment_id)
+}
+
+#[tokio::test]
+async fn duration_analytics_reports_percentiles_across_finished_runs() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+    let config = AnalyticsConfig::default();
+
+    for seconds in [10, 20, 30, 40, 100] {
+        create_finished_run(
+            &pool,
+            "api",
+            "us-east-1",
+            "dev",
+            Duration::seconds(seconds),
+            Duration::days(1),
+        )
+        .await?;
+    }
+
+    let analytics = handler::fetch::duration_analytics(&pool, "api", "us-east-1", "dev", &config)
+        .await?
+        .expect("analytics for a group with finished runs");
+
+    assert_eq!(analytics.deployment_count, 5);
+    // p50 of [10, 20, 30, 40, 100] is the median, 30s.
+    assert_eq!(analytics.p50_duration, Duration::seconds(30));
+    // The tail (p99) should reflect the 100s outlier, well above the mean.
+    assert!(analytics.p99_duration > analytics.avg_duration);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn duration_analytics_returns_none_for_a_group_with_no_finished_runs() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+    let config = AnalyticsConfig::default();
+
+    let analytics =
+        handler::fetch::duration_analytics(&pool, "nonexistent", "nowhere", "dev", &config).await?;
+
+    assert!(analytics.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn a_shorter_lookback_excludes_runs_outside_the_window() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+
+    create_finished_run(
+        &pool,
+        "batch",
+        "us-east-1",
+        "dev",
+        Duration::seconds(10),
+        Duration::days(3),
+    )
+    .await?;
+    create_finished_run(
+        &pool,
+        "batch",
+        "us-east-1",
+        "dev",
+        Duration::seconds(20),
+        Duration::days(40),
+    )
+    .await?;
+
+    // Wide enough to see both runs.
+    let wide = AnalyticsConfig {
+        lookback: Duration::days(90),
+        row_cap: 100,
+    };
+    let analytics = handler::fetch::duration_analytics(&pool, "batch", "us-east-1", "dev", &wide)
+        .await?
+        .expect("analytics for a group with finished runs");
+    assert_eq!(analytics.deployment_count, 2);
+
+    // Narrow enough to only see the 3-days-ago run.
+    let narrow = AnalyticsConfig {
+        lookback: Duration::days(7),
+        row_cap: 100,
+    };
+    let analytics = handler::fetch::duration_analytics(&pool, "batch", "us-east-1", "dev", &narrow)
+        .await?
+        .expect("analytics for a group with a run inside the narrow window");
+    assert_eq!(analytics.deployment_count, 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn a_smaller_row_cap_keeps_only_the_most_recent_runs() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+
+    // Oldest run first, each one day further apart, so the most recent
+    // `row_cap` runs are unambiguous.
+    for (seconds, days_ago) in [(10, 5), (20, 4), (30, 3), (40, 2), (50, 1)] {
+        create_finished_run(
+            &pool,
+            "worker",
+            "us-east-1",
+            "dev",
+            Duration::seconds(seconds),
+            Duration::days(days_ago),
+        )
+        .await?;
+    }
+
+    let capped = AnalyticsConfig {
+        lookback: Duration::days(90),
+        row_cap: 2,
+    };
+    let analytics = handler::fetch::duration_analytics(&pool, "worker", "us-east-1", "dev", &capped)
+        .await?
+        .expect("analytics for a group with finished runs");
+
+    // Only the two most recent runs (40s, 50s) should count.
+    assert_eq!(analytics.deployment_count, 2);
+    assert_eq!(analytics.p50_duration, Duration::seconds(45));
+
+    Ok(())
+}
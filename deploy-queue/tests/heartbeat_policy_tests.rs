@@ -0,0 +1,57 @@
+use deploy_queue::model::{HeartbeatPolicy, Severity, StaleHeartbeatDeployment};
+use time::{Duration, OffsetDateTime};
+
+fn stale(elapsed: Duration) -> StaleHeartbeatDeployment {
+    StaleHeartbeatDeployment {
+        id: 1,
+        component: "api".to_string(),
+        version: Some("v1".to_string()),
+        heartbeat_timestamp: OffsetDateTime::now_utc() - elapsed,
+        time_since_heartbeat: elapsed,
+    }
+}
+
+#[test]
+fn severity_for_is_none_below_the_lowest_tier() {
+    let policy = HeartbeatPolicy::default();
+    assert_eq!(policy.severity_for(Duration::minutes(1)), None);
+}
+
+#[test]
+fn severity_for_escalates_through_each_tier() {
+    let policy = HeartbeatPolicy::default();
+    assert_eq!(policy.severity_for(Duration::minutes(2)), Some(Severity::Warn));
+    assert_eq!(policy.severity_for(Duration::minutes(4)), Some(Severity::Warn));
+    assert_eq!(policy.severity_for(Duration::minutes(5)), Some(Severity::Alert));
+    assert_eq!(policy.severity_for(Duration::minutes(9)), Some(Severity::Alert));
+    assert_eq!(policy.severity_for(Duration::minutes(10)), Some(Severity::Page));
+    assert_eq!(policy.severity_for(Duration::hours(1)), Some(Severity::Page));
+}
+
+#[test]
+fn severity_orders_warn_below_alert_below_page() {
+    assert!(Severity::Warn < Severity::Alert);
+    assert!(Severity::Alert < Severity::Page);
+}
+
+#[test]
+fn stale_heartbeat_deployment_severity_delegates_to_policy() {
+    let policy = HeartbeatPolicy::default();
+    assert_eq!(stale(Duration::minutes(6)).severity(&policy), Some(Severity::Alert));
+    assert_eq!(stale(Duration::seconds(1)).severity(&policy), None);
+}
+
+#[test]
+fn summary_includes_a_severity_tag_once_a_tier_is_reached() {
+    let policy = HeartbeatPolicy::default();
+    let summary = stale(Duration::minutes(6)).summary(&policy);
+    assert!(summary.contains("deployment 1 component api heartbeat stale"));
+    assert!(summary.ends_with("[ALERT]"), "summary was: {summary}");
+}
+
+#[test]
+fn summary_omits_the_tag_below_the_lowest_tier() {
+    let policy = HeartbeatPolicy::default();
+    let summary = stale(Duration::seconds(1)).summary(&policy);
+    assert!(!summary.contains('['), "summary was: {summary}");
+}
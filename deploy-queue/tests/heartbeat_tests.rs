@@ -1,6 +1,7 @@
 use anyhow::Result;
-use deploy_queue::{constants::HEARTBEAT_TIMEOUT, handler};
+use deploy_queue::{constants::HEARTBEAT_TIMEOUT, handler, model::DeploymentStatus};
 use time::{Duration as TimeDuration, OffsetDateTime};
+use tokio_util::sync::CancellationToken;
 
 #[path = "common/test_db_setup.rs"]
 mod database_helpers;
@@ -12,15 +13,19 @@ extern crate deploy_queue;
 
 #[tokio::test]
 async fn heartbeat_loop_sets_timestamp() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
     let deployment_id = deployment_fixtures::create_test_deployment(&pool).await?;
 
     // Run the heartbeat and wait a few milliseconds (so it can write the timestamp)
     let heartbeat_pool = pool.clone();
-    let handle = tokio::spawn(async move {
-        handler::run_heartbeat_loop(&heartbeat_pool, deployment_id)
-            .await
-            .ok();
+    let shutdown = CancellationToken::new();
+    let handle = tokio::spawn({
+        let shutdown = shutdown.clone();
+        async move {
+            handler::run_heartbeat_loop(&heartbeat_pool, deployment_id, shutdown)
+                .await
+                .ok();
+        }
     });
     tokio::time::sleep(std::time::Duration::from_millis(100)).await;
 
@@ -37,14 +42,49 @@ async fn heartbeat_loop_sets_timestamp() -> Result<()> {
     );
 
     // Stop the heartbeat loop
-    handle.abort();
+    shutdown.cancel();
+    handle.await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn graceful_shutdown_flushes_final_heartbeat_and_joins_without_abort() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+    let deployment_id = deployment_fixtures::create_test_deployment(&pool).await?;
+
+    let handle = handler::spawn_heartbeat(pool.clone(), deployment_id);
+
+    // Let the loop start without waiting for a full interval tick, so the
+    // only heartbeat on record is the one `shutdown` flushes.
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+    handle
+        .shutdown(std::time::Duration::from_secs(2))
+        .await
+        .expect("heartbeat loop should join cleanly on graceful shutdown");
+
+    let (heartbeat_timestamp,): (Option<OffsetDateTime>,) = sqlx::query_as(
+        r#"SELECT heartbeat_timestamp FROM deployment_runs
+           WHERE deployment_id = $1
+           ORDER BY attempt_number DESC
+           LIMIT 1"#,
+    )
+    .bind(deployment_id)
+    .fetch_one(&pool)
+    .await?;
+
+    assert!(
+        heartbeat_timestamp.is_some(),
+        "Graceful shutdown should flush a final heartbeat before returning"
+    );
 
     Ok(())
 }
 
 #[tokio::test]
 async fn stale_heartbeat_detection_flags_old_running_deployments() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
     let deployment_id = deployment_fixtures::create_running_deployment(&pool).await?;
 
     // Set heartbeat older than the timeout
@@ -83,7 +123,7 @@ async fn stale_heartbeat_detection_flags_old_running_deployments() -> Result<()>
 
 #[tokio::test]
 async fn stale_blocker_gets_cancelled_when_waiting_for_blockers() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     // Create a running deployment with a stale heartbeat that will block others
     let blocking = deployment_fixtures::create_running_deployment(&pool).await?;
@@ -124,3 +164,153 @@ async fn stale_blocker_gets_cancelled_when_waiting_for_blockers() -> Result<()>
 
     Ok(())
 }
+
+#[tokio::test]
+async fn reaper_sweep_times_out_running_deployment_with_expired_lease() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+    let deployment_id = deployment_fixtures::create_running_deployment(&pool).await?;
+
+    let stale_at = OffsetDateTime::now_utc() - TimeDuration::minutes(10);
+    sqlx::query!(
+        "INSERT INTO deployment_runs (deployment_id, attempt_number, status, start_timestamp, heartbeat_timestamp)
+         VALUES ($1, 1, 'running', NOW() - INTERVAL '10 minutes', $2)",
+        deployment_id,
+        stale_at
+    )
+    .execute(&pool)
+    .await?;
+
+    let reaped = handler::reaper::sweep_once(&pool, TimeDuration::minutes(5), &[]).await?;
+    assert_eq!(reaped, 1, "stale running deployment should have been reaped");
+
+    let (status,): (DeploymentStatus,) = sqlx::query_as(
+        "SELECT status FROM deployment_runs WHERE deployment_id = $1 AND attempt_number = 1",
+    )
+    .bind(deployment_id)
+    .fetch_one(&pool)
+    .await?;
+    assert_eq!(status, DeploymentStatus::TimedOut);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn reaper_sweep_leaves_freshly_heartbeated_deployment_alone() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+    let deployment_id = deployment_fixtures::create_running_deployment(&pool).await?;
+
+    sqlx::query!(
+        "INSERT INTO deployment_runs (deployment_id, attempt_number, status, start_timestamp, heartbeat_timestamp)
+         VALUES ($1, 1, 'running', NOW() - INTERVAL '10 minutes', NOW())",
+        deployment_id
+    )
+    .execute(&pool)
+    .await?;
+
+    let reaped = handler::reaper::sweep_once(&pool, TimeDuration::minutes(5), &[]).await?;
+    assert_eq!(
+        reaped, 0,
+        "deployment with a fresh heartbeat should not be reaped"
+    );
+
+    let (status,): (DeploymentStatus,) = sqlx::query_as(
+        "SELECT status FROM deployment_runs WHERE deployment_id = $1 AND attempt_number = 1",
+    )
+    .bind(deployment_id)
+    .fetch_one(&pool)
+    .await?;
+    assert_eq!(status, DeploymentStatus::Running);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn stale_blocker_already_at_max_retries_is_cancelled_but_not_re_enqueued() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+
+    // `create_running_deployment` already leaves max_retries/retry_attempt at
+    // their defaults of 0, so this deployment has no retries left.
+    let blocking = deployment_fixtures::create_running_deployment(&pool).await?;
+    let stale_at =
+        OffsetDateTime::now_utc() - TimeDuration::seconds(HEARTBEAT_TIMEOUT.as_secs() as i64 + 60);
+    sqlx::query("UPDATE deployments SET heartbeat_timestamp = $1 WHERE id = $2")
+        .bind(stale_at)
+        .bind(blocking)
+        .execute(&pool)
+        .await?;
+
+    let waiter = deployment_fixtures::create_test_deployment(&pool).await?;
+    handler::wait_for_blocking_deployments(&pool, waiter).await?;
+
+    let (cancellation_timestamp,): (Option<OffsetDateTime>,) =
+        sqlx::query_as("SELECT cancellation_timestamp FROM deployments WHERE id = $1")
+            .bind(blocking)
+            .fetch_one(&pool)
+            .await?;
+    assert!(
+        cancellation_timestamp.is_some(),
+        "deployment with a stale heartbeat should still be cancelled"
+    );
+
+    let (retry_count,): (i64,) =
+        sqlx::query_as("SELECT COUNT(*) FROM deployments WHERE retry_of = $1")
+            .bind(blocking)
+            .fetch_one(&pool)
+            .await?;
+    assert_eq!(
+        retry_count, 0,
+        "a deployment already at max_retries should not be re-enqueued"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn stale_blocker_retry_waits_out_its_backoff_before_being_claimable_or_blocking() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+
+    let blocking = deployment_fixtures::create_running_deployment(&pool).await?;
+    sqlx::query!(
+        "UPDATE deployments SET max_retries = 3, retry_attempt = 0 WHERE id = $1",
+        blocking
+    )
+    .execute(&pool)
+    .await?;
+    let stale_at =
+        OffsetDateTime::now_utc() - TimeDuration::seconds(HEARTBEAT_TIMEOUT.as_secs() as i64 + 60);
+    sqlx::query("UPDATE deployments SET heartbeat_timestamp = $1 WHERE id = $2")
+        .bind(stale_at)
+        .bind(blocking)
+        .execute(&pool)
+        .await?;
+
+    let waiter = deployment_fixtures::create_test_deployment(&pool).await?;
+    handler::wait_for_blocking_deployments(&pool, waiter).await?;
+
+    let (retry_id, not_before): (i64, Option<OffsetDateTime>) =
+        sqlx::query_as("SELECT id, not_before FROM deployments WHERE retry_of = $1")
+            .bind(blocking)
+            .fetch_one(&pool)
+            .await?;
+    let not_before = not_before.expect("retry should carry a not_before backoff");
+    assert!(
+        not_before > OffsetDateTime::now_utc(),
+        "retry's not_before should still be in the future right after being enqueued"
+    );
+
+    // Not yet due, so `claim_next` must skip straight past it...
+    let claimed = handler::claim::claim_next(&pool, "test-worker", &[]).await?;
+    assert!(
+        !matches!(claimed, Some(d) if d.id == retry_id),
+        "a retry whose not_before hasn't passed yet must not be claimed"
+    );
+
+    // ...and it shouldn't be reported as blocking anything else either.
+    let blocking_deployments = handler::fetch::blocking_deployments(&pool, waiter).await?;
+    assert!(
+        !blocking_deployments.iter().any(|d| d.deployment.id == retry_id),
+        "a retry still waiting out its backoff shouldn't count as a blocker"
+    );
+
+    Ok(())
+}
@@ -14,7 +14,7 @@ extern crate deploy_queue;
 
 #[tokio::test]
 async fn test_insert_deployment_record() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     let environment = "prod";
     let cloud_provider = "aws";
@@ -78,7 +78,7 @@ async fn test_insert_deployment_record() -> Result<()> {
 
 #[tokio::test]
 async fn test_insert_deployment_record_minimal_data() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     // Test with minimal required fields only
     let environment = "dev";
@@ -116,7 +116,7 @@ async fn test_insert_deployment_record_minimal_data() -> Result<()> {
 
 #[tokio::test]
 async fn test_get_deployment_info() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     let environment = "prod";
     let cloud_provider = "aws";
@@ -175,7 +175,7 @@ async fn test_get_deployment_info() -> Result<()> {
 
 #[tokio::test]
 async fn test_start_deployment_success() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
     let deployment_id = deployment_fixtures::create_test_deployment(&pool).await?;
 
     // Initially, start_timestamp should be None
@@ -216,7 +216,7 @@ async fn test_start_deployment_success() -> Result<()> {
 
 #[tokio::test]
 async fn test_finish_deployment_success() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
     let deployment_id = deployment_fixtures::create_running_deployment(&pool).await?;
 
     // Initially, finish_timestamp should be None
@@ -257,7 +257,7 @@ async fn test_finish_deployment_success() -> Result<()> {
 
 #[tokio::test]
 async fn test_cancel_queued_deployment_with_note() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
     let deployment_id = deployment_fixtures::create_test_deployment(&pool).await?;
 
     // Cancel the deployment with a note
@@ -292,7 +292,7 @@ async fn test_cancel_queued_deployment_with_note() -> Result<()> {
 
 #[tokio::test]
 async fn test_cancel_running_deployment_without_note() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
     let deployment_id = deployment_fixtures::create_running_deployment(&pool).await?;
 
     // Cancel the deployment without a note
@@ -314,7 +314,7 @@ async fn test_cancel_running_deployment_without_note() -> Result<()> {
 
 #[tokio::test]
 async fn test_deployment_state_transitions() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
     let deployment_id = deployment_fixtures::create_test_deployment(&pool).await?;
 
     // Initial state: queued (no timestamps)
@@ -358,7 +358,7 @@ async fn test_deployment_state_transitions() -> Result<()> {
 
 #[tokio::test]
 async fn test_invalid_state_transitions() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     // Test finishing a deployment that was never started (queued â†’ finished is invalid)
     let queued_deployment_id = deployment_fixtures::create_test_deployment(&pool).await?;
@@ -373,7 +373,7 @@ async fn test_invalid_state_transitions() -> Result<()> {
 
 #[tokio::test]
 async fn test_operations_on_finished_deployment() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     // Create a finished deployment
     let deployment_id = deployment_fixtures::create_finished_deployment(&pool).await?;
@@ -402,7 +402,7 @@ async fn test_operations_on_finished_deployment() -> Result<()> {
 
 #[tokio::test]
 async fn test_operations_on_cancelled_deployment() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     // Create a cancelled deployment
     let deployment_id = deployment_fixtures::create_cancelled_deployment(&pool).await?;
@@ -431,7 +431,7 @@ async fn test_operations_on_cancelled_deployment() -> Result<()> {
 
 #[tokio::test]
 async fn test_database_constraint_violations() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     // Test invalid environment value (should fail due to CHECK constraint)
     let result = sqlx::query!(
@@ -495,7 +495,7 @@ async fn test_database_constraint_violations() -> Result<()> {
 
 #[tokio::test]
 async fn test_immutable_fields_cannot_be_modified() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     // Create a test deployment
     let deployment_id = deployment_fixtures::create_test_deployment(&pool).await?;
@@ -613,7 +613,7 @@ async fn test_immutable_fields_cannot_be_modified() -> Result<()> {
 
 #[tokio::test]
 async fn test_cancel_deployments_by_component_version() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     // Create test deployments for the same component/version across different regions
     let environment = "dev";
@@ -717,7 +717,7 @@ async fn test_cancel_deployments_by_component_version() -> Result<()> {
 
 #[tokio::test]
 async fn test_cancel_deployments_by_location() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     // Create test deployments in the same location across different components
     let environment = "dev";
@@ -843,7 +843,7 @@ async fn test_cancel_deployments_by_location() -> Result<()> {
 
 #[tokio::test]
 async fn test_cancel_deployments_by_location_without_cell_index() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     // Create test deployments in the same region across different cells
     let environment = "dev";
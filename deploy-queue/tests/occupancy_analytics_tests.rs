@@ -0,0 +1,152 @@
+use anyhow::Result;
+use deploy_queue::handler;
+use sqlx::{Pool, Postgres};
+use time::{Duration, OffsetDateTime};
+
+#[path = "common/test_db_setup.rs"]
+mod database_helpers;
+
+extern crate deploy_queue;
+
+/// Insert a deployment run that started `started_ago` in the past and ran
+/// for `run_duration`. Pass `None` for `run_duration` to leave it `running`
+/// (no `finish_timestamp`) instead of `finished`.
+async fn create_run(
+    pool: &Pool<Postgres>,
+    component: &str,
+    region: &str,
+    environment: &str,
+    started_ago: Duration,
+    run_duration: Option<Duration>,
+) -> Result<i64> {
+    let deployment_id = sqlx::query!(
+        "INSERT INTO deployments (environment, cloud_provider, region, cell_index, component, version, url, note, concurrency_key)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) RETURNING id",
+        environment,
+        "aws",
+        region,
+        1,
+        component,
+        "v1.0.0",
+        "https://github.com/test",
+        "test deployment",
+        None::<String>
+    )
+    .fetch_one(pool)
+    .await?
+    .id;
+
+    let start = OffsetDateTime::now_utc() - started_ago;
+    let finish = run_duration.map(|d| start + d);
+    let status = if finish.is_some() { "finished" } else { "running" };
+
+    sqlx::query!(
+        "INSERT INTO deployment_runs (deployment_id, attempt_number, status, start_timestamp, finish_timestamp)
+         VALUES ($1, 1, $2::deployment_status, $3, $4)",
+        deployment_id,
+        status,
+        start,
+        finish
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(deployment_id)
+}
+
+#[tokio::test]
+async fn occupancy_analytics_reports_throughput_across_finished_runs() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+
+    for minutes_ago in [10, 20, 30] {
+        create_run(
+            &pool,
+            "api",
+            "us-east-1",
+            "dev",
+            Duration::minutes(minutes_ago),
+            Some(Duration::minutes(1)),
+        )
+        .await?;
+    }
+
+    let analytics = handler::fetch::occupancy_analytics(&pool, "api", "us-east-1", "dev", Duration::hours(1))
+        .await?
+        .expect("analytics for a group with finished runs");
+
+    assert_eq!(analytics.throughput_count, 3);
+    assert!(analytics.p50_duration.is_some());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn occupancy_analytics_returns_none_for_a_group_with_no_activity() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+
+    let analytics =
+        handler::fetch::occupancy_analytics(&pool, "nonexistent", "nowhere", "dev", Duration::hours(1)).await?;
+
+    assert!(analytics.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn occupancy_analytics_merges_overlapping_runs_instead_of_double_counting() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+
+    // Run A: [-50m, -20m]. Run B: [-35m, -5m]. These overlap, so the
+    // occupied span is their union, [-50m, -5m] = 45 minutes, not the
+    // 60 minutes you'd get from summing both durations independently.
+    create_run(
+        &pool,
+        "batch",
+        "us-east-1",
+        "dev",
+        Duration::minutes(50),
+        Some(Duration::minutes(30)),
+    )
+    .await?;
+    create_run(
+        &pool,
+        "batch",
+        "us-east-1",
+        "dev",
+        Duration::minutes(35),
+        Some(Duration::minutes(30)),
+    )
+    .await?;
+
+    let analytics = handler::fetch::occupancy_analytics(&pool, "batch", "us-east-1", "dev", Duration::hours(1))
+        .await?
+        .expect("analytics for a group with overlapping runs");
+
+    assert!(
+        (analytics.occupancy_fraction - 0.75).abs() < 0.02,
+        "expected occupancy_fraction near 0.75 (45 of 60 minutes), got {}",
+        analytics.occupancy_fraction
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn occupancy_analytics_counts_a_still_running_deployment_as_occupied_up_to_now() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+
+    create_run(&pool, "worker", "us-east-1", "dev", Duration::minutes(30), None).await?;
+
+    let analytics = handler::fetch::occupancy_analytics(&pool, "worker", "us-east-1", "dev", Duration::hours(1))
+        .await?
+        .expect("analytics for a group with an in-flight run");
+
+    assert!(
+        (analytics.occupancy_fraction - 0.5).abs() < 0.02,
+        "expected occupancy_fraction near 0.5 (30 of 60 minutes), got {}",
+        analytics.occupancy_fraction
+    );
+    assert_eq!(analytics.throughput_count, 0);
+
+    Ok(())
+}
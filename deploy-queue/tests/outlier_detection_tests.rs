@@ -95,7 +95,7 @@ async fn create_running_deployment(
 
 #[tokio::test]
 async fn test_outlier_detection_basic() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     let component = "test-component";
     let region = "test-region";
@@ -152,7 +152,7 @@ async fn test_outlier_detection_basic() -> Result<()> {
 
 #[tokio::test]
 async fn test_no_outliers_when_all_within_range() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     let component = "normal-component";
     let region = "test-region";
@@ -188,7 +188,7 @@ async fn test_no_outliers_when_all_within_range() -> Result<()> {
 
 #[tokio::test]
 async fn test_no_outliers_when_no_running_deployments() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     let component = "finished-component";
     let region = "test-region";
@@ -214,7 +214,7 @@ async fn test_no_outliers_when_no_running_deployments() -> Result<()> {
 
 #[tokio::test]
 async fn test_no_outliers_when_no_analytics_data() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     let component = "new-component";
     let region = "new-region";
@@ -239,7 +239,7 @@ async fn test_no_outliers_when_no_analytics_data() -> Result<()> {
 
 #[tokio::test]
 async fn test_outliers_per_component_region_env() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     // Setup: comp1 in region1/dev has fast deployments
     for duration in [10, 15, 20, 25, 30].iter() {
@@ -284,7 +284,7 @@ async fn test_outliers_per_component_region_env() -> Result<()> {
 
 #[tokio::test]
 async fn test_outliers_excludes_finished_deployments() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     let component = "test-component";
     let region = "test-region";
@@ -321,7 +321,7 @@ async fn test_outliers_excludes_finished_deployments() -> Result<()> {
 
 #[tokio::test]
 async fn test_outliers_excludes_cancelled_deployments() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     let component = "test-component";
     let region = "test-region";
@@ -376,7 +376,7 @@ async fn test_outliers_excludes_cancelled_deployments() -> Result<()> {
 
 #[tokio::test]
 async fn test_outliers_optional_fields_omitted() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     let component = "test-component";
     let region = "test-region";
@@ -414,7 +414,7 @@ async fn test_outliers_optional_fields_omitted() -> Result<()> {
 
 #[tokio::test]
 async fn test_multiple_outliers() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     let component = "test-component";
     let region = "test-region";
@@ -0,0 +1,99 @@
+use anyhow::Result;
+use deploy_queue::{
+    model::{AnalyticsConfig, Cell, Deployment},
+    predict,
+};
+use sqlx::{Pool, Postgres};
+use time::{Duration, OffsetDateTime};
+
+#[path = "common/test_db_setup.rs"]
+mod database_helpers;
+
+extern crate deploy_queue;
+
+async fn create_finished_run(
+    pool: &Pool<Postgres>,
+    component: &str,
+    region: &str,
+    environment: &str,
+    duration: Duration,
+) -> Result<()> {
+    let deployment_id = sqlx::query!(
+        "INSERT INTO deployments (environment, cloud_provider, region, cell_index, component, version, url, note, concurrency_key)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) RETURNING id",
+        environment,
+        "aws",
+        region,
+        1,
+        component,
+        "v1.0.0",
+        "https://github.com/test",
+        "test deployment",
+        None::<String>
+    )
+    .fetch_one(pool)
+    .await?
+    .id;
+
+    let start = OffsetDateTime::now_utc() - Duration::days(1);
+    let finish = start + duration;
+    sqlx::query!(
+        "INSERT INTO deployment_runs (deployment_id, attempt_number, status, start_timestamp, finish_timestamp)
+         VALUES ($1, 1, 'finished', $2, $3)",
+        deployment_id,
+        start,
+        finish
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+fn running_deployment(component: &str, region: &str, environment: &str, started_ago: Duration) -> Deployment {
+    Deployment {
+        component: component.to_string(),
+        cell: Cell {
+            environment: environment.to_string(),
+            cloud_provider: "aws".to_string(),
+            region: region.to_string(),
+            index: 1,
+        },
+        start_timestamp: Some(OffsetDateTime::now_utc() - started_ago),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn eta_is_none_with_fewer_than_two_finished_runs() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+    create_finished_run(&pool, "api", "us-east-1", "dev", Duration::minutes(10)).await?;
+
+    let deployment = running_deployment("api", "us-east-1", "dev", Duration::minutes(1));
+    let prediction = predict::eta(&pool, &deployment, &AnalyticsConfig::default()).await?;
+
+    assert!(prediction.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn eta_predicts_a_band_and_flags_an_overrun_deployment_as_anomalous() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+    for minutes in [9, 10, 11, 10, 10] {
+        create_finished_run(&pool, "api", "us-east-1", "dev", Duration::minutes(minutes)).await?;
+    }
+
+    // Started well over an hour ago, against a ~10 minute history with
+    // (near) zero variance - this is well past any reasonable confidence
+    // band.
+    let deployment = running_deployment("api", "us-east-1", "dev", Duration::hours(2));
+    let prediction = predict::eta(&pool, &deployment, &AnalyticsConfig::default())
+        .await?
+        .expect("analytics for a group with enough finished runs");
+
+    assert!(prediction.is_anomalous);
+    assert!(prediction.upper_bound > prediction.lower_bound);
+
+    Ok(())
+}
@@ -0,0 +1,150 @@
+use deploy_queue::model::{BlockingDeployment, Cell, Deployment, DeploymentStatus, OutlierDeployment};
+use deploy_queue::prometheus;
+use time::{Duration, OffsetDateTime};
+
+fn cell() -> Cell {
+    Cell {
+        environment: "prod".to_string(),
+        cloud_provider: "aws".to_string(),
+        region: "us-east-1".to_string(),
+        index: 1,
+    }
+}
+
+#[test]
+fn remaining_time_at_percentile_matches_the_mean_at_p50() {
+    // probit(0.5) == 0, so the p50 estimate should be exactly `avg_duration`
+    // for a still-`Queued` deployment.
+    let blocking = BlockingDeployment {
+        deployment: Deployment {
+            cell: cell(),
+            ..Default::default()
+        },
+        avg_duration: Some(Duration::minutes(10)),
+        stddev_duration: Some(Duration::minutes(2)),
+    };
+
+    assert_eq!(blocking.remaining_time_at_percentile(0.5), Some(Duration::minutes(10)));
+}
+
+#[test]
+fn remaining_time_at_percentile_grows_with_the_percentile() {
+    let blocking = BlockingDeployment {
+        deployment: Deployment {
+            cell: cell(),
+            ..Default::default()
+        },
+        avg_duration: Some(Duration::minutes(10)),
+        stddev_duration: Some(Duration::minutes(2)),
+    };
+
+    let p50 = blocking.remaining_time_at_percentile(0.5).unwrap();
+    let p90 = blocking.remaining_time_at_percentile(0.9).unwrap();
+    assert!(p90 > p50, "p90 estimate ({p90}) should exceed p50 ({p50})");
+}
+
+#[test]
+fn detect_flags_a_run_far_slower_than_its_peers() {
+    let deployment = Deployment {
+        id: 42,
+        cell: cell(),
+        component: "api".to_string(),
+        ..Default::default()
+    };
+    let samples = [
+        Duration::seconds(60),
+        Duration::seconds(62),
+        Duration::seconds(58),
+        Duration::seconds(61),
+    ];
+
+    let outlier = OutlierDeployment::detect(&deployment, Duration::seconds(600), &samples, 3.5);
+
+    let outlier = outlier.expect("a 10x-slower run should be flagged as an outlier");
+    assert_eq!(outlier.id, 42);
+    assert_eq!(outlier.current_duration, Duration::seconds(600));
+}
+
+#[test]
+fn detect_does_not_flag_a_run_within_the_usual_spread() {
+    let deployment = Deployment {
+        cell: cell(),
+        component: "api".to_string(),
+        ..Default::default()
+    };
+    let samples = [
+        Duration::seconds(60),
+        Duration::seconds(62),
+        Duration::seconds(58),
+        Duration::seconds(61),
+    ];
+
+    assert!(OutlierDeployment::detect(&deployment, Duration::seconds(63), &samples, 3.5).is_none());
+}
+
+#[test]
+fn detect_returns_none_with_no_samples_to_compare_against() {
+    let deployment = Deployment {
+        cell: cell(),
+        ..Default::default()
+    };
+    assert!(OutlierDeployment::detect(&deployment, Duration::seconds(600), &[], 3.5).is_none());
+}
+
+#[test]
+fn render_includes_a_gauge_series_per_deployment_state() {
+    let now = OffsetDateTime::now_utc();
+    let deployments = vec![
+        Deployment {
+            component: "api".to_string(),
+            cell: cell(),
+            ..Default::default()
+        },
+        Deployment {
+            component: "api".to_string(),
+            cell: cell(),
+            status: DeploymentStatus::Running,
+            start_timestamp: Some(now),
+            ..Default::default()
+        },
+    ];
+
+    let rendered = prometheus::render(&deployments, &[]);
+
+    assert!(rendered.contains("deploy_queue_deployments{state=\"queued\"} 1"));
+    assert!(rendered.contains("deploy_queue_deployments{state=\"running\"} 1"));
+    assert!(rendered.contains("deploy_queue_blocking_deployments{cell=\"prod/aws/us-east-1/1\"} 2"));
+}
+
+#[test]
+fn render_duration_histogram_bucket_counts_are_monotonically_non_decreasing() {
+    let now = OffsetDateTime::now_utc();
+    let deployments = vec![
+        Deployment {
+            component: "api".to_string(),
+            start_timestamp: Some(now - Duration::seconds(10)),
+            finish_timestamp: Some(now),
+            ..Default::default()
+        },
+        Deployment {
+            component: "api".to_string(),
+            start_timestamp: Some(now - Duration::minutes(50)),
+            finish_timestamp: Some(now),
+            ..Default::default()
+        },
+    ];
+
+    let rendered = prometheus::render(&deployments, &[]);
+
+    let bucket_counts: Vec<u64> = rendered
+        .lines()
+        .filter(|line| line.starts_with("deploy_queue_deployment_duration_seconds_bucket"))
+        .map(|line| line.rsplit(' ').next().unwrap().parse().unwrap())
+        .collect();
+
+    assert!(
+        bucket_counts.windows(2).all(|pair| pair[0] <= pair[1]),
+        "bucket counts must never decrease as `le` widens: {bucket_counts:?}"
+    );
+    assert_eq!(bucket_counts.last(), Some(&2), "the `+Inf` bucket should see every sample");
+}
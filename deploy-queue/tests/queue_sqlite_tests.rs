@@ -0,0 +1,98 @@
+use anyhow::Result;
+use deploy_queue::{
+    handler::DeploymentFilter,
+    model::{Cell, Deployment},
+    queue::{DeploymentQueue, SqliteQueue},
+};
+
+extern crate deploy_queue;
+
+fn deployment_for(component: &str, version: &str) -> Deployment {
+    Deployment {
+        component: component.to_string(),
+        version: Some(version.to_string()),
+        cell: Cell {
+            environment: "dev".to_string(),
+            cloud_provider: "aws".to_string(),
+            region: "sqlite-region".to_string(),
+            index: 1,
+        },
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn claim_next_returns_deployments_in_enqueue_order() -> Result<()> {
+    let queue = SqliteQueue::connect("sqlite::memory:").await?;
+
+    let first_id = queue.enqueue_deployment(deployment_for("api", "v1"), &[]).await?;
+    let _second_id = queue.enqueue_deployment(deployment_for("api", "v2"), &[]).await?;
+
+    let claimed = queue.claim_next("worker-1", &[]).await?.expect("a queued deployment");
+    assert_eq!(claimed.id, first_id);
+
+    let fetched = queue
+        .fetch_deployment(first_id)
+        .await?
+        .expect("deployment still exists");
+    assert_eq!(fetched.status.to_string(), "running");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn claim_next_returns_none_when_queue_is_empty() -> Result<()> {
+    let queue = SqliteQueue::connect("sqlite::memory:").await?;
+
+    assert!(queue.claim_next("worker-1", &[]).await?.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn list_filters_by_component() -> Result<()> {
+    let queue = SqliteQueue::connect("sqlite::memory:").await?;
+
+    queue.enqueue_deployment(deployment_for("api", "v1"), &[]).await?;
+    queue.enqueue_deployment(deployment_for("worker", "v1"), &[]).await?;
+
+    let matches = queue
+        .list(DeploymentFilter {
+            component: Some("api".to_string()),
+            ..Default::default()
+        })
+        .await?;
+
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].component, "api");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn cancel_by_location_only_cancels_matching_region() -> Result<()> {
+    let queue = SqliteQueue::connect("sqlite::memory:").await?;
+
+    let here_id = queue.enqueue_deployment(deployment_for("api", "v1"), &[]).await?;
+    let elsewhere = Deployment {
+        cell: Cell {
+            region: "other-region".to_string(),
+            ..deployment_for("api", "v1").cell
+        },
+        ..deployment_for("api", "v1")
+    };
+    let elsewhere_id = queue.enqueue_deployment(elsewhere, &[]).await?;
+
+    let cancelled = queue
+        .cancel_by_location("dev", "aws", "sqlite-region", Some(1), None, &[])
+        .await?;
+    assert_eq!(cancelled, 1);
+
+    let here = queue.fetch_deployment(here_id).await?.expect("exists");
+    assert_eq!(here.status.to_string(), "cancelled");
+
+    let elsewhere = queue.fetch_deployment(elsewhere_id).await?.expect("exists");
+    assert_eq!(elsewhere.status.to_string(), "queued");
+
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use anyhow::Result;
+use deploy_queue::{
+    handler::{self, worker::BackoffPolicy},
+    model::DeploymentStatus,
+};
+use time::{Duration as TimeDuration, OffsetDateTime};
+
+#[path = "common/test_db_setup.rs"]
+mod database_helpers;
+
+#[path = "fixtures/deployment.rs"]
+mod deployment_fixtures;
+
+extern crate deploy_queue;
+
+#[tokio::test]
+async fn sweep_requeues_a_lease_expiry_with_backoff_before_giving_up() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+    let deployment_id = deployment_fixtures::create_running_deployment(&pool).await?;
+    let stale_heartbeat = OffsetDateTime::now_utc() - TimeDuration::hours(1);
+    sqlx::query!(
+        "INSERT INTO deployment_runs (deployment_id, attempt_number, status, start_timestamp, heartbeat_timestamp) VALUES ($1, 1, 'running', NOW(), $2)",
+        deployment_id,
+        stale_heartbeat
+    )
+    .execute(&pool)
+    .await?;
+
+    let backoff = BackoffPolicy::Linear {
+        base: TimeDuration::seconds(1),
+        increment: TimeDuration::seconds(1),
+        max: TimeDuration::seconds(10),
+    };
+
+    let reaped = handler::reaper::sweep_once(&pool, TimeDuration::minutes(2), backoff, 3, &[]).await?;
+    assert_eq!(reaped, 1);
+
+    let row = sqlx::query!(
+        r#"SELECT status AS "status: DeploymentStatus", attempts, next_run_at
+           FROM deployment_runs WHERE deployment_id = $1"#,
+        deployment_id
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    assert_eq!(row.status, DeploymentStatus::Queued);
+    assert_eq!(row.attempts, 1);
+    assert!(row.next_run_at > OffsetDateTime::now_utc());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn sweep_times_out_a_lease_expiry_once_max_attempts_is_reached() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+    let deployment_id = deployment_fixtures::create_running_deployment(&pool).await?;
+    let stale_heartbeat = OffsetDateTime::now_utc() - TimeDuration::hours(1);
+    sqlx::query!(
+        "INSERT INTO deployment_runs (deployment_id, attempt_number, status, start_timestamp, heartbeat_timestamp, attempts) VALUES ($1, 1, 'running', NOW(), $2, 2)",
+        deployment_id,
+        stale_heartbeat
+    )
+    .execute(&pool)
+    .await?;
+
+    let backoff = BackoffPolicy::Linear {
+        base: TimeDuration::seconds(1),
+        increment: TimeDuration::seconds(1),
+        max: TimeDuration::seconds(10),
+    };
+
+    let reaped = handler::reaper::sweep_once(&pool, TimeDuration::minutes(2), backoff, 3, &[]).await?;
+    assert_eq!(reaped, 1);
+
+    let row = sqlx::query!(
+        r#"SELECT status AS "status: DeploymentStatus", attempts
+           FROM deployment_runs WHERE deployment_id = $1"#,
+        deployment_id
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    assert_eq!(row.status, DeploymentStatus::TimedOut);
+    assert_eq!(row.attempts, 3);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn deployments_due_for_retry_lists_queued_runs_past_their_backoff() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+
+    let due_id = deployment_fixtures::create_test_deployment(&pool).await?;
+    sqlx::query!(
+        r#"INSERT INTO deployment_runs (deployment_id, attempt_number, status, attempts, next_run_at)
+           VALUES ($1, 1, 'queued', 1, NOW() - INTERVAL '1 minute')"#,
+        due_id
+    )
+    .execute(&pool)
+    .await?;
+
+    let not_yet_due_id = deployment_fixtures::create_test_deployment(&pool).await?;
+    sqlx::query!(
+        r#"INSERT INTO deployment_runs (deployment_id, attempt_number, status, attempts, next_run_at)
+           VALUES ($1, 1, 'queued', 1, NOW() + INTERVAL '1 hour')"#,
+        not_yet_due_id
+    )
+    .execute(&pool)
+    .await?;
+
+    let never_failed_id = deployment_fixtures::create_test_deployment(&pool).await?;
+    sqlx::query!(
+        "INSERT INTO deployment_runs (deployment_id, attempt_number, status) VALUES ($1, 1, 'queued')",
+        never_failed_id
+    )
+    .execute(&pool)
+    .await?;
+
+    let due = handler::fetch::deployments_due_for_retry(&pool, OffsetDateTime::now_utc()).await?;
+
+    assert_eq!(due.len(), 1);
+    assert_eq!(due[0].id, due_id);
+
+    Ok(())
+}
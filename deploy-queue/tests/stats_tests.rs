@@ -0,0 +1,85 @@
+use deploy_queue::model::Deployment;
+use deploy_queue::stats;
+use time::{Duration, OffsetDateTime};
+
+fn finished(component: &str, started_ago: Duration, run_seconds: i64) -> Deployment {
+    let now = OffsetDateTime::now_utc();
+    let start = now - started_ago;
+    Deployment {
+        component: component.to_string(),
+        start_timestamp: Some(start),
+        finish_timestamp: Some(start + Duration::seconds(run_seconds)),
+        ..Default::default()
+    }
+}
+
+fn queued(component: &str) -> Deployment {
+    Deployment {
+        component: component.to_string(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn rollup_groups_by_component_and_counts_states() {
+    let now = OffsetDateTime::now_utc();
+    let deployments = vec![
+        finished("api", Duration::hours(1), 60),
+        finished("api", Duration::hours(2), 120),
+        queued("api"),
+        finished("web", Duration::hours(1), 30),
+    ];
+
+    let rollup = stats::rollup(&deployments, Duration::hours(24), now);
+
+    assert_eq!(rollup.len(), 2);
+
+    let api = rollup.iter().find(|s| s.component == "api").unwrap();
+    assert_eq!(api.total, 3);
+    assert_eq!(api.finished, 2);
+    assert_eq!(api.queued, 1);
+
+    let web = rollup.iter().find(|s| s.component == "web").unwrap();
+    assert_eq!(web.total, 1);
+    assert_eq!(web.finished, 1);
+}
+
+#[test]
+fn rollup_excludes_deployments_outside_the_lookback_window() {
+    let now = OffsetDateTime::now_utc();
+    let deployments = vec![
+        finished("api", Duration::hours(1), 60),
+        finished("api", Duration::hours(48), 60),
+    ];
+
+    let rollup = stats::rollup(&deployments, Duration::hours(24), now);
+
+    let api = rollup.iter().find(|s| s.component == "api").unwrap();
+    assert_eq!(api.total, 1, "the deployment finished 48h ago should fall outside a 24h lookback");
+}
+
+#[test]
+fn rollup_always_includes_still_queued_deployments_regardless_of_window() {
+    let now = OffsetDateTime::now_utc();
+    let deployments = vec![queued("api")];
+
+    let rollup = stats::rollup(&deployments, Duration::hours(1), now);
+
+    let api = rollup.iter().find(|s| s.component == "api").unwrap();
+    assert_eq!(api.total, 1);
+    assert_eq!(api.queued, 1);
+}
+
+#[test]
+fn rollup_computes_mean_duration_over_finished_runs() {
+    let now = OffsetDateTime::now_utc();
+    let deployments = vec![
+        finished("api", Duration::hours(1), 60),
+        finished("api", Duration::hours(1), 120),
+    ];
+
+    let rollup = stats::rollup(&deployments, Duration::hours(24), now);
+
+    let api = rollup.iter().find(|s| s.component == "api").unwrap();
+    assert_eq!(api.mean_duration, Duration::seconds(90));
+}
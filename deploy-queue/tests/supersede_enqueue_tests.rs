@@ -0,0 +1,74 @@
+use anyhow::Result;
+use deploy_queue::{handler, model::Deployment};
+
+#[path = "common/test_db_setup.rs"]
+mod database_helpers;
+
+extern crate deploy_queue;
+
+fn deployment_for(component: &str, version: &str) -> Deployment {
+    Deployment {
+        component: component.to_string(),
+        version: Some(version.to_string()),
+        cell: deploy_queue::model::Cell {
+            environment: "dev".to_string(),
+            cloud_provider: "aws".to_string(),
+            region: "supersede-region".to_string(),
+            index: 1,
+        },
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn superseding_enqueue_cancels_older_non_terminal_deployments_for_the_same_target() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+
+    let (old_id, superseded) =
+        handler::enqueue_deployment_superseding_older(&pool, deployment_for("api", "v1"), &[]).await?;
+    assert!(superseded.is_empty());
+
+    let (new_id, superseded) =
+        handler::enqueue_deployment_superseding_older(&pool, deployment_for("api", "v2"), &[]).await?;
+    assert_eq!(superseded, vec![old_id]);
+
+    let old_status: String = sqlx::query_scalar(
+        "SELECT status::text FROM deployment_runs WHERE deployment_id = $1 ORDER BY attempt_number DESC LIMIT 1",
+    )
+    .bind(old_id)
+    .fetch_one(&pool)
+    .await?;
+    assert_eq!(old_status, "cancelled");
+
+    let new_status: String = sqlx::query_scalar(
+        "SELECT status::text FROM deployment_runs WHERE deployment_id = $1 ORDER BY attempt_number DESC LIMIT 1",
+    )
+    .bind(new_id)
+    .fetch_one(&pool)
+    .await?;
+    assert_eq!(new_status, "queued");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn superseding_enqueue_leaves_unrelated_targets_alone() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+
+    let (other_id, _) =
+        handler::enqueue_deployment_superseding_older(&pool, deployment_for("worker", "v1"), &[]).await?;
+    let (_new_id, superseded) =
+        handler::enqueue_deployment_superseding_older(&pool, deployment_for("api", "v2"), &[]).await?;
+
+    assert!(superseded.is_empty());
+
+    let other_status: String = sqlx::query_scalar(
+        "SELECT status::text FROM deployment_runs WHERE deployment_id = $1 ORDER BY attempt_number DESC LIMIT 1",
+    )
+    .bind(other_id)
+    .fetch_one(&pool)
+    .await?;
+    assert_eq!(other_status, "queued");
+
+    Ok(())
+}
@@ -72,7 +72,7 @@ async fn cancel_deployment(pool: &Pool<Postgres>, id: i64) -> Result<()> {
 
 #[tokio::test]
 async fn test_prod_latest_deployments_basic() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     // Insert deployments with different versions
     let id1 = insert_deployment(&pool, "us-east-1", "api", Some("v1.0.0"), "prod").await?;
@@ -110,7 +110,7 @@ async fn test_prod_latest_deployments_basic() -> Result<()> {
 
 #[tokio::test]
 async fn test_prod_latest_deployments_pending_status() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     // Insert deployments where one region is behind
     // Insert v1.0.0 to us-west-1 first (lower ID)
@@ -157,7 +157,7 @@ async fn test_prod_latest_deployments_pending_status() -> Result<()> {
 
 #[tokio::test]
 async fn test_prod_latest_deployments_excludes_cancelled() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     // Insert a cancelled deployment
     let id1 = insert_deployment(&pool, "us-east-1", "api", Some("v1.0.0"), "prod").await?;
@@ -183,7 +183,7 @@ async fn test_prod_latest_deployments_excludes_cancelled() -> Result<()> {
 
 #[tokio::test]
 async fn test_prod_latest_deployments_excludes_null_versions() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     // Insert deployment without version
     let _id1 = insert_deployment(&pool, "us-east-1", "api", None, "prod").await?;
@@ -208,7 +208,7 @@ async fn test_prod_latest_deployments_excludes_null_versions() -> Result<()> {
 
 #[tokio::test]
 async fn test_prod_latest_deployments_uses_highest_id_for_max_version() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     // Insert v2.0.0 first (lower ID)
     let _id1 = insert_deployment(&pool, "us-east-1", "api", Some("v2.0.0"), "prod").await?;
@@ -237,7 +237,7 @@ async fn test_prod_latest_deployments_uses_highest_id_for_max_version() -> Resul
 
 #[tokio::test]
 async fn test_prod_current_deployments_excludes_finished() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     // Insert a finished deployment (outside buffer time)
     let id1 = insert_deployment(&pool, "us-east-1", "api", Some("v1.0.0"), "prod").await?;
@@ -271,7 +271,7 @@ async fn test_prod_current_deployments_excludes_finished() -> Result<()> {
 
 #[tokio::test]
 async fn test_prod_current_deployments_includes_buffering() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     // Insert a deployment that finished recently (within buffer time)
     let id1 = insert_deployment(&pool, "us-east-1", "api", Some("v1.0.0"), "prod").await?;
@@ -292,7 +292,7 @@ async fn test_prod_current_deployments_includes_buffering() -> Result<()> {
 
 #[tokio::test]
 async fn test_prod_current_deployments_shows_analytics() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     // Insert and finish some deployments to generate analytics
     for _ in 0..5 {
@@ -328,7 +328,7 @@ async fn test_prod_current_deployments_shows_analytics() -> Result<()> {
 
 #[tokio::test]
 async fn test_prod_finished_deployments_only_shows_finished() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     // Insert various deployment states
     let _id1 = insert_deployment(&pool, "us-east-1", "api", Some("v1.0.0"), "prod").await?;
@@ -378,7 +378,7 @@ async fn test_prod_finished_deployments_only_shows_finished() -> Result<()> {
 
 #[tokio::test]
 async fn test_prod_finished_deployments_excludes_cancelled() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     // Insert a finished deployment
     let id1 = insert_deployment(&pool, "us-east-1", "api", Some("v1.0.0"), "prod").await?;
@@ -410,7 +410,7 @@ async fn test_prod_finished_deployments_excludes_cancelled() -> Result<()> {
 
 #[tokio::test]
 async fn test_views_only_show_prod_environment() -> Result<()> {
-    let pool = database_helpers::setup_test_db().await?;
+    let (pool, _db) = database_helpers::setup_test_db().await?;
 
     // Insert deployments in both environments
     let prod_id = insert_deployment(&pool, "us-east-1", "api", Some("v1.0.0"), "prod").await?;
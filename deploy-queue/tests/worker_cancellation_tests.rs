@@ -0,0 +1,90 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use deploy_queue::{
+    handler::{
+        self,
+        worker::{BackoffPolicy, DeploymentProcessor, WorkerConfig},
+    },
+    model::{Deployment, DeploymentStatus},
+};
+use time::Duration as TimeDuration;
+use tokio_util::sync::CancellationToken;
+
+#[path = "common/test_db_setup.rs"]
+mod database_helpers;
+
+#[path = "fixtures/deployment.rs"]
+mod deployment_fixtures;
+
+extern crate deploy_queue;
+
+/// A processor that cancels the deployment out from under itself (simulating
+/// `cancel::deployment` being called by another actor while it's running),
+/// then keeps "working" past that point, to prove its own outcome gets
+/// suppressed either way.
+struct CancelsItselfThenSucceeds {
+    pool: sqlx::Pool<sqlx::Postgres>,
+}
+
+#[async_trait]
+impl DeploymentProcessor for CancelsItselfThenSucceeds {
+    async fn process(&self, deployment: &Deployment, cancellation: &CancellationToken) -> Result<()> {
+        handler::cancel::deployment(&self.pool, deployment.id, None::<String>, &[]).await?;
+
+        // Give the background poller a chance to observe the cancellation
+        // and flip the token before this "completes successfully".
+        for _ in 0..20 {
+            if cancellation.is_cancelled() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn cancellation_during_processing_suppresses_the_processors_own_outcome() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+    let deployment_id = deployment_fixtures::create_test_deployment(&pool).await?;
+    sqlx::query!(
+        "INSERT INTO deployment_runs (deployment_id, attempt_number, status) VALUES ($1, 1, 'queued')",
+        deployment_id
+    )
+    .execute(&pool)
+    .await?;
+
+    let processor = CancelsItselfThenSucceeds { pool: pool.clone() };
+    let config = WorkerConfig {
+        worker_id: "test-worker".to_string(),
+        poll_interval: TimeDuration::milliseconds(10),
+        backoff: BackoffPolicy::Exponential {
+            base: TimeDuration::milliseconds(1),
+            factor: 2.0,
+            max: TimeDuration::seconds(1),
+        },
+        max_attempts: 5,
+    };
+
+    // The loop only returns on a fatal error; give it a window to claim,
+    // process, and observe the cancellation, then stop waiting on it.
+    let _ = tokio::time::timeout(
+        std::time::Duration::from_secs(2),
+        handler::worker::run(&pool, &processor, config, &[], CancellationToken::new()),
+    )
+    .await;
+
+    let row = sqlx::query!(
+        r#"SELECT status AS "status: DeploymentStatus" FROM deployment_runs WHERE deployment_id = $1"#,
+        deployment_id
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    // Despite the processor returning Ok(()), the run stays `cancelled` -
+    // `run` must not resurrect it as `finished`.
+    assert_eq!(row.status, DeploymentStatus::Cancelled);
+
+    Ok(())
+}
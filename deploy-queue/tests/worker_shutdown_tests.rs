@@ -0,0 +1,127 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use deploy_queue::{
+    handler::{
+        self,
+        worker::{BackoffPolicy, DeploymentProcessor, WorkerConfig},
+    },
+    model::{Deployment, DeploymentStatus},
+};
+use std::sync::Arc;
+use time::Duration as TimeDuration;
+use tokio_util::sync::CancellationToken;
+
+#[path = "common/test_db_setup.rs"]
+mod database_helpers;
+
+#[path = "fixtures/deployment.rs"]
+mod deployment_fixtures;
+
+extern crate deploy_queue;
+
+/// A processor that finishes promptly once it sees the shared token it was
+/// handed get cancelled, to simulate a well-behaved long-running job.
+struct WaitsForCancellation;
+
+#[async_trait]
+impl DeploymentProcessor for WaitsForCancellation {
+    async fn process(&self, _deployment: &Deployment, cancellation: &CancellationToken) -> Result<()> {
+        cancellation.cancelled().await;
+        Ok(())
+    }
+}
+
+/// A processor that never returns on its own - only a process abort, not a
+/// cooperative shutdown, could stop this one.
+struct NeverFinishes;
+
+#[async_trait]
+impl DeploymentProcessor for NeverFinishes {
+    async fn process(&self, _deployment: &Deployment, _cancellation: &CancellationToken) -> Result<()> {
+        std::future::pending().await
+    }
+}
+
+fn test_config() -> WorkerConfig {
+    WorkerConfig {
+        worker_id: "shutdown-test-worker".to_string(),
+        poll_interval: TimeDuration::milliseconds(10),
+        backoff: BackoffPolicy::Exponential {
+            base: TimeDuration::milliseconds(1),
+            factor: 2.0,
+            max: TimeDuration::seconds(1),
+        },
+        max_attempts: 5,
+    }
+}
+
+#[tokio::test]
+async fn shutdown_lets_a_cooperative_processor_drain_cleanly() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+    let deployment_id = deployment_fixtures::create_test_deployment(&pool).await?;
+    sqlx::query!(
+        "INSERT INTO deployment_runs (deployment_id, attempt_number, status) VALUES ($1, 1, 'queued')",
+        deployment_id
+    )
+    .execute(&pool)
+    .await?;
+
+    let handle = handler::worker::spawn(
+        pool.clone(),
+        Arc::new(WaitsForCancellation),
+        test_config(),
+        vec![],
+    );
+
+    // Give the loop a chance to claim the run before asking it to shut down.
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+    handle.shutdown(std::time::Duration::from_secs(2)).await?;
+
+    let row = sqlx::query!(
+        r#"SELECT status AS "status: DeploymentStatus", run_host FROM deployment_runs WHERE deployment_id = $1"#,
+        deployment_id
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    // The processor observed the cancellation and returned - `run` left the
+    // row as-is rather than recording that outcome, so it's still `running`
+    // under this worker, ready for the reaper or a future claim.
+    assert_eq!(row.status, DeploymentStatus::Running);
+    assert_eq!(row.run_host.as_deref(), Some("shutdown-test-worker"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn shutdown_resets_stuck_running_rows_after_timeout() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+    let deployment_id = deployment_fixtures::create_test_deployment(&pool).await?;
+    sqlx::query!(
+        "INSERT INTO deployment_runs (deployment_id, attempt_number, status) VALUES ($1, 1, 'queued')",
+        deployment_id
+    )
+    .execute(&pool)
+    .await?;
+
+    let handle = handler::worker::spawn(pool.clone(), Arc::new(NeverFinishes), test_config(), vec![]);
+
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+    handle.shutdown(std::time::Duration::from_millis(200)).await?;
+
+    let row = sqlx::query!(
+        r#"SELECT status AS "status: DeploymentStatus", run_host, start_timestamp
+           FROM deployment_runs WHERE deployment_id = $1"#,
+        deployment_id
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    assert_eq!(row.status, DeploymentStatus::Queued);
+    assert!(row.run_host.is_none());
+    assert!(row.start_timestamp.is_none());
+
+    Ok(())
+}
@@ -0,0 +1,174 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use deploy_queue::{
+    handler::{
+        self,
+        worker::{BackoffPolicy, DeploymentProcessor, WorkerConfig},
+    },
+    model::{Deployment, DeploymentStatus},
+};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+use time::Duration as TimeDuration;
+use tokio_util::sync::CancellationToken;
+
+#[path = "common/test_db_setup.rs"]
+mod database_helpers;
+
+#[path = "fixtures/deployment.rs"]
+mod deployment_fixtures;
+
+extern crate deploy_queue;
+
+/// A processor that always fails, counting how many times it was invoked.
+struct AlwaysFails {
+    calls: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl DeploymentProcessor for AlwaysFails {
+    async fn process(&self, _deployment: &Deployment, _cancellation: &CancellationToken) -> Result<()> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        anyhow::bail!("processor intentionally failed");
+    }
+}
+
+async fn run_worker_once(
+    pool: &sqlx::Pool<sqlx::Postgres>,
+    processor: &dyn DeploymentProcessor,
+    max_attempts: i32,
+) {
+    let config = WorkerConfig {
+        worker_id: "test-worker".to_string(),
+        poll_interval: TimeDuration::milliseconds(10),
+        backoff: BackoffPolicy::Exponential {
+            base: TimeDuration::milliseconds(1),
+            factor: 2.0,
+            max: TimeDuration::seconds(1),
+        },
+        max_attempts,
+    };
+
+    let result = tokio::time::timeout(
+        std::time::Duration::from_millis(100),
+        handler::worker::run(pool, processor, config, &[], CancellationToken::new()),
+    )
+    .await;
+
+    // The loop only returns on error; timing out is the expected way to
+    // stop it after it has had a chance to claim and process the run.
+    assert!(result.is_err(), "worker loop should still be running");
+}
+
+#[tokio::test]
+async fn failed_processor_reschedules_with_backoff() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+    let deployment_id = deployment_fixtures::create_test_deployment(&pool).await?;
+    sqlx::query!(
+        "INSERT INTO deployment_runs (deployment_id, attempt_number, status) VALUES ($1, 1, 'queued')",
+        deployment_id
+    )
+    .execute(&pool)
+    .await?;
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let processor = AlwaysFails {
+        calls: calls.clone(),
+    };
+
+    run_worker_once(&pool, &processor, 5).await;
+
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    let row = sqlx::query!(
+        r#"SELECT status AS "status: DeploymentStatus", attempts, next_run_at
+           FROM deployment_runs WHERE deployment_id = $1"#,
+        deployment_id
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    assert_eq!(row.status, DeploymentStatus::Queued);
+    assert_eq!(row.attempts, 1);
+    assert!(row.next_run_at > time::OffsetDateTime::now_utc());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn processor_gives_up_after_max_attempts() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+    let deployment_id = deployment_fixtures::create_test_deployment(&pool).await?;
+    sqlx::query!(
+        "INSERT INTO deployment_runs (deployment_id, attempt_number, status, attempts) VALUES ($1, 1, 'queued', 0)",
+        deployment_id
+    )
+    .execute(&pool)
+    .await?;
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let processor = AlwaysFails {
+        calls: calls.clone(),
+    };
+
+    run_worker_once(&pool, &processor, 1).await;
+
+    let row = sqlx::query!(
+        r#"SELECT status AS "status: DeploymentStatus", attempts
+           FROM deployment_runs WHERE deployment_id = $1"#,
+        deployment_id
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    assert_eq!(row.status, DeploymentStatus::Failed);
+    assert_eq!(row.attempts, 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn fail_deployment_reschedules_with_linear_backoff() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+    let deployment_id = deployment_fixtures::create_test_deployment(&pool).await?;
+    sqlx::query!(
+        "INSERT INTO deployment_runs (deployment_id, attempt_number, status, attempts) VALUES ($1, 1, 'running', 1)",
+        deployment_id
+    )
+    .execute(&pool)
+    .await?;
+
+    let gave_up = handler::worker::fail_deployment(
+        &pool,
+        deployment_id,
+        "reported failed externally",
+        BackoffPolicy::Linear {
+            base: TimeDuration::seconds(1),
+            increment: TimeDuration::seconds(1),
+            max: TimeDuration::seconds(10),
+        },
+        5,
+        &[],
+    )
+    .await?;
+
+    assert!(!gave_up);
+
+    let row = sqlx::query!(
+        r#"SELECT status AS "status: DeploymentStatus", attempts, next_run_at
+           FROM deployment_runs WHERE deployment_id = $1"#,
+        deployment_id
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    // base (1s) + increment (1s) * attempts-before-increment (1) = 2s out.
+    assert_eq!(row.status, DeploymentStatus::Queued);
+    assert_eq!(row.attempts, 2);
+    let delay = row.next_run_at - time::OffsetDateTime::now_utc();
+    assert!(delay > TimeDuration::seconds(1) && delay <= TimeDuration::seconds(2));
+
+    Ok(())
+}
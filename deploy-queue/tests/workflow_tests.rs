@@ -0,0 +1,131 @@
+use anyhow::Result;
+use deploy_queue::{
+    model::{Cell, Deployment},
+    workflow::WorkflowBuilder,
+};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+use tokio_util::sync::CancellationToken;
+
+#[path = "common/test_db_setup.rs"]
+mod database_helpers;
+
+#[path = "fixtures/deployment.rs"]
+mod deployment_fixtures;
+
+extern crate deploy_queue;
+
+fn test_deployment(id: i64) -> Deployment {
+    Deployment {
+        id,
+        cell: Cell {
+            environment: "dev".to_string(),
+            cloud_provider: "aws".to_string(),
+            region: "test-region".to_string(),
+            index: 1,
+        },
+        component: "test-component".to_string(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn replaying_a_workflow_skips_already_completed_activities() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+    let deployment_id = deployment_fixtures::create_test_deployment(&pool).await?;
+    let deployment = test_deployment(deployment_id);
+
+    let first_calls = Arc::new(AtomicUsize::new(0));
+    let second_calls = Arc::new(AtomicUsize::new(0));
+
+    let build_workflow = |first_calls: Arc<AtomicUsize>, second_calls: Arc<AtomicUsize>| {
+        WorkflowBuilder::new()
+            .activity("first-step", move |_ctx| {
+                let first_calls = first_calls.clone();
+                async move {
+                    first_calls.fetch_add(1, Ordering::SeqCst);
+                    Ok(serde_json::json!({ "step": 1 }))
+                }
+            })
+            .activity("second-step", move |_ctx| {
+                let second_calls = second_calls.clone();
+                async move {
+                    second_calls.fetch_add(1, Ordering::SeqCst);
+                    Ok(serde_json::json!({ "step": 2 }))
+                }
+            })
+            .build()
+    };
+
+    let token = CancellationToken::new();
+
+    // First run: both activities execute.
+    let workflow = build_workflow(first_calls.clone(), second_calls.clone());
+    deploy_queue::workflow::run(&pool, &workflow, &deployment, &token).await?;
+    assert_eq!(first_calls.load(Ordering::SeqCst), 1);
+    assert_eq!(second_calls.load(Ordering::SeqCst), 1);
+
+    // Replay: neither activity should run again, since both are recorded complete.
+    let workflow = build_workflow(first_calls.clone(), second_calls.clone());
+    deploy_queue::workflow::run(&pool, &workflow, &deployment, &token).await?;
+    assert_eq!(first_calls.load(Ordering::SeqCst), 1);
+    assert_eq!(second_calls.load(Ordering::SeqCst), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn replaying_a_workflow_resumes_after_a_failed_activity() -> Result<()> {
+    let (pool, _db) = database_helpers::setup_test_db().await?;
+    let deployment_id = deployment_fixtures::create_test_deployment(&pool).await?;
+    let deployment = test_deployment(deployment_id);
+
+    let first_calls = Arc::new(AtomicUsize::new(0));
+    let second_calls = Arc::new(AtomicUsize::new(0));
+    let should_fail_second = Arc::new(std::sync::atomic::AtomicBool::new(true));
+
+    let token = CancellationToken::new();
+
+    let build_workflow = |first_calls: Arc<AtomicUsize>,
+                          second_calls: Arc<AtomicUsize>,
+                          should_fail_second: Arc<std::sync::atomic::AtomicBool>| {
+        WorkflowBuilder::new()
+            .activity("first-step", move |_ctx| {
+                let first_calls = first_calls.clone();
+                async move {
+                    first_calls.fetch_add(1, Ordering::SeqCst);
+                    Ok(serde_json::Value::Null)
+                }
+            })
+            .activity("second-step", move |_ctx| {
+                let second_calls = second_calls.clone();
+                let should_fail_second = should_fail_second.clone();
+                async move {
+                    second_calls.fetch_add(1, Ordering::SeqCst);
+                    if should_fail_second.load(Ordering::SeqCst) {
+                        anyhow::bail!("second step intentionally failed");
+                    }
+                    Ok(serde_json::Value::Null)
+                }
+            })
+            .build()
+    };
+
+    let workflow = build_workflow(first_calls.clone(), second_calls.clone(), should_fail_second.clone());
+    let result = deploy_queue::workflow::run(&pool, &workflow, &deployment, &token).await;
+    assert!(result.is_err(), "second-step should have failed the first run");
+    assert_eq!(first_calls.load(Ordering::SeqCst), 1);
+    assert_eq!(second_calls.load(Ordering::SeqCst), 1);
+
+    should_fail_second.store(false, Ordering::SeqCst);
+    let workflow = build_workflow(first_calls.clone(), second_calls.clone(), should_fail_second.clone());
+    deploy_queue::workflow::run(&pool, &workflow, &deployment, &token).await?;
+
+    // first-step was already recorded complete, so only second-step re-runs.
+    assert_eq!(first_calls.load(Ordering::SeqCst), 1);
+    assert_eq!(second_calls.load(Ordering::SeqCst), 2);
+
+    Ok(())
+}
@@ -0,0 +1,82 @@
+//! AWS-style decorrelated-jitter backoff, used instead of a flat retry
+//! interval so many concurrent callers contending for the same resource
+//! spread their retries out instead of all hammering the API in lockstep.
+
+use std::time::Duration;
+
+use anyhow::Result;
+use log::info;
+use rand::Rng;
+use tokio::time::sleep;
+
+/// One backoff per retried operation - construct a fresh instance at the
+/// start of the operation rather than sharing one across calls, so a
+/// previous operation's failures don't carry over into the next.
+pub struct Backoff {
+    base: Duration,
+    cap: Duration,
+    max_attempts: usize,
+    current: Duration,
+    attempts: usize,
+}
+
+impl Backoff {
+    pub fn new(base: Duration, cap: Duration, max_attempts: usize) -> Self {
+        Self {
+            base,
+            cap,
+            max_attempts,
+            current: base,
+            attempts: 0,
+        }
+    }
+
+    /// Sleep the next decorrelated-jitter delay (`min(cap, rand(base,
+    /// current * 3))`) and count the attempt, bailing with `context` once
+    /// `max_attempts` is exceeded.
+    pub async fn retry(&mut self, context: &str) -> Result<()> {
+        self.attempts += 1;
+        if self.attempts > self.max_attempts {
+            anyhow::bail!("{context} (gave up after {} attempts)", self.max_attempts);
+        }
+
+        let delay = self.next_delay();
+        info!("{context}. Retrying in {:.1} seconds...", delay.as_secs_f64());
+        sleep(delay).await;
+        Ok(())
+    }
+
+    /// Sleep the next decorrelated-jitter delay, same as `retry`, but never
+    /// gives up - for waits like `reserve`'s busy/conflict loop, where the
+    /// resource being held isn't a failure to bail out of. Returns the delay
+    /// slept, so callers that track cumulative wait time don't have to
+    /// duplicate the jitter math.
+    pub async fn wait(&mut self) -> Duration {
+        let delay = self.next_delay();
+        info!("Retrying in {:.1} seconds...", delay.as_secs_f64());
+        sleep(delay).await;
+        delay
+    }
+
+    /// Back to `base` - call this after a successful attempt so the next
+    /// failure starts the jitter over instead of picking up where a prior,
+    /// unrelated failure run left off.
+    pub fn reset(&mut self) {
+        self.current = self.base;
+        self.attempts = 0;
+    }
+
+    fn next_delay(&mut self) -> Duration {
+        let upper = self.current.saturating_mul(3).max(self.base);
+        let next = if upper == self.base {
+            self.base
+        } else {
+            Duration::from_millis(
+                rand::thread_rng().gen_range(self.base.as_millis() as u64..=upper.as_millis() as u64),
+            )
+        };
+        let next = next.min(self.cap);
+        self.current = next;
+        next
+    }
+}
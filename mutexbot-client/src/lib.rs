@@ -0,0 +1,467 @@
+//! Shared lock client for reserving and releasing resources.
+//!
+//! This is the code the `mutexbot` CLI and the deploy queue's
+//! `--reserve-resource` integration both call, so the retry/backoff and
+//! reservation-conflict handling only has to be right in one place.
+//!
+//! Reservations go through a pluggable [`LockBackend`] rather than a
+//! hardcoded `https://mutexbot.com/api/...` client, so a team without a
+//! mutexbot account can point the same callers at [`RedisBackend`] instead.
+//! The orchestration below (waiting out an existing reservation, retrying on
+//! conflict) is backend-agnostic; each backend only has to implement a
+//! single-attempt `reserve`/`release`/`fetch_resource` and its own retry
+//! policy for transient failures.
+
+use std::{convert::Infallible, time::Duration};
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use log::info;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tokio::time::sleep;
+
+mod backoff;
+mod mutexbot_backend;
+pub mod notifier;
+mod redis_backend;
+
+pub use backoff::Backoff;
+pub use mutexbot_backend::MutexbotBackend;
+pub use redis_backend::RedisBackend;
+
+use notifier::Notifier;
+
+/// Starting delay for a backend's own transport-failure/server-error
+/// `Backoff` (see `LockBackend` implementations).
+pub const FAILURE_BACKOFF_BASE: Duration = Duration::from_millis(500);
+/// Ceiling for a backend's transport-failure/server-error `Backoff`.
+pub const FAILURE_BACKOFF_CAP: Duration = Duration::from_secs(30);
+/// How many transport failures/server errors a backend tolerates before
+/// giving up on an operation.
+pub const FAILURE_MAX_ATTEMPTS: usize = 15;
+
+/// Starting delay for the busy/conflict wait in `reserve`'s retry loop.
+/// Longer-capped than `FAILURE_BACKOFF_*` since a conflict isn't a failure -
+/// it just means someone else is holding the resource.
+pub const BUSY_BACKOFF_BASE: Duration = Duration::from_secs(5);
+pub const BUSY_BACKOFF_CAP: Duration = Duration::from_secs(5 * 60);
+
+/// Fraction of a reservation's remaining lifetime to let elapse before
+/// `run_renewal_loop` renews it - renewing at the midpoint leaves slack for a
+/// slow renewal attempt without ever letting the lease actually expire.
+const GUARD_RENEWAL_FRACTION: f64 = 0.5;
+
+/// Renewal interval `run_renewal_loop` falls back to when it can't read back
+/// a parseable expiration for the reservation it just took (e.g. the backend
+/// doesn't report one, or the lookup right after reserving failed).
+const GUARD_RENEWAL_FALLBACK: Duration = Duration::from_secs(30);
+
+/// How long `reserve`/`reserve_exclusive` wait on a blocker before alerting
+/// through a [`BlockingNotify`], configured via `MUTEXBOT_BLOCK_NOTIFY_AFTER`
+/// (a humantime duration, e.g. `10m`). Unset disables blocking-reservation
+/// alerts.
+pub fn blocking_notify_after_from_env() -> Result<Option<Duration>> {
+    match std::env::var("MUTEXBOT_BLOCK_NOTIFY_AFTER") {
+        Ok(value) => Ok(Some(
+            humantime::parse_duration(&value).context("Failed to parse MUTEXBOT_BLOCK_NOTIFY_AFTER")?,
+        )),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Alert once a reservation has blocked `reserve`/`reserve_exclusive` for
+/// longer than `after`. Built from [`blocking_notify_after_from_env`] and a
+/// [`notifier::from_env`] list; passing `None` to the reservation functions
+/// disables alerting entirely.
+pub struct BlockingNotify<'a> {
+    pub notifiers: &'a [Box<dyn Notifier>],
+    pub after: Duration,
+}
+
+/// Send a one-time alert naming whoever is blocking `resource_name`, reusing
+/// the same "is the reason a workflow URL" parsing as `log_reservation_info`.
+async fn notify_blocked(blocking_notify: &BlockingNotify<'_>, resource_name: &str, resource: &ResourceListItem) {
+    let subject = format!("Reservation of {resource_name} has been blocked");
+    let body = describe_blocker(resource).unwrap_or_else(|_| "Resource is reserved by an unknown holder.".to_string());
+    notifier::notify_all(blocking_notify.notifiers, &subject, &body).await;
+}
+
+/// A pluggable place to reserve/release named resources. `MutexbotBackend`
+/// talks to the mutexbot API; `RedisBackend` does the same thing against a
+/// plain Redis instance, for teams without a mutexbot account. Callers pick
+/// one at startup (e.g. via a `--backend` flag) and drive it through the
+/// `reserve`/`reserve_exclusive`/`release` functions below rather than
+/// calling its methods directly.
+#[async_trait]
+pub trait LockBackend: Send + Sync {
+    /// Attempt to reserve `resource_name` once. Implementations retry their
+    /// own transient failures internally and only return once they have a
+    /// definitive `Success` or `Conflict`.
+    async fn reserve(
+        &self,
+        resource_name: &str,
+        isolation_channel: Option<&str>,
+        notes: &str,
+        duration: Option<&str>,
+    ) -> Result<ReservationResult>;
+
+    /// Release `resource_name`, failing if it isn't currently held (by
+    /// whatever the backend considers "held by us").
+    async fn release(&self, resource_name: &str, isolation_channel: Option<&str>) -> Result<()>;
+
+    /// Release `resource_name` regardless of who holds it.
+    async fn force_release(&self, resource_name: &str, isolation_channel: Option<&str>) -> Result<()>;
+
+    /// Look up the current state of `resource_name`, if it exists.
+    async fn fetch_resource(
+        &self,
+        resource_name: &str,
+        isolation_channel: Option<&str>,
+    ) -> Result<Option<ResourceListItem>>;
+
+    /// Create `resource_name` if the backend needs resources to exist before
+    /// they can be reserved. A no-op for backends (like Redis) where a
+    /// reservation implicitly creates its own key.
+    async fn create_if_missing(&self, resource_name: &str, isolation_channel: Option<&str>) -> Result<()>;
+}
+
+#[derive(Deserialize)]
+// We don't read all of the fields
+#[allow(dead_code)]
+pub struct ResourceListItem {
+    pub name: String,
+    pub description: String,
+    pub isolated: bool,
+    pub isolation_channel_name: Option<String>,
+    pub active_reservation: Option<String>,
+    pub active_reservation_user_name: Option<String>,
+    pub active_reservation_reason: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum ReservationResult {
+    Success,
+    Conflict,
+}
+
+/// A reservation/release attempt's final outcome, in a form cheap to render
+/// as JSON for `mutexbot --format json`. `reserve`/`reserve_exclusive` only
+/// ever return once they've actually secured the resource (see their docs),
+/// so there's no `conflict` variant here - by the time either of them
+/// returns `Ok`, the outcome is always `Reserved`.
+#[derive(Debug, Serialize)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum ReservationOutcome {
+    Reserved {
+        resource: String,
+        /// RFC 3339 expiration timestamp, if the backend reports one for
+        /// this reservation.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        expires_at: Option<String>,
+    },
+    Released { resource: String },
+}
+
+impl ReservationOutcome {
+    /// Describe a reservation that was just taken, reading back its
+    /// expiration (if any) from the backend.
+    pub async fn describe_reserved(
+        backend: &dyn LockBackend,
+        resource_name: &str,
+        isolation_channel: Option<&str>,
+    ) -> Result<Self> {
+        let expires_at = match backend.fetch_resource(resource_name, isolation_channel).await? {
+            Some(resource) => parse_expiration_time(&resource.active_reservation),
+            None => None,
+        };
+
+        Ok(ReservationOutcome::Reserved {
+            resource: resource_name.to_string(),
+            expires_at: expires_at.map(|expires_at| expires_at.to_rfc3339()),
+        })
+    }
+
+    /// Describe a release that just completed.
+    pub fn released(resource_name: &str) -> Self {
+        ReservationOutcome::Released {
+            resource: resource_name.to_string(),
+        }
+    }
+}
+
+/// Describe who holds `resource`'s active reservation, naming the blocking
+/// component and its workflow URL when the reason looks like one (i.e. ends
+/// in something containing `/actions/runs/`).
+fn describe_blocker(resource: &ResourceListItem) -> Result<String> {
+    let user = resource
+        .active_reservation_user_name
+        .as_ref()
+        .context("Resource doesn't have active_reservation_user_name!")?;
+    let reason = resource
+        .active_reservation_reason
+        .as_ref()
+        .context("Resource doesn't have active_reservation_reason!")?;
+
+    Ok(if let Some(workflow_url) = reason.split_whitespace().last() {
+        if workflow_url.contains("/actions/runs/") {
+            format!("Existing reservation by component {user} in {workflow_url}")
+        } else {
+            format!("Existing reservation by user {user} with reason \"{reason}\"")
+        }
+    } else {
+        format!("Existing reservation by user {user} with reason \"{reason}\"")
+    })
+}
+
+/// Log information about an existing reservation.
+fn log_reservation_info(resource: &ResourceListItem) -> Result<()> {
+    if resource.active_reservation.is_none() {
+        info!("No active reservation.");
+        return Ok(());
+    }
+
+    let base_message = describe_blocker(resource)?;
+    if let Some(expires_at) = parse_expiration_time(&resource.active_reservation) {
+        info!("{}. Expires at: {}.", base_message, expires_at);
+    } else {
+        info!("{}.", base_message);
+    }
+    Ok(())
+}
+
+/// Check if a resource has an active (non-expired) reservation.
+fn has_active_reservation(resource: &ResourceListItem) -> bool {
+    if resource.active_reservation.is_none() {
+        return false;
+    }
+
+    if let Some(expires_at) = parse_expiration_time(&resource.active_reservation) {
+        return expires_at > Utc::now();
+    }
+    true
+}
+
+/// Parse expiration time in a resource.
+fn parse_expiration_time(active_reservation: &Option<String>) -> Option<DateTime<Utc>> {
+    match active_reservation {
+        Some(timestamp) => match DateTime::parse_from_rfc3339(timestamp.as_str()) {
+            Ok(datetime) => Some(datetime.with_timezone(&Utc)),
+            Err(_) => {
+                info!(
+                    "Active reservation {} is not a valid ISO 8601 timestamp",
+                    timestamp
+                );
+                None
+            }
+        },
+        _ => None,
+    }
+}
+
+/// Calculate wait time based on reservation expiration.
+fn calculate_wait_time(resource: &ResourceListItem) -> Duration {
+    let max_wait = Duration::from_secs(5 * 60);
+    let no_wait = Duration::from_secs(1);
+
+    let expiration_time = parse_expiration_time(&resource.active_reservation);
+
+    let base_wait = match expiration_time {
+        None => no_wait,
+        Some(expires_at) => {
+            let now = Utc::now();
+            if expires_at > now {
+                let time_until_expiration = (expires_at - now).to_std().unwrap_or(Duration::ZERO);
+                return std::cmp::min(time_until_expiration, max_wait);
+            }
+            return no_wait;
+        }
+    };
+
+    let jitter_range = base_wait.as_millis() as u64 / 3;
+    let jitter_offset = rand::thread_rng().gen_range(0..=jitter_range);
+    base_wait + Duration::from_millis(jitter_offset)
+}
+
+/// Reserve `resource_name`, retrying on transient failures, returning as
+/// soon as the reservation is made even if someone else already holds it.
+pub async fn reserve(
+    backend: &dyn LockBackend,
+    resource_name: &str,
+    isolation_channel: &Option<String>,
+    notes: String,
+    duration: Option<String>,
+    blocking_notify: Option<&BlockingNotify<'_>>,
+) -> Result<()> {
+    let mut backoff = Backoff::new(BUSY_BACKOFF_BASE, BUSY_BACKOFF_CAP, usize::MAX);
+    let mut waited = Duration::ZERO;
+    let mut notified = false;
+    loop {
+        match backend
+            .reserve(resource_name, isolation_channel.as_deref(), &notes, duration.as_deref())
+            .await?
+        {
+            ReservationResult::Success => return Ok(()),
+            ReservationResult::Conflict => {
+                info!("Resource already reserved, fetching reservation data.");
+                let resource = match backend.fetch_resource(resource_name, isolation_channel.as_deref()).await {
+                    Ok(Some(resource)) => {
+                        log_reservation_info(&resource)?;
+                        Some(resource)
+                    }
+                    _ => {
+                        info!("Could not find resource after conflict.");
+                        None
+                    }
+                };
+
+                if let (Some(blocking_notify), Some(resource), false) = (blocking_notify, &resource, notified) {
+                    if waited >= blocking_notify.after {
+                        notify_blocked(blocking_notify, resource_name, resource).await;
+                        notified = true;
+                    }
+                }
+
+                waited += backoff.wait().await;
+            }
+        }
+    }
+}
+
+/// Reserve `resource_name` exclusively, waiting out any existing reservation
+/// before attempting to reserve it ourselves.
+pub async fn reserve_exclusive(
+    backend: &dyn LockBackend,
+    resource_name: &str,
+    isolation_channel: &Option<String>,
+    notes: String,
+    duration: Option<String>,
+    blocking_notify: Option<&BlockingNotify<'_>>,
+) -> Result<()> {
+    let mut waited = Duration::ZERO;
+    let mut notified = false;
+    loop {
+        let resource_data = match backend.fetch_resource(resource_name, isolation_channel.as_deref()).await {
+            Ok(data) => data,
+            Err(_) => continue,
+        };
+
+        if resource_data.is_none()
+            && backend
+                .create_if_missing(resource_name, isolation_channel.as_deref())
+                .await
+                .is_err()
+        {
+            continue;
+        }
+
+        if let Some(resource) = &resource_data {
+            if has_active_reservation(resource) {
+                log_reservation_info(resource)?;
+
+                if let (Some(blocking_notify), false) = (blocking_notify, notified) {
+                    if waited >= blocking_notify.after {
+                        notify_blocked(blocking_notify, resource_name, resource).await;
+                        notified = true;
+                    }
+                }
+
+                let wait_time = calculate_wait_time(resource);
+                info!(
+                    "Resource is reserved, waiting {:.1} seconds before retrying...",
+                    wait_time.as_secs_f64()
+                );
+                sleep(wait_time).await;
+                waited += wait_time;
+                continue;
+            }
+        }
+
+        match backend
+            .reserve(resource_name, isolation_channel.as_deref(), &notes, duration.as_deref())
+            .await?
+        {
+            ReservationResult::Success => return Ok(()),
+            ReservationResult::Conflict => {
+                info!("Resource became reserved between check and reservation attempt");
+                let wait_time = match backend.fetch_resource(resource_name, isolation_channel.as_deref()).await {
+                    Ok(Some(resource)) => calculate_wait_time(&resource),
+                    _ => Duration::from_millis(rand::thread_rng().gen_range(1000..=5000)),
+                };
+                info!(
+                    "Waiting {:.1} seconds before retrying...",
+                    wait_time.as_secs_f64()
+                );
+                sleep(wait_time).await;
+                waited += wait_time;
+            }
+        }
+    }
+}
+
+/// Re-reserve `resource_name` on `duration` in the background, at roughly
+/// half of its remaining lifetime each time, so a caller holding it across a
+/// long-running operation (see `mutexbot guard`) never loses the lease
+/// mid-flight. Schedules each renewal off the backend's own reported
+/// expiration (via [`parse_expiration_time`]) rather than just re-sleeping
+/// `duration`, so a backend that doesn't honor `duration` verbatim still
+/// gets renewed before its real expiry.
+///
+/// Only returns on error: either the resource turns out to be held by
+/// someone else by the time a renewal is attempted (the lease was lost), or
+/// the backend's own `reserve` call fails. A renewal loop that silently gave
+/// up instead would let the caller keep going unprotected.
+pub async fn run_renewal_loop(
+    backend: &dyn LockBackend,
+    resource_name: &str,
+    isolation_channel: Option<&str>,
+    notes: &str,
+    duration: &str,
+) -> Result<Infallible> {
+    loop {
+        let next_renewal = match backend.fetch_resource(resource_name, isolation_channel).await {
+            // `to_std()` only fails when `expires_at` is already in the
+            // past (e.g. clock skew, or a slow prior renewal round-trip) -
+            // that's the one case where the lease is most at risk, so renew
+            // right away rather than falling back to a fixed sleep.
+            Ok(Some(resource)) => match parse_expiration_time(&resource.active_reservation) {
+                Some(expires_at) => (expires_at - Utc::now())
+                    .to_std()
+                    .map(|remaining| remaining.mul_f64(GUARD_RENEWAL_FRACTION))
+                    .unwrap_or(Duration::from_secs(0)),
+                None => GUARD_RENEWAL_FALLBACK,
+            },
+            _ => GUARD_RENEWAL_FALLBACK,
+        };
+
+        sleep(next_renewal).await;
+
+        match backend
+            .reserve(resource_name, isolation_channel, notes, Some(duration))
+            .await?
+        {
+            ReservationResult::Success => {
+                info!("Renewed reservation of {resource_name} for another {duration}");
+            }
+            ReservationResult::Conflict => {
+                anyhow::bail!("Lost reservation of {resource_name} to another holder while renewing it");
+            }
+        }
+    }
+}
+
+/// Release `resource_name`. `force` selects force-release, which releases
+/// the resource regardless of who holds the reservation.
+pub async fn release(
+    backend: &dyn LockBackend,
+    resource_name: &str,
+    isolation_channel: &Option<String>,
+    force: bool,
+) -> Result<()> {
+    if force {
+        backend.force_release(resource_name, isolation_channel.as_deref()).await
+    } else {
+        backend.release(resource_name, isolation_channel.as_deref()).await
+    }
+}
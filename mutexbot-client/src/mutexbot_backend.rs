@@ -0,0 +1,263 @@
+//! HTTP-backed `LockBackend` talking to `https://mutexbot.com/api/...`. This
+//! is the original, and still default, backend - the logic here is unchanged
+//! from before `LockBackend` was extracted, just moved behind the trait and
+//! with its own retry loop instead of bubbling `ReservationResult::Retry`
+//! back up to the orchestration functions in `lib.rs`.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use log::info;
+use reqwest::{Client, StatusCode, header};
+use serde::Serialize;
+
+use crate::{Backoff, FAILURE_BACKOFF_BASE, FAILURE_BACKOFF_CAP, FAILURE_MAX_ATTEMPTS, LockBackend, ReservationResult, ResourceListItem};
+
+pub struct MutexbotBackend {
+    http: Client,
+}
+
+impl MutexbotBackend {
+    pub fn new(api_key: &str) -> Result<Self> {
+        let mut headers = header::HeaderMap::new();
+        let mut auth_value = header::HeaderValue::from_str(api_key)
+            .context("Failure creating auth header from API key")?;
+        auth_value.set_sensitive(true);
+        headers.insert("X-API-Key", auth_value);
+
+        let http = reqwest::Client::builder()
+            .default_headers(headers)
+            .build()
+            .context("Failure creating http client")?;
+
+        Ok(Self { http })
+    }
+
+    fn backoff() -> Backoff {
+        Backoff::new(FAILURE_BACKOFF_BASE, FAILURE_BACKOFF_CAP, FAILURE_MAX_ATTEMPTS)
+    }
+
+    async fn request_failure(&self, backoff: &mut Backoff, error: reqwest::Error) -> Result<()> {
+        backoff.retry("Failed to send request").await.with_context(|| error.to_string())
+    }
+
+    async fn status_code(&self, backoff: &mut Backoff, status_code: StatusCode) -> Result<()> {
+        if !status_code.is_server_error() {
+            anyhow::bail!("Unexpected status code: {status_code}")
+        }
+        backoff
+            .retry(&format!("Server error, status code {status_code}"))
+            .await
+    }
+}
+
+#[derive(Serialize)]
+struct ReservePayload {
+    notes: String,
+    duration: Option<String>,
+    isolation_channel: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ReleasePayload {
+    isolation_channel: Option<String>,
+}
+
+#[derive(Serialize)]
+struct CreatePayload {
+    name: String,
+    isolation_channel: Option<String>,
+}
+
+#[async_trait]
+impl LockBackend for MutexbotBackend {
+    async fn reserve(
+        &self,
+        resource_name: &str,
+        isolation_channel: Option<&str>,
+        notes: &str,
+        duration: Option<&str>,
+    ) -> Result<ReservationResult> {
+        let endpoint = format!("https://mutexbot.com/api/resources/global/{resource_name}/reserve");
+        let payload = ReservePayload {
+            notes: notes.to_string(),
+            duration: duration.map(str::to_owned),
+            isolation_channel: isolation_channel.map(str::to_owned),
+        };
+
+        let mut backoff = Self::backoff();
+        loop {
+            match self.http.post(&endpoint).json(&payload).send().await {
+                Ok(resp) => match resp.status() {
+                    StatusCode::CREATED => {
+                        info!("Resource reserved successfully");
+                        return Ok(ReservationResult::Success);
+                    }
+                    StatusCode::CONFLICT => return Ok(ReservationResult::Conflict),
+                    StatusCode::BAD_REQUEST => {
+                        anyhow::bail!("Bad request. Check your input data.");
+                    }
+                    StatusCode::UNAUTHORIZED => {
+                        anyhow::bail!("Unauthorized. Check your API keys.");
+                    }
+                    StatusCode::NOT_FOUND => {
+                        self.create_if_missing(resource_name, isolation_channel).await?;
+                    }
+                    status_code => {
+                        self.status_code(&mut backoff, status_code)
+                            .await
+                            .context("Failure reserving resource")?;
+                    }
+                },
+                Err(error) => {
+                    self.request_failure(&mut backoff, error)
+                        .await
+                        .context("Failure reserving resource")?;
+                }
+            }
+        }
+    }
+
+    async fn release(&self, resource_name: &str, isolation_channel: Option<&str>) -> Result<()> {
+        self.release_inner(resource_name, isolation_channel, false).await
+    }
+
+    async fn force_release(&self, resource_name: &str, isolation_channel: Option<&str>) -> Result<()> {
+        self.release_inner(resource_name, isolation_channel, true).await
+    }
+
+    async fn fetch_resource(
+        &self,
+        resource_name: &str,
+        isolation_channel: Option<&str>,
+    ) -> Result<Option<ResourceListItem>> {
+        let mut backoff = Self::backoff();
+        loop {
+            match self
+                .http
+                .get("https://mutexbot.com/api/resources")
+                .send()
+                .await
+            {
+                Ok(resp) => match resp.status() {
+                    StatusCode::OK => {
+                        let resources = resp.json::<Vec<ResourceListItem>>().await?;
+                        return Ok(resources.into_iter().find(|resource| {
+                            resource.name == resource_name
+                                && (isolation_channel.is_none()
+                                    || (resource.isolated
+                                        && resource.isolation_channel_name.as_deref() == isolation_channel))
+                        }));
+                    }
+                    StatusCode::BAD_REQUEST => {
+                        anyhow::bail!("Bad request. Check your input data.");
+                    }
+                    StatusCode::UNAUTHORIZED => {
+                        anyhow::bail!("Unauthorized. Check your API keys.");
+                    }
+                    status_code => {
+                        self.status_code(&mut backoff, status_code)
+                            .await
+                            .context("Failure fetching resource data")?;
+                    }
+                },
+                Err(error) => {
+                    self.request_failure(&mut backoff, error)
+                        .await
+                        .context("Failure fetching resource data")?;
+                }
+            }
+        }
+    }
+
+    async fn create_if_missing(&self, resource_name: &str, isolation_channel: Option<&str>) -> Result<()> {
+        info!("Resource not found, creating it.");
+        let mut backoff = Self::backoff();
+        loop {
+            match self
+                .http
+                .post("https://mutexbot.com/api/resources")
+                .json(&CreatePayload {
+                    name: resource_name.to_string(),
+                    isolation_channel: isolation_channel.map(str::to_owned),
+                })
+                .send()
+                .await
+            {
+                Ok(resp) => match resp.status() {
+                    StatusCode::CREATED => {
+                        info!("Resource created");
+                        return Ok(());
+                    }
+                    StatusCode::CONFLICT => {
+                        info!("Resource already exists, trying again.");
+                        return Ok(());
+                    }
+                    StatusCode::BAD_REQUEST => {
+                        anyhow::bail!("Bad request. Check your input data.");
+                    }
+                    StatusCode::UNAUTHORIZED => {
+                        anyhow::bail!("Unauthorized. Check your API keys.");
+                    }
+                    status_code => {
+                        self.status_code(&mut backoff, status_code)
+                            .await
+                            .context("Failure creating missing resource")?;
+                    }
+                },
+                Err(error) => {
+                    self.request_failure(&mut backoff, error)
+                        .await
+                        .context("Failure creating missing resource")?;
+                }
+            }
+        }
+    }
+}
+
+impl MutexbotBackend {
+    async fn release_inner(&self, resource_name: &str, isolation_channel: Option<&str>, force: bool) -> Result<()> {
+        let endpoint = format!(
+            "https://mutexbot.com/api/resources/global/{}/{}",
+            resource_name,
+            if force { "force-release" } else { "release" }
+        );
+        let payload = ReleasePayload {
+            isolation_channel: isolation_channel.map(str::to_owned),
+        };
+
+        let mut backoff = Self::backoff();
+        loop {
+            match self.http.post(&endpoint).json(&payload).send().await {
+                Ok(resp) => match resp.status() {
+                    StatusCode::OK => {
+                        info!("Resource released successfully.");
+                        return Ok(());
+                    }
+                    StatusCode::ALREADY_REPORTED => {
+                        anyhow::bail!("Resource not reserved, aborting.");
+                    }
+                    StatusCode::CONFLICT => {
+                        anyhow::bail!("Resource reserved by someone else, aborting.");
+                    }
+                    StatusCode::BAD_REQUEST => {
+                        anyhow::bail!("Bad request. Check your input data.");
+                    }
+                    StatusCode::UNAUTHORIZED => {
+                        anyhow::bail!("Unauthorized. Check your API keys.")
+                    }
+                    StatusCode::NOT_FOUND => {
+                        anyhow::bail!("Resource not found.")
+                    }
+                    status_code => self
+                        .status_code(&mut backoff, status_code)
+                        .await
+                        .context("Failure releasing resource")?,
+                },
+                Err(error) => self
+                    .request_failure(&mut backoff, error)
+                    .await
+                    .context("Failure releasing resource")?,
+            }
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use lettre::{
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+    message::Mailbox,
+    transport::smtp::authentication::Credentials,
+};
+
+use super::Notifier;
+
+pub struct EmailNotifier {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    to: Mailbox,
+}
+
+impl EmailNotifier {
+    /// Build an SMTP notifier from `MUTEXBOT_SMTP_*` environment variables:
+    /// `host` (passed in, already read by `from_env` in `mod.rs`),
+    /// `MUTEXBOT_SMTP_TO` (required), `MUTEXBOT_SMTP_FROM` (defaults to
+    /// `mutexbot@localhost`), and `MUTEXBOT_SMTP_USERNAME`/
+    /// `MUTEXBOT_SMTP_PASSWORD` (optional - skips auth if either is unset).
+    pub fn from_env(host: String) -> Result<Self> {
+        let to = std::env::var("MUTEXBOT_SMTP_TO")
+            .context("MUTEXBOT_SMTP_TO is required when MUTEXBOT_SMTP_HOST is set")?
+            .parse()
+            .context("MUTEXBOT_SMTP_TO is not a valid email address")?;
+        let from = std::env::var("MUTEXBOT_SMTP_FROM").unwrap_or_else(|_| "mutexbot@localhost".to_string());
+        let from = from.parse().context("MUTEXBOT_SMTP_FROM is not a valid email address")?;
+
+        let mut builder = AsyncSmtpTransport::<Tokio1Executor>::relay(&host)
+            .with_context(|| format!("Failed to configure SMTP relay {host}"))?;
+
+        if let (Ok(username), Ok(password)) = (
+            std::env::var("MUTEXBOT_SMTP_USERNAME"),
+            std::env::var("MUTEXBOT_SMTP_PASSWORD"),
+        ) {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+            from,
+            to,
+        })
+    }
+}
+
+#[async_trait]
+impl Notifier for EmailNotifier {
+    async fn notify(&self, subject: &str, body: &str) -> Result<()> {
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(self.to.clone())
+            .subject(subject)
+            .body(body.to_string())
+            .context("Failed to build notification email")?;
+
+        self.transport
+            .send(message)
+            .await
+            .context("Failed to send notification email")?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+//! Fan-out alerting for events that don't fit deploy-queue's
+//! `DeploymentEvent`-shaped notifier (a long-held reservation blocking a
+//! queue, a deployment running as an outlier) - these just need to ship a
+//! one-line subject/body to whoever's on call, not drive a richer state
+//! transition object. Modeled on the same shape as that notifier: a
+//! `Notifier` trait, one sender per channel, `notify_all` swallowing and
+//! logging individual failures.
+
+pub mod email;
+pub mod slack;
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+/// Something that wants to hear about a blocking reservation or a
+/// deployment outlier. A failure to deliver should never abort the
+/// reservation loop or the `Outliers` listing - callers log and move on.
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    async fn notify(&self, subject: &str, body: &str) -> Result<()>;
+}
+
+/// Deliver `subject`/`body` to every configured notifier. Individual
+/// failures are logged and otherwise swallowed so a broken webhook or SMTP
+/// server can't fail the caller.
+pub async fn notify_all(notifiers: &[Box<dyn Notifier>], subject: &str, body: &str) {
+    for notifier in notifiers {
+        if let Err(err) = notifier.notify(subject, body).await {
+            log::warn!("Notifier failed to deliver \"{subject}\": {err:#}");
+        }
+    }
+}
+
+/// Build the notifiers configured via environment variables:
+/// - `MUTEXBOT_SLACK_WEBHOOK_URL` for [`slack::SlackNotifier`]
+/// - `MUTEXBOT_SMTP_HOST`, `MUTEXBOT_SMTP_TO` (and optionally
+///   `MUTEXBOT_SMTP_FROM`, `MUTEXBOT_SMTP_USERNAME`/`MUTEXBOT_SMTP_PASSWORD`)
+///   for [`email::EmailNotifier`]
+///
+/// Each channel is independently opt-in - an unset variable just skips that
+/// sender rather than erroring, so callers that don't care about alerting
+/// can ignore this entirely and get an empty list.
+pub fn from_env() -> Result<Vec<Box<dyn Notifier>>> {
+    let mut notifiers: Vec<Box<dyn Notifier>> = Vec::new();
+
+    if let Ok(webhook_url) = std::env::var("MUTEXBOT_SLACK_WEBHOOK_URL") {
+        notifiers.push(Box::new(slack::SlackNotifier::new(webhook_url)));
+    }
+
+    if let Ok(host) = std::env::var("MUTEXBOT_SMTP_HOST") {
+        notifiers.push(Box::new(email::EmailNotifier::from_env(host)?));
+    }
+
+    Ok(notifiers)
+}
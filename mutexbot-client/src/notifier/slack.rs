@@ -0,0 +1,44 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::Serialize;
+
+use super::Notifier;
+
+pub struct SlackNotifier {
+    client: reqwest::Client,
+    webhook_url: String,
+}
+
+impl SlackNotifier {
+    pub fn new(webhook_url: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            webhook_url,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct SlackMessage<'a> {
+    text: &'a str,
+}
+
+#[async_trait]
+impl Notifier for SlackNotifier {
+    async fn notify(&self, subject: &str, body: &str) -> Result<()> {
+        let text = format!("*{subject}*\n{body}");
+        let response = self
+            .client
+            .post(&self.webhook_url)
+            .json(&SlackMessage { text: &text })
+            .send()
+            .await
+            .context("Failed to send Slack webhook request")?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("Slack webhook returned status {}", response.status());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,227 @@
+//! Redis-backed `LockBackend` for teams that don't have a mutexbot account.
+//! The isolation-channel concept maps to a key prefix, reservations are a
+//! Lua compare-and-set keyed on the owner token stored in the value (so a
+//! renewal by the same holder extends the TTL instead of conflicting with
+//! itself), and releases are the same compare-and-delete pattern - so a
+//! release only ever removes a reservation this process itself created.
+
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use bb8::Pool;
+use bb8_redis::{RedisConnectionManager, redis};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{LockBackend, ReservationResult, ResourceListItem};
+
+/// How long a reservation is held when the caller doesn't pass a `duration`.
+const DEFAULT_LOCK_DURATION: Duration = Duration::from_secs(30 * 60);
+
+/// Sets `KEYS[1]` to `ARGV[1]` with a `PX` of `ARGV[2]` if the key is
+/// missing, or if its current value decodes to the owner token in `ARGV[1]`
+/// (i.e. we already hold it) - so a holder renewing its own reservation
+/// extends the TTL instead of conflicting with itself, while anyone else's
+/// reservation is left alone.
+const RESERVE_SCRIPT: &str = r#"
+local value = redis.call('GET', KEYS[1])
+if value then
+    local ok, decoded = pcall(cjson.decode, value)
+    local new_ok, new_decoded = pcall(cjson.decode, ARGV[1])
+    if not ok or not new_ok or decoded.owner_token ~= new_decoded.owner_token then
+        return 0
+    end
+end
+redis.call('SET', KEYS[1], ARGV[1], 'PX', ARGV[2])
+return 1
+"#;
+
+/// Deletes `KEYS[1]` only if its value decodes to the owner token in
+/// `ARGV[1]`, so a release never clobbers a reservation taken out from under
+/// it (e.g. after this one expired and someone else claimed the key).
+const RELEASE_SCRIPT: &str = r#"
+local value = redis.call('GET', KEYS[1])
+if not value then
+    return 0
+end
+local ok, decoded = pcall(cjson.decode, value)
+if not ok or decoded.owner_token ~= ARGV[1] then
+    return 0
+end
+redis.call('DEL', KEYS[1])
+return 1
+"#;
+
+pub struct RedisBackend {
+    pool: Pool<RedisConnectionManager>,
+    /// Identifies reservations taken out by this process, so `release` only
+    /// ever deletes a key it itself holds - not one another caller grabbed
+    /// after this one expired.
+    owner_token: String,
+}
+
+impl RedisBackend {
+    pub async fn new(redis_url: &str) -> Result<Self> {
+        let manager =
+            RedisConnectionManager::new(redis_url).context("Failed to build Redis connection manager")?;
+        let pool = Pool::builder()
+            .build(manager)
+            .await
+            .context("Failed to build Redis connection pool")?;
+
+        Ok(Self {
+            pool,
+            owner_token: Uuid::new_v4().to_string(),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Reservation {
+    owner_token: String,
+    user: String,
+    reason: String,
+}
+
+/// The isolation channel maps to a key prefix: `mutexbot:<channel>:<name>`,
+/// or `mutexbot:<name>` with no channel - mirroring how the mutexbot backend
+/// scopes a resource to an isolation channel.
+fn redis_key(resource_name: &str, isolation_channel: Option<&str>) -> String {
+    match isolation_channel {
+        Some(channel) => format!("mutexbot:{channel}:{resource_name}"),
+        None => format!("mutexbot:{resource_name}"),
+    }
+}
+
+/// Parse a mutexbot-style duration string (`"30s"`, `"5m"`, `"1h"`) into a
+/// `Duration`, falling back to `DEFAULT_LOCK_DURATION` if it's absent or
+/// doesn't parse - the mutexbot API accepts the same strings but interprets
+/// them server-side, so this is the Redis backend's own equivalent.
+fn parse_duration(duration: Option<&str>) -> Duration {
+    duration
+        .and_then(|value| {
+            let value = value.trim();
+            let (amount, suffix) = value.split_at(value.len().checked_sub(1)?);
+            let amount: u64 = amount.parse().ok()?;
+            match suffix {
+                "s" => Some(Duration::from_secs(amount)),
+                "m" => Some(Duration::from_secs(amount * 60)),
+                "h" => Some(Duration::from_secs(amount * 3600)),
+                _ => None,
+            }
+        })
+        .unwrap_or(DEFAULT_LOCK_DURATION)
+}
+
+#[async_trait]
+impl LockBackend for RedisBackend {
+    async fn reserve(
+        &self,
+        resource_name: &str,
+        isolation_channel: Option<&str>,
+        notes: &str,
+        duration: Option<&str>,
+    ) -> Result<ReservationResult> {
+        let mut conn = self.pool.get().await.context("Failed to get Redis connection")?;
+        let key = redis_key(resource_name, isolation_channel);
+        let value = serde_json::to_string(&Reservation {
+            owner_token: self.owner_token.clone(),
+            user: "redis-lock".to_string(),
+            reason: notes.to_string(),
+        })
+        .context("Failed to serialize reservation")?;
+        let ttl_ms = parse_duration(duration).as_millis() as u64;
+
+        let set: i64 = redis::Script::new(RESERVE_SCRIPT)
+            .key(&key)
+            .arg(&value)
+            .arg(ttl_ms)
+            .invoke_async(&mut *conn)
+            .await
+            .context("Failed to run reserve script")?;
+
+        Ok(match set {
+            1 => ReservationResult::Success,
+            _ => ReservationResult::Conflict,
+        })
+    }
+
+    async fn release(&self, resource_name: &str, isolation_channel: Option<&str>) -> Result<()> {
+        let mut conn = self.pool.get().await.context("Failed to get Redis connection")?;
+        let key = redis_key(resource_name, isolation_channel);
+
+        let deleted: i64 = redis::Script::new(RELEASE_SCRIPT)
+            .key(&key)
+            .arg(&self.owner_token)
+            .invoke_async(&mut *conn)
+            .await
+            .context("Failed to run release script")?;
+
+        if deleted == 0 {
+            anyhow::bail!("Resource not reserved by us, aborting.");
+        }
+        Ok(())
+    }
+
+    async fn force_release(&self, resource_name: &str, isolation_channel: Option<&str>) -> Result<()> {
+        let mut conn = self.pool.get().await.context("Failed to get Redis connection")?;
+        let key = redis_key(resource_name, isolation_channel);
+
+        redis::cmd("DEL")
+            .arg(&key)
+            .query_async::<_, ()>(&mut *conn)
+            .await
+            .context("Failed to force-release resource")?;
+        Ok(())
+    }
+
+    async fn fetch_resource(
+        &self,
+        resource_name: &str,
+        isolation_channel: Option<&str>,
+    ) -> Result<Option<ResourceListItem>> {
+        let mut conn = self.pool.get().await.context("Failed to get Redis connection")?;
+        let key = redis_key(resource_name, isolation_channel);
+
+        let value: Option<String> = redis::cmd("GET")
+            .arg(&key)
+            .query_async(&mut *conn)
+            .await
+            .context("Failed to GET reservation")?;
+        let Some(value) = value else {
+            return Ok(None);
+        };
+
+        let pttl: i64 = redis::cmd("PTTL")
+            .arg(&key)
+            .query_async(&mut *conn)
+            .await
+            .context("Failed to PTTL reservation")?;
+        let reservation: Reservation =
+            serde_json::from_str(&value).context("Failed to deserialize reservation")?;
+
+        // `PTTL` feeds the expiration-aware wait: a positive value becomes
+        // the `active_reservation` timestamp `calculate_wait_time` reads; a
+        // missing/expired key (<= 0) reports no active reservation instead.
+        let active_reservation =
+            (pttl > 0).then(|| (Utc::now() + chrono::Duration::milliseconds(pttl)).to_rfc3339());
+
+        Ok(Some(ResourceListItem {
+            name: resource_name.to_string(),
+            description: String::new(),
+            isolated: isolation_channel.is_some(),
+            isolation_channel_name: isolation_channel.map(str::to_owned),
+            active_reservation,
+            active_reservation_user_name: Some(reservation.user),
+            active_reservation_reason: Some(reservation.reason),
+        }))
+    }
+
+    async fn create_if_missing(&self, _resource_name: &str, _isolation_channel: Option<&str>) -> Result<()> {
+        // Redis keys are implicit - `reserve`'s `SET ... NX` already creates
+        // the key, so there's nothing to do ahead of time.
+        Ok(())
+    }
+}
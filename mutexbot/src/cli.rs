@@ -1,18 +1,45 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 
-/// CLI for reserving and (force-)releasing MutexBot resources.
+/// CLI for reserving and (force-)releasing resources against a pluggable
+/// lock backend.
 ///
-/// Use the `MUTEXBOT_API_KEY` environment variable to pass the API key.
+/// Use the `MUTEXBOT_API_KEY` environment variable to pass the API key for
+/// the (default) `mutexbot` backend.
 #[derive(Parser)]
 #[command(version, about, long_about)]
 pub(crate) struct Cli {
-    /// Isolation channel for resource
+    /// Isolation channel for resource. Maps to a key prefix for the `redis` backend.
     #[arg(long)]
     pub(crate) isolation_channel: Option<String>,
+    /// Which lock backend to reserve/release resources against
+    #[arg(long, value_enum, default_value_t = Backend::Mutexbot)]
+    pub(crate) backend: Backend,
+    /// Connection URL for the selected backend. Required (and only
+    /// meaningful) for the `redis` backend, e.g. `redis://localhost:6379`
+    #[arg(long)]
+    pub(crate) connection_url: Option<String>,
+    /// Output format for the final reservation/release result printed to
+    /// stdout. `info!` logging always goes to stderr regardless of this, so
+    /// `--format json` leaves stdout carrying only the JSON document.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub(crate) format: OutputFormat,
     #[command(subcommand)]
     pub(crate) mode: Mode,
 }
 
+#[derive(Clone, Copy, ValueEnum)]
+pub(crate) enum Backend {
+    Mutexbot,
+    Redis,
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+pub(crate) enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
 #[derive(Subcommand, Clone)]
 pub(crate) enum Mode {
     /// Reserve a resource
@@ -51,29 +78,29 @@ pub(crate) enum Mode {
         /// Resource to force-release
         resource_name: String,
     },
-}
-
-impl Mode {
-    pub(crate) fn api_endpoint(&self) -> String {
-        match self {
-            Mode::Reserve { resource_name, .. } => format!(
-                "https://mutexbot.com/api/resources/global/{}/reserve",
-                resource_name,
-            ),
-            Mode::ReserveExclusive { resource_name, .. } => format!(
-                "https://mutexbot.com/api/resources/global/{}/reserve",
-                resource_name,
-            ),
-            Mode::Release { resource_name } => format!(
-                "https://mutexbot.com/api/resources/global/{}/release",
-                resource_name,
-            ),
-            Mode::ForceRelease { resource_name } => format!(
-                "https://mutexbot.com/api/resources/global/{}/force-release",
-                resource_name,
-            ),
-        }
-    }
+    /// Reserve a resource exclusively, run a command while holding it, and
+    /// release it when the command exits
+    ///
+    /// Like `flock`: reserves `--resource-name` for `--duration`, waiting
+    /// out any existing reservation first, then runs the command after
+    /// `--`. While the command runs, the reservation is renewed in the
+    /// background so it never expires mid-command regardless of how long
+    /// the command takes. The reservation is released when the command
+    /// exits on its own or this process receives SIGINT/SIGTERM.
+    ///
+    /// Use the `MUTEXBOT_API_KEY` environment variable to pass the API key.
+    Guard {
+        /// Resource to reserve for the lifetime of the command
+        #[arg(long)]
+        resource_name: String,
+        /// Duration to reserve the resource for; renewed automatically
+        /// while the command runs
+        #[arg(long)]
+        duration: String,
+        /// Command (and its arguments) to run while the resource is held
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true, required = true)]
+        command: Vec<String>,
+    },
 }
 
 impl Cli {
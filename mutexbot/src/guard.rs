@@ -0,0 +1,122 @@
+//! `mutexbot guard`: hold a reservation for exactly the lifetime of a
+//! wrapped command instead of the caller having to pick one fixed duration
+//! up front.
+//!
+//! Reserves the resource the same way `reserve-exclusive` does, spawns the
+//! command, and renews the reservation in the background (via
+//! `mutexbot_client::run_renewal_loop`) for as long as the command runs.
+//! Whichever happens first - the command exiting, the renewal loop giving
+//! up, or a SIGINT/SIGTERM reaching this process - stops the others and
+//! releases the reservation before `run` returns.
+
+use anyhow::{Context, Result};
+use log::{info, warn};
+use mutexbot_client::{BlockingNotify, LockBackend};
+use tokio::{
+    process::Command,
+    signal::unix::{SignalKind, signal},
+};
+
+/// Reserve `resource_name`, run `command` while renewing the reservation in
+/// the background, release it, and return the exit code to propagate to the
+/// `mutexbot` process itself.
+///
+/// The release always runs once the reservation is taken, even if
+/// `run_command` below returns early on an error (failed spawn, failed
+/// signal-handler install, a `wait()` error) - otherwise a once-off failure
+/// in any of those would leave `resource_name` reserved for the full
+/// `duration` with nothing left running to use it.
+pub(crate) async fn run(
+    backend: &dyn LockBackend,
+    resource_name: &str,
+    isolation_channel: &Option<String>,
+    duration: &str,
+    command: &[String],
+    blocking_notify: Option<&BlockingNotify<'_>>,
+) -> Result<i32> {
+    let notes = format!("guard: {}", command.join(" "));
+
+    mutexbot_client::reserve_exclusive(
+        backend,
+        resource_name,
+        isolation_channel,
+        notes.clone(),
+        Some(duration.to_string()),
+        blocking_notify,
+    )
+    .await
+    .with_context(|| format!("Failed to reserve {resource_name}"))?;
+
+    let result = run_command(backend, resource_name, isolation_channel, duration, command, &notes).await;
+
+    if let Err(err) = mutexbot_client::release(backend, resource_name, isolation_channel, false).await {
+        warn!("Failed to release {} after guard exited: {}", resource_name, err);
+    }
+
+    result
+}
+
+/// Spawn `command` and race it against the renewal loop and shutdown
+/// signals, returning the exit code to propagate. Split out from `run` so
+/// every return path here - including the early `?`s - still goes through
+/// `run`'s release.
+async fn run_command(
+    backend: &dyn LockBackend,
+    resource_name: &str,
+    isolation_channel: &Option<String>,
+    duration: &str,
+    command: &[String],
+    notes: &str,
+) -> Result<i32> {
+    let (program, args) = command
+        .split_first()
+        .context("guard requires a command to run after `--`")?;
+
+    let mut child = Command::new(program)
+        .args(args)
+        .spawn()
+        .with_context(|| format!("Failed to spawn guarded command: {}", command.join(" ")))?;
+
+    // Only SIGTERM needs a handler installed; Ctrl-C/SIGINT already has one
+    // via `tokio::signal::ctrl_c`.
+    let mut sigterm = signal(SignalKind::terminate()).context("Failed to install SIGTERM handler")?;
+
+    let exit_code = tokio::select! {
+        biased;
+
+        status = child.wait() => {
+            status.context("Failed to wait on guarded command")?.code().unwrap_or(1)
+        }
+        renewal_result = mutexbot_client::run_renewal_loop(
+            backend,
+            resource_name,
+            isolation_channel.as_deref(),
+            notes,
+            duration,
+        ) => {
+            // `run_renewal_loop` never returns `Ok` - it only returns at all
+            // once it's given up on the reservation. The command is still
+            // running unprotected at that point, so kill it rather than let
+            // it keep going.
+            warn!(
+                "Reservation renewal for {} failed, terminating guarded command: {}",
+                resource_name,
+                renewal_result.unwrap_err()
+            );
+            let _ = child.kill().await;
+            1
+        }
+        _ = tokio::signal::ctrl_c() => {
+            info!("Received SIGINT, terminating guarded command");
+            let _ = child.kill().await;
+            130
+        }
+        _ = sigterm.recv() => {
+            info!("Received SIGTERM, terminating guarded command");
+            let _ = child.kill().await;
+            143
+        }
+    };
+
+    Ok(exit_code)
+}